@@ -0,0 +1,219 @@
+//! 主机地址校验与解析
+//!
+//! 按 `std::net::IpAddr` 判断地址类别 (回环/私有/链路本地)，
+//! 默认只允许局域网内的IPv4地址，可通过 [`TelemetryConfig`] 配置额外允许的网段和IPv6
+
+use crate::error::{Result, GT7Error};
+use crate::types::TelemetryConfig;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub use ipnet::IpNet;
+
+/// 地址类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressScope {
+    /// 回环地址 (127.0.0.0/8, ::1)
+    Loopback,
+    /// 私有地址 (RFC 1918: 10/8, 172.16/12, 192.168/16; IPv6 ULA: fc00::/7)
+    Private,
+    /// 链路本地地址 (169.254/16, fe80::/10)
+    LinkLocal,
+    /// 公网地址
+    Public,
+    /// 不能作为主机地址 (未指定、广播、组播)
+    Unusable,
+}
+
+impl AddressScope {
+    /// 判断地址类别
+    pub fn of(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => Self::of_v4(ip),
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => Self::of_v4(ip),
+                None => Self::of_v6(ip),
+            },
+        }
+    }
+
+    fn of_v4(ip: Ipv4Addr) -> Self {
+        if ip.is_unspecified() || ip.is_broadcast() || ip.is_multicast() {
+            Self::Unusable
+        } else if ip.is_loopback() {
+            Self::Loopback
+        } else if ip.is_private() {
+            Self::Private
+        } else if ip.is_link_local() {
+            Self::LinkLocal
+        } else {
+            Self::Public
+        }
+    }
+
+    fn of_v6(ip: Ipv6Addr) -> Self {
+        let first = ip.segments()[0];
+        if ip.is_unspecified() || ip.is_multicast() {
+            Self::Unusable
+        } else if ip.is_loopback() {
+            Self::Loopback
+        } else if first & 0xfe00 == 0xfc00 {
+            Self::Private
+        } else if first & 0xffc0 == 0xfe80 {
+            Self::LinkLocal
+        } else {
+            Self::Public
+        }
+    }
+
+    /// 是否是本机或局域网地址
+    pub fn is_local(self) -> bool {
+        matches!(self, Self::Loopback | Self::Private | Self::LinkLocal)
+    }
+}
+
+/// 允许连接的地址范围
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressPolicy {
+    /// 局域网地址之外额外允许的网段
+    pub allowed_networks: Vec<IpNet>,
+    /// 是否允许IPv6地址
+    pub allow_ipv6: bool,
+}
+
+impl AddressPolicy {
+    /// 从客户端配置创建
+    pub fn from_config(config: &TelemetryConfig) -> Self {
+        Self {
+            allowed_networks: config.allowed_networks.clone(),
+            allow_ipv6: config.allow_ipv6,
+        }
+    }
+
+    /// 地址是否允许连接
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        let scope = AddressScope::of(ip);
+        if scope == AddressScope::Unusable || (ip.is_ipv6() && !self.allow_ipv6) {
+            return false;
+        }
+        scope.is_local() || self.allowed_networks.iter().any(|network| network.contains(&ip))
+    }
+
+    /// 解析IP地址或主机名，返回第一个允许连接的地址 (优先IPv4)
+    ///
+    /// 只由数字和点组成却不是合法IPv4的地址 (如 `172.999.1.1`) 直接报错，不做DNS查询
+    pub async fn resolve(&self, host: &str) -> Result<IpAddr> {
+        let host = host.trim();
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = literal.parse::<IpAddr>() {
+            let ip = ip.to_canonical();
+            return if self.is_allowed(ip) {
+                Ok(ip)
+            } else {
+                Err(GT7Error::address_not_allowed(ip.to_string()))
+            };
+        }
+
+        if host.is_empty() || host.bytes().all(|byte| byte.is_ascii_digit() || byte == b'.') {
+            return Err(GT7Error::invalid_ip(host));
+        }
+        let mut resolved: Vec<IpAddr> = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|_| GT7Error::invalid_ip(host))?
+            .map(|address| address.ip().to_canonical())
+            .collect();
+        resolved.sort_by_key(IpAddr::is_ipv6);
+
+        match resolved.iter().find(|&&ip| self.is_allowed(ip)) {
+            Some(&ip) => {
+                log::debug!("解析主机名 {} -> {}", host, ip);
+                Ok(ip)
+            }
+            None => match resolved.first() {
+                Some(ip) => Err(GT7Error::address_not_allowed(format!("{} ({})", host, ip))),
+                None => Err(GT7Error::invalid_ip(host)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_address_scope() {
+        assert_eq!(AddressScope::of(ip("127.0.0.1")), AddressScope::Loopback);
+        assert_eq!(AddressScope::of(ip("10.1.2.3")), AddressScope::Private);
+        assert_eq!(AddressScope::of(ip("172.16.0.1")), AddressScope::Private);
+        assert_eq!(AddressScope::of(ip("172.31.255.254")), AddressScope::Private);
+        assert_eq!(AddressScope::of(ip("172.32.0.1")), AddressScope::Public);
+        assert_eq!(AddressScope::of(ip("192.168.1.30")), AddressScope::Private);
+        assert_eq!(AddressScope::of(ip("169.254.10.1")), AddressScope::LinkLocal);
+        assert_eq!(AddressScope::of(ip("8.8.8.8")), AddressScope::Public);
+        assert_eq!(AddressScope::of(ip("255.255.255.255")), AddressScope::Unusable);
+        assert_eq!(AddressScope::of(ip("224.0.0.1")), AddressScope::Unusable);
+        assert_eq!(AddressScope::of(ip("::1")), AddressScope::Loopback);
+        assert_eq!(AddressScope::of(ip("fd12:3456::1")), AddressScope::Private);
+        assert_eq!(AddressScope::of(ip("fe80::1")), AddressScope::LinkLocal);
+        assert_eq!(AddressScope::of(ip("2001:db8::1")), AddressScope::Public);
+        assert_eq!(AddressScope::of(ip("::ffff:192.168.1.2")), AddressScope::Private);
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let policy = AddressPolicy::default();
+        assert!(policy.is_allowed(ip("192.168.1.30")));
+        assert!(policy.is_allowed(ip("127.0.0.1")));
+        assert!(!policy.is_allowed(ip("172.32.0.1")));
+        assert!(!policy.is_allowed(ip("0.0.0.0")));
+        assert!(!policy.is_allowed(ip("fe80::1")));
+        // IPv4映射的IPv6地址按IPv4处理
+        assert!(policy.is_allowed(ip("::ffff:10.0.0.2")));
+    }
+
+    #[test]
+    fn test_allow_list_and_ipv6() {
+        let policy = AddressPolicy {
+            allowed_networks: vec!["100.64.0.0/10".parse().unwrap(), "2001:db8::/32".parse().unwrap()],
+            allow_ipv6: true,
+        };
+        assert!(policy.is_allowed(ip("100.100.1.1")));
+        assert!(!policy.is_allowed(ip("100.128.0.1")));
+        assert!(policy.is_allowed(ip("2001:db8::1")));
+        assert!(policy.is_allowed(ip("fe80::1")));
+        assert!(!policy.is_allowed(ip("2001:db9::1")));
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let policy = AddressPolicy::default();
+        assert_eq!(policy.resolve("192.168.1.30").await.unwrap(), ip("192.168.1.30"));
+        assert_eq!(policy.resolve("localhost").await.unwrap(), ip("127.0.0.1"));
+        assert!(matches!(policy.resolve("172.999.1.1").await, Err(GT7Error::InvalidIPAddress { .. })));
+        assert!(matches!(policy.resolve("192.168.1").await, Err(GT7Error::InvalidIPAddress { .. })));
+        assert!(matches!(policy.resolve("10.0.0.1.").await, Err(GT7Error::InvalidIPAddress { .. })));
+        assert!(matches!(policy.resolve("8.8.8.8").await, Err(GT7Error::AddressNotAllowed { .. })));
+        assert!(matches!(policy.resolve("[::1]").await, Err(GT7Error::AddressNotAllowed { .. })));
+        assert!(policy.resolve("").await.is_err());
+
+        let policy = AddressPolicy { allow_ipv6: true, ..Default::default() };
+        assert_eq!(policy.resolve("[::1]").await.unwrap(), ip("::1"));
+    }
+
+    #[test]
+    fn test_config_allow_list_serde() {
+        let config = TelemetryConfig {
+            allowed_networks: vec!["10.8.0.0/24".parse().unwrap()],
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("\"10.8.0.0/24\""));
+        let parsed: TelemetryConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(AddressPolicy::from_config(&parsed).allowed_networks, config.allowed_networks);
+    }
+}
@@ -0,0 +1,104 @@
+//! GT7遥测数据导出工具
+//!
+//! 把录制文件 (`.gt7r`) 或JSON Lines数据包文件导出为CSV、JSON Lines、Parquet或MoTeC i2日志
+//!
+//! ```text
+//! gt7-export <输入文件> -o <输出文件> [--format 格式] [--columns 列1,列2] [--split-laps]
+//! gt7-export <输入文件> -o <输出文件.ld> [--driver 车手] [--vehicle 车辆] [--venue 赛道]
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use gt7_telemetry::export::{self, ExportFormat, ExportOptions, Exporter};
+use gt7_telemetry::motec::{self, MotecMetadata};
+
+const USAGE: &str = "用法: gt7-export <输入文件> -o <输出文件> [选项]
+
+输入文件为录制文件 (.gt7r) 或JSON Lines数据包文件
+
+选项:
+  -o, --output <路径>   输出文件 (按圈拆分时作为文件名前缀)
+  --format <格式>       csv, jsonl, parquet 或 motec (默认按输出文件扩展名识别，.ld为motec)
+  --columns <列名>      逗号分隔的导出列 (默认全部)
+  --split-laps          每圈写一个文件
+  --driver <车手>       MoTeC日志的车手名
+  --vehicle <车辆>      MoTeC日志的车辆名 (默认为GT7车辆代码)
+  --venue <赛道>        MoTeC日志的赛道名
+  --list-columns        列出所有可导出的列
+  --help                显示帮助";
+
+fn next_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next().ok_or_else(|| anyhow!("{} 需要一个参数", flag))
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut input = None;
+    let mut output = None;
+    let mut format = None;
+    let mut motec_output = false;
+    let mut options = ExportOptions::default();
+    let mut metadata = MotecMetadata::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(next_value(&mut args, "--output")?),
+            "--format" => match next_value(&mut args, "--format")?.as_str() {
+                "motec" | "ld" => motec_output = true,
+                other => format = Some(other.parse::<ExportFormat>()?),
+            },
+            "--columns" => {
+                options.columns = next_value(&mut args, "--columns")?
+                    .split(',')
+                    .filter(|name| !name.trim().is_empty())
+                    .map(|name| name.trim().to_string())
+                    .collect()
+            }
+            "--split-laps" => options.split_laps = true,
+            "--driver" => metadata.driver = next_value(&mut args, "--driver")?,
+            "--vehicle" => metadata.vehicle = next_value(&mut args, "--vehicle")?,
+            "--venue" => metadata.venue = next_value(&mut args, "--venue")?,
+            "--list-columns" => {
+                for column in export::all_columns() {
+                    println!("{:<28} {:?}", column.name, column.kind);
+                }
+                return Ok(());
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            other if other.starts_with('-') => bail!("未知参数: {}\n\n{}", other, USAGE),
+            other if input.is_none() => input = Some(other.to_string()),
+            other => bail!("多余的参数: {}\n\n{}", other, USAGE),
+        }
+    }
+
+    let input = input.ok_or_else(|| anyhow!("缺少输入文件\n\n{}", USAGE))?;
+    let output = output.ok_or_else(|| anyhow!("缺少输出文件\n\n{}", USAGE))?;
+
+    if motec_output || (format.is_none() && output.ends_with(".ld")) {
+        let (ld, ldx) = motec::export_session(&input, &output, metadata).context("导出MoTeC日志失败")?;
+        println!("导出MoTeC日志:\n  {}\n  {}", ld.display(), ldx.display());
+        return Ok(());
+    }
+
+    options.format = match format.or_else(|| ExportFormat::from_path(&output)) {
+        Some(format) => format,
+        None => bail!("无法从 {} 识别导出格式，请使用 --format 指定", output),
+    };
+
+    let packets = export::read_packets(&input).with_context(|| format!("读取 {} 失败", input))?;
+    let mut exporter = Exporter::create(&output, options).context("创建导出文件失败")?;
+    for packet in &packets {
+        exporter.write(packet).context("写入导出文件失败")?;
+    }
+    let paths = exporter.finish().context("写入导出文件失败")?;
+
+    println!("导出 {} 个数据包到 {} 个文件", packets.len(), paths.len());
+    for path in paths {
+        println!("  {}", path.display());
+    }
+    Ok(())
+}
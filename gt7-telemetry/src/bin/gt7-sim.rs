@@ -0,0 +1,76 @@
+//! GT7主机模拟器
+//!
+//! 在本机模拟PS4/PS5上的GT7遥测发送，无需主机即可开发和测试
+//!
+//! ```text
+//! gt7-sim [--bind 地址] [--reply-port 端口 | --reply-to-source]
+//!         [--rate 频率] [--timeout 秒] [--laps 圈数] [--file 录制文件]
+//! ```
+
+use anyhow::{anyhow, bail, Context, Result};
+use gt7_telemetry::simulator::{
+    ConsoleSimulator, PacketSource, RecordedPackets, ScriptedLap, SimulatorConfig,
+};
+use std::time::Duration;
+
+const USAGE: &str = "用法: gt7-sim [选项]
+
+选项:
+  --bind <地址>         监听心跳的地址 (默认 0.0.0.0:33739)
+  --reply-port <端口>   数据包发送到心跳来源IP的端口 (默认 33740)
+  --reply-to-source     直接回复心跳的来源端口
+  --rate <频率>         发送频率Hz (默认 60)
+  --timeout <秒>        心跳超时时间 (默认 5)
+  --laps <圈数>         脚本化比赛总圈数 (默认 0，计时赛)
+  --file <路径>         循环回放JSON Lines录制文件，替代脚本化圈速
+  --help                显示帮助";
+
+fn next_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next().ok_or_else(|| anyhow!("{} 需要一个参数", flag))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut config = SimulatorConfig::default();
+    let mut laps = 0u16;
+    let mut file = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" => {
+                config.bind_address = next_value(&mut args, "--bind")?.parse().context("无效的监听地址")?
+            }
+            "--reply-port" => {
+                config.reply_port = Some(next_value(&mut args, "--reply-port")?.parse().context("无效的端口")?)
+            }
+            "--reply-to-source" => config.reply_port = None,
+            "--rate" => config.packet_rate = next_value(&mut args, "--rate")?.parse().context("无效的频率")?,
+            "--timeout" => {
+                let secs: f64 = next_value(&mut args, "--timeout")?.parse().context("无效的超时时间")?;
+                config.heartbeat_timeout = Duration::try_from_secs_f64(secs)
+                    .map_err(|_| anyhow!("无效的超时时间: {}\n\n{}", secs, USAGE))?;
+            }
+            "--laps" => laps = next_value(&mut args, "--laps")?.parse().context("无效的圈数")?,
+            "--file" => file = Some(next_value(&mut args, "--file")?),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            other => bail!("未知参数: {}\n\n{}", other, USAGE),
+        }
+    }
+
+    let source: Box<dyn PacketSource> = match file {
+        Some(path) => Box::new(
+            RecordedPackets::from_json_lines(&path).with_context(|| format!("加载录制文件 {} 失败", path))?,
+        ),
+        None => Box::new(ScriptedLap::new(laps)),
+    };
+
+    let simulator = ConsoleSimulator::bind(config, source).await.context("启动模拟器失败")?;
+    simulator.run().await.context("模拟器运行出错")?;
+    Ok(())
+}
@@ -0,0 +1,360 @@
+//! GT7遥测数据包构建器
+//!
+//! 用于测试和模拟器生成合法的遥测数据包
+
+use crate::crypto::GT7_PACKET_MAGIC;
+use crate::packet::{offsets, GT7TelemetryPacket};
+use crate::types::*;
+
+/// 遥测数据包构建器
+///
+/// 默认生成车辆在赛道上、已挂档的"A"格式数据包
+///
+/// # 示例
+///
+/// ```
+/// use gt7_telemetry::PacketBuilder;
+///
+/// let bytes = PacketBuilder::new()
+///     .packet_id(1)
+///     .speed_kmh(180.0)
+///     .lap(2, 5)
+///     .gear(4)
+///     .build_bytes();
+///
+/// let packet = gt7_telemetry::GT7TelemetryPacket::from_bytes(&bytes).unwrap();
+/// assert_eq!(packet.get_gear_display(), "4");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PacketBuilder {
+    packet: GT7TelemetryPacket,
+}
+
+impl Default for PacketBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PacketBuilder {
+    /// 创建新的构建器
+    pub fn new() -> Self {
+        let mut data = vec![0u8; PacketVariant::A.packet_size()];
+        data[0..4].copy_from_slice(&GT7_PACKET_MAGIC.to_le_bytes());
+        let flags = CarFlags::CAR_ON_TRACK | CarFlags::IN_GEAR;
+        let offset = offsets::FLAGS as usize;
+        data[offset..offset + 2].copy_from_slice(&flags.to_le_bytes());
+
+        let mut packet = GT7TelemetryPacket::from_decrypted_bytes(&data)
+            .expect("构建器的初始数据包总是合法的");
+        packet.car_info.engine.suggested_gear = 15;
+
+        Self { packet }
+    }
+
+    /// 设置数据包格式
+    pub fn variant(mut self, variant: PacketVariant) -> Self {
+        self.packet.variant = variant;
+        if variant != PacketVariant::A && self.packet.motion.is_none() {
+            self.packet.motion = Some(MotionData {
+                wheel_rotation: 0.0,
+                sway: 0.0,
+                heave: 0.0,
+                surge: 0.0,
+            });
+        }
+        if variant == PacketVariant::Tilde && self.packet.inputs.is_none() {
+            self.packet.inputs = Some(InputData {
+                throttle_filtered: 0.0,
+                brake_filtered: 0.0,
+                energy_recovery: 0.0,
+            });
+        }
+        self
+    }
+
+    /// 设置数据包序号
+    pub fn packet_id(mut self, packet_id: u32) -> Self {
+        self.packet.packet_id = packet_id;
+        self
+    }
+
+    /// 设置游戏内时间 (毫秒)
+    pub fn time_of_day(mut self, ms: u32) -> Self {
+        self.packet.timestamp = ms as u64;
+        self
+    }
+
+    /// 设置状态标志位
+    pub fn flags(mut self, flags: CarFlags) -> Self {
+        self.packet.game_state.flags = flags;
+        self
+    }
+
+    /// 设置车速 (km/h)
+    pub fn speed_kmh(mut self, kmh: f32) -> Self {
+        self.packet.car_info.speed = kmh / 3.6;
+        self
+    }
+
+    /// 设置世界坐标位置
+    pub fn position(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.packet.car_info.position.world = Vector3::new(x, y, z);
+        self
+    }
+
+    /// 设置速度向量 (m/s)
+    pub fn velocity(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.packet.car_info.position.velocity = Vector3::new(x, y, z);
+        self
+    }
+
+    /// 设置当前圈数和总圈数
+    ///
+    /// 比赛信息只在车辆在赛道上时编码，赛道外设置的值在重新设置 `CAR_ON_TRACK` 后生效
+    pub fn lap(mut self, current_lap: u16, total_laps: u16) -> Self {
+        let race = self.race_info_mut();
+        race.current_lap = current_lap;
+        race.total_laps = total_laps;
+        self
+    }
+
+    /// 设置最快圈速和上一圈时间 (毫秒)
+    pub fn lap_times(mut self, best_lap_time: Option<u32>, last_lap_time: Option<u32>) -> Self {
+        let race = self.race_info_mut();
+        race.best_lap_time = best_lap_time;
+        race.last_lap_time = last_lap_time;
+        self
+    }
+
+    /// 设置当前档位 (0=倒档)
+    pub fn gear(mut self, gear: i8) -> Self {
+        self.packet.car_info.engine.gear = gear;
+        self
+    }
+
+    /// 设置建议档位 (15表示无建议)
+    pub fn suggested_gear(mut self, gear: i8) -> Self {
+        self.packet.car_info.engine.suggested_gear = gear;
+        self
+    }
+
+    /// 设置发动机转速
+    pub fn rpm(mut self, rpm: f32) -> Self {
+        self.packet.car_info.engine.rpm = rpm;
+        self
+    }
+
+    /// 设置换挡提示转速区间
+    pub fn rpm_alert(mut self, min_rpm: u16, max_rpm: u16) -> Self {
+        self.packet.car_info.engine.min_alert_rpm = min_rpm as f32;
+        self.packet.car_info.engine.max_rpm = max_rpm as f32;
+        self
+    }
+
+    /// 设置油门 (0.0-1.0)
+    pub fn throttle(mut self, throttle: f32) -> Self {
+        self.packet.car_info.engine.throttle = throttle;
+        self
+    }
+
+    /// 设置刹车 (0.0-1.0)
+    pub fn brake(mut self, brake: f32) -> Self {
+        self.packet.car_info.engine.brake = brake;
+        self
+    }
+
+    /// 设置涡轮增压 (bar)
+    pub fn boost(mut self, boost: f32) -> Self {
+        self.packet.car_info.engine.boost = boost;
+        self
+    }
+
+    /// 设置剩余燃油和油箱容量 (升)
+    pub fn fuel(mut self, remaining: f32, capacity: f32) -> Self {
+        self.packet.car_info.engine.fuel_remaining = remaining;
+        self.packet.car_info.engine.fuel_capacity = capacity;
+        self
+    }
+
+    /// 设置胎面温度 (FL/FR/RL/RR，摄氏度)
+    pub fn tyre_temperatures(mut self, temperatures: [f32; 4]) -> Self {
+        let tires = &mut self.packet.car_info.tires;
+        tires.front_left.temperature = temperatures[0];
+        tires.front_right.temperature = temperatures[1];
+        tires.rear_left.temperature = temperatures[2];
+        tires.rear_right.temperature = temperatures[3];
+        self
+    }
+
+    /// 设置车辆代码
+    pub fn car_code(mut self, car_code: u32) -> Self {
+        self.packet.car_info.car_code = car_code;
+        self
+    }
+
+    /// 设置附加运动数据 (仅"B"及以上格式编码)
+    pub fn motion(mut self, motion: MotionData) -> Self {
+        self.packet.motion = Some(motion);
+        self
+    }
+
+    /// 构建数据包
+    ///
+    /// 结果经过一次编码/解码，与从网络接收到的数据包完全一致
+    pub fn build(&self) -> GT7TelemetryPacket {
+        GT7TelemetryPacket::from_decrypted_bytes(&self.packet.encode())
+            .expect("编码后的数据包总是合法的")
+    }
+
+    /// 构建加密后的网络字节数据 (使用数据包序号作为IV种子)
+    pub fn build_bytes(&self) -> Vec<u8> {
+        self.build().to_bytes()
+    }
+
+    /// 比赛信息，缺失时创建空的比赛信息
+    fn race_info_mut(&mut self) -> &mut RaceInfo {
+        self.packet.game_state.race_info.get_or_insert(RaceInfo {
+            current_lap: 0,
+            total_laps: 0,
+            position: 0,
+            total_participants: 0,
+            best_lap_time: None,
+            last_lap_time: None,
+            current_lap_time: 0,
+            track_progress: 0.0,
+        })
+    }
+}
+
+/// 测试用的连续数据包生成器，数据包序号跨圈递增 (60帧为一秒)
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct LapDriver {
+    packet_id: u32,
+}
+
+#[cfg(test)]
+impl LapDriver {
+    /// 第 `lap` 圈 (共 `total_laps` 圈) 的 `frames` 个连续数据包，`customize` 按帧序号调整每个数据包
+    pub(crate) fn lap(
+        &mut self,
+        lap: u16,
+        total_laps: u16,
+        frames: u32,
+        mut customize: impl FnMut(PacketBuilder, u32) -> PacketBuilder,
+    ) -> Vec<GT7TelemetryPacket> {
+        (0..frames)
+            .map(|frame| {
+                self.packet_id += 1;
+                customize(PacketBuilder::new().packet_id(self.packet_id).lap(lap, total_laps), frame).build()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let packet = PacketBuilder::new().build();
+        assert!(packet.is_in_race());
+        assert!(packet.game_state.flags.in_gear);
+        assert_eq!(packet.variant, PacketVariant::A);
+        assert_eq!(packet.game_state.race_info.unwrap().best_lap_time, None);
+    }
+
+    #[test]
+    fn test_builder_values() {
+        let packet = PacketBuilder::new()
+            .speed_kmh(216.0)
+            .position(100.0, 5.0, -250.0)
+            .lap(3, 10)
+            .gear(5)
+            .throttle(1.0)
+            .fuel(45.0, 90.0)
+            .build();
+
+        assert!((packet.get_speed_kmh() - 216.0).abs() < 1e-3);
+        assert_eq!(packet.car_info.position.world, Vector3::new(100.0, 5.0, -250.0));
+        assert_eq!(packet.game_state.race_info.as_ref().unwrap().current_lap, 3);
+        assert_eq!(packet.car_info.engine.gear, 5);
+        assert_eq!(packet.car_info.engine.fuel_level, 0.5);
+    }
+
+    #[test]
+    fn test_builder_off_track_drops_race_info() {
+        let packet = PacketBuilder::new().flags(CarFlags::default()).lap(2, 3).build();
+        assert!(packet.is_in_menu());
+        assert!(packet.game_state.race_info.is_none());
+    }
+
+    #[test]
+    fn test_builder_lap_without_race_info() {
+        let mut builder = PacketBuilder::new();
+        builder.packet.game_state.race_info = None;
+        let packet = builder.lap(3, 5).lap_times(Some(90_000), Some(91_000)).build();
+        let race = packet.game_state.race_info.unwrap();
+        assert_eq!((race.current_lap, race.total_laps), (3, 5));
+        assert_eq!((race.best_lap_time, race.last_lap_time), (Some(90_000), Some(91_000)));
+
+        // 赛道外设置的圈数在回到赛道后保留
+        let packet = PacketBuilder::new()
+            .flags(CarFlags::default())
+            .lap(3, 5)
+            .flags(CarFlags::from(CarFlags::CAR_ON_TRACK | CarFlags::IN_GEAR))
+            .build();
+        assert_eq!(packet.game_state.race_info.unwrap().current_lap, 3);
+    }
+
+    fn variant_strategy() -> impl Strategy<Value = PacketVariant> {
+        prop_oneof![
+            Just(PacketVariant::A),
+            Just(PacketVariant::B),
+            Just(PacketVariant::Tilde),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_round_trip(
+            variant in variant_strategy(),
+            packet_id in any::<u32>(),
+            time_of_day in 0u32..86_400_000,
+            speed in -500.0f32..500.0,
+            (x, y, z) in (-1e5f32..1e5, -1e3f32..1e3, -1e5f32..1e5),
+            (current_lap, total_laps) in (0u16..500, 0u16..500),
+            best_lap_time in proptest::option::of(1u32..600_000),
+            gear in 0i8..=8,
+            throttle in any::<u8>(),
+            boost in (-256i32..768).prop_map(|v| v as f32 / 256.0),
+            fuel in 0.0f32..100.0,
+            temps in proptest::array::uniform4(-20.0f32..150.0),
+            wheel_rotation in -3.2f32..3.2,
+            seed in any::<u32>(),
+        ) {
+            let packet = PacketBuilder::new()
+                .variant(variant)
+                .packet_id(packet_id)
+                .time_of_day(time_of_day)
+                .speed_kmh(speed)
+                .position(x, y, z)
+                .lap(current_lap, total_laps)
+                .lap_times(best_lap_time, None)
+                .gear(gear)
+                .throttle(throttle as f32 / 255.0)
+                .boost(boost)
+                .fuel(fuel, 100.0)
+                .tyre_temperatures(temps)
+                .motion(MotionData { wheel_rotation, sway: 0.1, heave: -0.2, surge: 0.3 })
+                .build();
+
+            prop_assert_eq!(GT7TelemetryPacket::from_bytes(&packet.to_bytes()).unwrap(), packet.clone());
+            prop_assert_eq!(GT7TelemetryPacket::from_bytes(&packet.to_bytes_with_seed(seed)).unwrap(), packet.clone());
+            prop_assert_eq!(GT7TelemetryPacket::from_decrypted_bytes(&packet.encode()).unwrap(), packet);
+        }
+    }
+}
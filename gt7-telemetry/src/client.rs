@@ -2,15 +2,120 @@
 //! 
 //! 参考gt7telemetry Python库实现UDP客户端和多IP支持
 
+use crate::address::AddressPolicy;
 use crate::error::{Result, GT7Error};
+use crate::jitter::{JitterBuffer, Release};
 use crate::packet::GT7TelemetryPacket;
-use crate::types::TelemetryConfig;
+use crate::recorder::{SessionRecorder, DEFAULT_RECORDING_DIR};
+use crate::stats::{ConnectionStats, StatsTracker};
+use crate::types::{JitterBufferConfig, PacketVariant, TelemetryConfig};
 use std::collections::HashMap;
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::sync::broadcast;
+use tokio::net::UdpSocket;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinSet;
 use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
+
+/// 尚未发送过心跳的时间戳标记
+const NEVER: u64 = u64::MAX;
+
+/// 共享套接字转发给单个连接的数据报队列长度
+const INBOX_CAPACITY: usize = 256;
+
+/// 共享套接字收到的数据报
+type Datagram = (Vec<u8>, SocketAddr);
+
+/// 连接表: IP -> 连接共享状态
+type ConnectionMap = Arc<Mutex<HashMap<String, Arc<ClientConnection>>>>;
+
+/// 连接生命周期事件
+/// 
+/// 通过 [`GT7TelemetryClient::subscribe_events`] 订阅，订阅前发生的事件不会补发
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// 添加了连接
+    Added {
+        /// 设备IP
+        ip: String,
+        /// 心跳目标地址
+        address: SocketAddr,
+    },
+    /// 收到该连接的第一个有效数据包
+    FirstPacket {
+        /// 设备IP
+        ip: String,
+        /// 数据包格式
+        variant: PacketVariant,
+    },
+    /// 超过配置的超时时间未收到数据包
+    TimedOut {
+        /// 设备IP
+        ip: String,
+        /// 距最后一个数据包的时间
+        silence: Duration,
+    },
+    /// 超时后重新收到数据包
+    Recovered {
+        /// 设备IP
+        ip: String,
+        /// 中断时长
+        downtime: Duration,
+    },
+    /// 连接被移除 (包括被同IP的新连接替换)
+    Removed {
+        /// 设备IP
+        ip: String,
+        /// 累计接收的数据包数
+        packet_count: u64,
+    },
+    /// 发送心跳失败
+    HeartbeatFailed {
+        /// 设备IP
+        ip: String,
+        /// 错误信息
+        error: String,
+        /// 连续失败次数
+        consecutive_failures: u64,
+    },
+    /// 启用抖动缓冲时，数据包序号不连续 (缺失的数据包不会再输出)
+    SequenceGap {
+        /// 设备IP
+        ip: String,
+        /// 期望的下一个序号
+        expected: u32,
+        /// 实际继续输出的序号
+        resumed: u32,
+    },
+    /// 收到无法解析或校验失败的数据
+    DecodeError {
+        /// 设备IP
+        ip: String,
+        /// 错误信息
+        error: String,
+        /// 该连接累计解析失败次数
+        total_errors: u64,
+    },
+}
+
+impl ConnectionEvent {
+    /// 事件对应的设备IP
+    pub fn ip(&self) -> &str {
+        match self {
+            Self::Added { ip, .. }
+            | Self::FirstPacket { ip, .. }
+            | Self::TimedOut { ip, .. }
+            | Self::Recovered { ip, .. }
+            | Self::Removed { ip, .. }
+            | Self::HeartbeatFailed { ip, .. }
+            | Self::SequenceGap { ip, .. }
+            | Self::DecodeError { ip, .. } => ip,
+        }
+    }
+}
 
 /// GT7遥测客户端
 /// 
@@ -19,28 +124,156 @@ pub struct GT7TelemetryClient {
     /// 客户端配置
     config: TelemetryConfig,
     /// 活动连接管理
-    connections: Arc<Mutex<HashMap<String, ClientConnection>>>,
+    connections: ConnectionMap,
     /// 数据包广播发送器
     packet_sender: broadcast::Sender<(String, GT7TelemetryPacket)>,
-    /// 客户端状态
-    is_running: Arc<Mutex<bool>>,
+    /// 连接事件广播发送器
+    event_sender: broadcast::Sender<ConnectionEvent>,
+    /// 共享套接字 (按地址族各一个，配置了 `shared_socket_port` 时按需创建)
+    shared_sockets: Mutex<Vec<Arc<UdpSocket>>>,
+    /// 运行中的后台任务 (未运行时为 `None`)
+    runtime: Mutex<Option<ClientRuntime>>,
 }
 
-/// 单个客户端连接信息
-#[derive(Debug, Clone)]
+/// 客户端一次运行期间的后台任务
+struct ClientRuntime {
+    /// 停止信号
+    shutdown: CancellationToken,
+    /// 所有后台任务句柄
+    tasks: JoinSet<()>,
+    /// 本次运行的会话录制 (启用 `enable_logging` 时)
+    recorder: Option<Arc<SessionRecorder>>,
+}
+
+/// 单个客户端连接的共享状态
+/// 
+/// 每个连接只有一份状态，各任务持有同一个 `Arc` 并通过原子操作更新，
+/// 不会互相覆盖；连接被移除后标记为已移除，旧任务不会影响同IP的新连接
+#[derive(Debug)]
 struct ClientConnection {
     /// 目标地址
     address: SocketAddr,
-    /// UDP套接字
+    /// UDP套接字 (共享模式下为共享套接字，只用于发送心跳)
     socket: Arc<UdpSocket>,
+    /// 共享模式下由分发任务转发来的数据报
+    inbox: Option<(mpsc::Sender<Datagram>, tokio::sync::Mutex<mpsc::Receiver<Datagram>>)>,
+    /// 时间基准 (原子时间戳均为相对该时刻的微秒数)
+    epoch: Instant,
     /// 最后接收数据包时间
-    last_received: Instant,
+    last_received: AtomicU64,
     /// 最后发送心跳时间
-    last_heartbeat: Instant,
+    last_heartbeat: AtomicU64,
     /// 连接状态
-    is_connected: bool,
+    is_connected: AtomicBool,
     /// 接收到的数据包计数
-    packet_count: u64,
+    packet_count: AtomicU64,
+    /// 解析失败计数
+    decode_errors: AtomicU64,
+    /// 连续心跳发送失败次数
+    heartbeat_failures: AtomicU64,
+    /// 序号和到达时间统计 (仅接收任务和心跳任务更新)
+    stats: Mutex<StatsTracker>,
+    /// 连接被移除时取消
+    removed: CancellationToken,
+}
+
+impl ClientConnection {
+    fn new(address: SocketAddr, socket: Arc<UdpSocket>) -> Self {
+        Self::with_inbox(address, socket, None)
+    }
+
+    /// 使用共享套接字的连接
+    fn shared(address: SocketAddr, socket: Arc<UdpSocket>) -> Self {
+        let (sender, receiver) = mpsc::channel(INBOX_CAPACITY);
+        Self::with_inbox(address, socket, Some((sender, tokio::sync::Mutex::new(receiver))))
+    }
+
+    fn with_inbox(
+        address: SocketAddr,
+        socket: Arc<UdpSocket>,
+        inbox: Option<(mpsc::Sender<Datagram>, tokio::sync::Mutex<mpsc::Receiver<Datagram>>)>,
+    ) -> Self {
+        Self {
+            address,
+            socket,
+            inbox,
+            epoch: Instant::now(),
+            last_received: AtomicU64::new(0),
+            last_heartbeat: AtomicU64::new(NEVER),
+            is_connected: AtomicBool::new(false),
+            packet_count: AtomicU64::new(0),
+            decode_errors: AtomicU64::new(0),
+            heartbeat_failures: AtomicU64::new(0),
+            stats: Mutex::new(StatsTracker::default()),
+            removed: CancellationToken::new(),
+        }
+    }
+
+    /// 当前时刻相对时间基准的微秒数
+    fn now_micros(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+
+    /// 记录收到一个有效数据包，返回累计数据包数和此前是否处于连接状态
+    fn record_packet(&self) -> (u64, bool) {
+        self.last_received.store(self.now_micros(), Ordering::Release);
+        let was_connected = self.is_connected.swap(true, Ordering::AcqRel);
+        (self.packet_count.fetch_add(1, Ordering::AcqRel) + 1, was_connected)
+    }
+
+    /// 记录一次解析失败，返回累计失败次数
+    fn record_decode_error(&self) -> u64 {
+        self.decode_errors.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// 记录已发送心跳，`sent_at` 为发送前取得的 [`now_micros`](Self::now_micros)
+    fn record_heartbeat(&self, sent_at: u64) {
+        self.last_heartbeat.store(sent_at, Ordering::Release);
+        self.heartbeat_failures.store(0, Ordering::Release);
+    }
+
+    /// 记录一次心跳发送失败，返回连续失败次数
+    fn record_heartbeat_failure(&self) -> u64 {
+        self.heartbeat_failures.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// 距最后接收数据包的时间
+    fn since_last_received(&self) -> Duration {
+        let last = self.last_received.load(Ordering::Acquire);
+        Duration::from_micros(self.now_micros().saturating_sub(last))
+    }
+
+    /// 距最后发送心跳的时间 (从未发送时为 `Duration::MAX`，立即发送首个心跳)
+    fn since_last_heartbeat(&self) -> Duration {
+        let last = self.last_heartbeat.load(Ordering::Acquire);
+        if last == NEVER {
+            return Duration::MAX;
+        }
+        Duration::from_micros(self.now_micros().saturating_sub(last))
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected.load(Ordering::Acquire)
+    }
+
+    fn packet_count(&self) -> u64 {
+        self.packet_count.load(Ordering::Acquire)
+    }
+
+    /// 生成统计快照
+    fn stats(&self) -> ConnectionStats {
+        self.stats.lock().unwrap().snapshot(
+            self.address,
+            self.is_connected(),
+            self.packet_count(),
+            self.decode_errors.load(Ordering::Acquire),
+        )
+    }
+
+    /// 标记连接已移除，其接收任务随之退出
+    fn mark_removed(&self) {
+        self.removed.cancel();
+    }
 }
 
 impl GT7TelemetryClient {
@@ -55,55 +288,81 @@ impl GT7TelemetryClient {
     /// 新的客户端实例和数据包接收器
     pub fn new(config: TelemetryConfig) -> Result<(Self, broadcast::Receiver<(String, GT7TelemetryPacket)>)> {
         let (packet_sender, packet_receiver) = broadcast::channel(1000);
+        let (event_sender, _) = broadcast::channel(256);
         
         let client = Self {
             config,
             connections: Arc::new(Mutex::new(HashMap::new())),
             packet_sender,
-            is_running: Arc::new(Mutex::new(false)),
+            event_sender,
+            shared_sockets: Mutex::new(Vec::new()),
+            runtime: Mutex::new(None),
         };
 
         Ok((client, packet_receiver))
     }
 
+    /// 订阅连接生命周期事件
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.event_sender.subscribe()
+    }
+
     /// 添加GT7设备连接
     /// 
+    /// 客户端运行中添加的连接会立即启动自己的接收任务
+    /// 
     /// # 参数
     /// 
-    /// * `ip` - GT7设备IP地址
+    /// * `ip` - GT7设备IP地址或主机名 (必须在配置允许的地址范围内)
     /// * `port` - 可选端口（默认使用配置中的端口）
     pub async fn add_connection(&self, ip: String, port: Option<u16>) -> Result<()> {
-        // 验证IP地址格式
-        if !crate::is_valid_gt7_ip(&ip) {
-            return Err(GT7Error::invalid_ip(ip));
-        }
-
         let port = port.unwrap_or(self.config.port);
         if port == 0 {
             return Err(GT7Error::invalid_port(port));
         }
 
-        let address: SocketAddr = format!("{}:{}", ip, port).parse()
-            .map_err(|_| GT7Error::invalid_ip(&ip))?;
+        // 验证IP地址 (主机名解析为允许的地址)
+        let resolved = AddressPolicy::from_config(&self.config).resolve(&ip).await?;
+        let address = SocketAddr::new(resolved, port);
 
-        // 创建UDP套接字
-        let socket = UdpSocket::bind("0.0.0.0:0")
-            .map_err(|e| GT7Error::network_error(&address.to_string(), e.to_string()))?;
+        let connection = match self.config.shared_socket_port {
+            Some(shared_port) => {
+                let socket = self.shared_socket(resolved.is_ipv6(), shared_port)?;
+                Arc::new(ClientConnection::shared(address, socket))
+            }
+            None => {
+                // 创建UDP套接字
+                let local: SocketAddr = if resolved.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }.parse().unwrap();
+                let socket = UdpSocket::bind(local).await
+                    .map_err(|e| GT7Error::network_error(address.to_string(), e.to_string()))?;
+                Arc::new(ClientConnection::new(address, Arc::new(socket)))
+            }
+        };
 
-        socket.set_read_timeout(Some(Duration::from_millis(100)))
-            .map_err(|e| GT7Error::network_error(&address.to_string(), e.to_string()))?;
+        // 与 `start` 相同的加锁顺序: 插入连接和启动接收任务期间持有运行状态锁，
+        // 否则并发的 `start` 可能为同一连接再启动一个接收任务
+        let mut runtime = self.runtime.lock().unwrap();
 
-        let connection = ClientConnection {
-            address,
-            socket: Arc::new(socket),
-            last_received: Instant::now(),
-            last_heartbeat: Instant::now(),
-            is_connected: false,
-            packet_count: 0,
+        // 替换同IP的旧连接时，旧连接的任务会随之退出
+        let replaced = {
+            let mut connections = self.connections.lock().unwrap();
+            connections.insert(ip.clone(), Arc::clone(&connection))
         };
+        if let Some(old) = replaced {
+            old.mark_removed();
+            let _ = self.event_sender.send(ConnectionEvent::Removed {
+                ip: ip.clone(),
+                packet_count: old.packet_count(),
+            });
+        }
+        let _ = self.event_sender.send(ConnectionEvent::Added { ip: ip.clone(), address });
 
-        let mut connections = self.connections.lock().unwrap();
-        connections.insert(ip.clone(), connection);
+        if let Some(runtime) = runtime.as_mut() {
+            // 顺便回收已结束的任务 (如已移除连接的接收任务)
+            while runtime.tasks.try_join_next().is_some() {}
+            self.spawn_receiver(runtime, ip.clone(), connection);
+        }
+        drop(runtime);
         
         log::info!("添加GT7连接: {} -> {}", ip, address);
         Ok(())
@@ -115,9 +374,14 @@ impl GT7TelemetryClient {
     /// 
     /// * `ip` - 要移除的GT7设备IP地址
     pub async fn remove_connection(&self, ip: &str) -> Result<()> {
-        let mut connections = self.connections.lock().unwrap();
-        if let Some(_) = connections.remove(ip) {
-            log::info!("移除GT7连接: {}", ip);
+        let removed = self.connections.lock().unwrap().remove(ip);
+        if let Some(connection) = removed {
+            connection.mark_removed();
+            log::info!("移除GT7连接: {} (共接收 {} 个数据包)", ip, connection.packet_count());
+            let _ = self.event_sender.send(ConnectionEvent::Removed {
+                ip: ip.to_string(),
+                packet_count: connection.packet_count(),
+            });
             Ok(())
         } else {
             Err(GT7Error::network_error(ip, "连接不存在".to_string()))
@@ -128,166 +392,393 @@ impl GT7TelemetryClient {
     pub fn get_connection_status(&self) -> HashMap<String, bool> {
         let connections = self.connections.lock().unwrap();
         connections.iter()
-            .map(|(ip, conn)| (ip.clone(), conn.is_connected))
+            .map(|(ip, conn)| (ip.clone(), conn.is_connected()))
+            .collect()
+    }
+
+    /// 客户端是否正在运行
+    pub fn is_running(&self) -> bool {
+        self.runtime.lock().unwrap().is_some()
+    }
+
+    /// 获取单个连接的统计信息
+    pub fn connection_stats(&self, ip: &str) -> Option<ConnectionStats> {
+        let connection = self.connections.lock().unwrap().get(ip).cloned();
+        connection.map(|connection| connection.stats())
+    }
+
+    /// 获取所有连接的统计信息
+    pub fn all_connection_stats(&self) -> HashMap<String, ConnectionStats> {
+        Self::snapshot(&self.connections)
+            .into_iter()
+            .map(|(ip, connection)| (ip, connection.stats()))
             .collect()
     }
 
     /// 启动客户端
     /// 
-    /// 开始监听所有连接的数据包和发送心跳
+    /// 为每个连接启动独立的接收任务，并开始发送心跳。
+    /// 停止后可以再次启动
     pub async fn start(&self) -> Result<()> {
-        {
-            let mut is_running = self.is_running.lock().unwrap();
-            if *is_running {
-                return Err(GT7Error::config_error(
-                    "client_state", 
-                    "running", 
-                    "客户端已在运行"
-                ));
-            }
-            *is_running = true;
+        let mut guard = self.runtime.lock().unwrap();
+        if guard.is_some() {
+            return Err(GT7Error::config_error(
+                "client_state", 
+                "running", 
+                "客户端已在运行"
+            ));
         }
 
         log::info!("启动GT7遥测客户端...");
 
-        // 启动数据包接收任务
-        let connections_clone = Arc::clone(&self.connections);
-        let packet_sender_clone = self.packet_sender.clone();
-        let is_running_clone = Arc::clone(&self.is_running);
-        
-        tokio::spawn(async move {
-            Self::packet_receiver_task(connections_clone, packet_sender_clone, is_running_clone).await;
-        });
+        let recorder = if self.config.enable_logging {
+            let dir = self.config.log_file_path.as_deref().unwrap_or(DEFAULT_RECORDING_DIR);
+            Some(Arc::new(SessionRecorder::create_in(dir, self.config.packet_variant)?))
+        } else {
+            None
+        };
+
+        let mut runtime = ClientRuntime {
+            shutdown: CancellationToken::new(),
+            tasks: JoinSet::new(),
+            recorder,
+        };
+
+        // 为每个连接启动数据包接收任务
+        for (ip, connection) in Self::snapshot(&self.connections) {
+            self.spawn_receiver(&mut runtime, ip, connection);
+        }
+        for socket in self.shared_sockets.lock().unwrap().iter() {
+            Self::spawn_dispatcher(&mut runtime, Arc::clone(socket), Arc::clone(&self.connections));
+        }
 
         // 启动心跳发送任务
-        let connections_clone = Arc::clone(&self.connections);
-        let heartbeat_interval = self.config.heartbeat_interval;
-        let is_running_clone = Arc::clone(&self.is_running);
-        
-        tokio::spawn(async move {
-            Self::heartbeat_sender_task(connections_clone, heartbeat_interval, is_running_clone).await;
-        });
+        runtime.tasks.spawn(Self::heartbeat_sender_task(
+            Arc::clone(&self.connections),
+            self.config.heartbeat_interval,
+            self.config.packet_variant,
+            self.event_sender.clone(),
+            runtime.shutdown.clone(),
+        ));
 
         // 启动连接监控任务
-        let connections_clone = Arc::clone(&self.connections);
-        let timeout_duration = Duration::from_secs(self.config.timeout);
-        let is_running_clone = Arc::clone(&self.is_running);
-        
-        tokio::spawn(async move {
-            Self::connection_monitor_task(connections_clone, timeout_duration, is_running_clone).await;
-        });
+        runtime.tasks.spawn(Self::connection_monitor_task(
+            Arc::clone(&self.connections),
+            Duration::from_secs(self.config.timeout),
+            self.event_sender.clone(),
+            runtime.shutdown.clone(),
+        ));
 
+        *guard = Some(runtime);
         Ok(())
     }
 
     /// 停止客户端
+    /// 
+    /// 通知所有后台任务退出并等待它们结束。未运行时不做任何事
     pub async fn stop(&self) {
-        {
-            let mut is_running = self.is_running.lock().unwrap();
-            *is_running = false;
+        let runtime = self.runtime.lock().unwrap().take();
+        let Some(mut runtime) = runtime else {
+            return;
+        };
+
+        runtime.shutdown.cancel();
+        while let Some(result) = runtime.tasks.join_next().await {
+            if let Err(e) = result {
+                log::warn!("后台任务异常退出: {}", e);
+            }
+        }
+        if let Some(recorder) = runtime.recorder {
+            if let Err(e) = recorder.finish() {
+                log::warn!("结束录制失败: {}", e);
+            }
         }
         
         log::info!("停止GT7遥测客户端");
     }
 
-    /// 数据包接收任务
+    /// 获取 (必要时创建) 指定地址族的共享套接字
+    fn shared_socket(&self, ipv6: bool, port: u16) -> Result<Arc<UdpSocket>> {
+        // 与 `start` 相同的加锁顺序
+        let mut runtime = self.runtime.lock().unwrap();
+        let mut shared_sockets = self.shared_sockets.lock().unwrap();
+        if let Some(socket) = shared_sockets.iter().find(|socket| {
+            socket.local_addr().is_ok_and(|local| local.is_ipv6() == ipv6)
+        }) {
+            return Ok(Arc::clone(socket));
+        }
+
+        let ip = if ipv6 { IpAddr::V6(Ipv6Addr::UNSPECIFIED) } else { IpAddr::V4(Ipv4Addr::UNSPECIFIED) };
+        let socket = Arc::new(bind_reusable_socket(SocketAddr::new(ip, port))?);
+        log::info!("绑定共享遥测套接字: {}", socket.local_addr()?);
+        shared_sockets.push(Arc::clone(&socket));
+
+        if let Some(runtime) = runtime.as_mut() {
+            Self::spawn_dispatcher(runtime, Arc::clone(&socket), Arc::clone(&self.connections));
+        }
+        Ok(socket)
+    }
+
+    /// 启动共享套接字的分发任务
+    fn spawn_dispatcher(runtime: &mut ClientRuntime, socket: Arc<UdpSocket>, connections: ConnectionMap) {
+        runtime.tasks.spawn(Self::shared_socket_dispatcher_task(socket, connections, runtime.shutdown.clone()));
+    }
+
+    /// 共享套接字分发任务
+    /// 
+    /// 按来源IP把数据报转发给对应连接的接收任务
+    async fn shared_socket_dispatcher_task(
+        socket: Arc<UdpSocket>,
+        connections: ConnectionMap,
+        shutdown: CancellationToken,
+    ) {
+        let mut buffer = [0u8; crate::GT7_MAX_PACKET_SIZE * 2]; // 留点余量
+
+        loop {
+            let (size, from) = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                received = socket.recv_from(&mut buffer) => match received {
+                    Ok(received) => received,
+                    Err(e) => {
+                        log::warn!("共享套接字接收数据时出错: {}", e);
+                        continue;
+                    }
+                },
+            };
+
+            let source = from.ip().to_canonical();
+            let target = connections.lock().unwrap()
+                .values()
+                .find(|conn| conn.address.ip() == source && Arc::ptr_eq(&conn.socket, &socket))
+                .cloned();
+            let Some((inbox, _)) = target.as_ref().and_then(|conn| conn.inbox.as_ref()) else {
+                log::debug!("忽略来自 {} 的非预期数据", from);
+                continue;
+            };
+            if inbox.try_send((buffer[..size].to_vec(), from)).is_err() {
+                log::warn!("{} 的接收队列已满，丢弃数据包", source);
+            }
+        }
+    }
+
+    /// 获取当前所有连接的快照 (不持有锁)
+    fn snapshot(connections: &ConnectionMap) -> Vec<(String, Arc<ClientConnection>)> {
+        connections.lock().unwrap()
+            .iter()
+            .map(|(ip, conn)| (ip.clone(), Arc::clone(conn)))
+            .collect()
+    }
+
+    /// 为单个连接启动接收任务
+    fn spawn_receiver(&self, runtime: &mut ClientRuntime, ip: String, connection: Arc<ClientConnection>) {
+        runtime.tasks.spawn(Self::packet_receiver_task(
+            ip,
+            connection,
+            self.config.jitter_buffer,
+            runtime.recorder.clone(),
+            self.packet_sender.clone(),
+            self.event_sender.clone(),
+            runtime.shutdown.clone(),
+        ));
+    }
+
+    /// 广播抖动缓冲输出的数据包和序号缺口
+    fn deliver(
+        ip: &str,
+        released: Vec<Release>,
+        packet_sender: &broadcast::Sender<(String, GT7TelemetryPacket)>,
+        event_sender: &broadcast::Sender<ConnectionEvent>,
+    ) {
+        for release in released {
+            match release {
+                Release::Packet(packet) => Self::broadcast_packet(ip, packet, packet_sender),
+                Release::Gap(gap) => {
+                    log::debug!("{} 的数据包序号不连续: 期望 {}，继续 {}", ip, gap.expected, gap.resumed);
+                    let _ = event_sender.send(ConnectionEvent::SequenceGap {
+                        ip: ip.to_string(),
+                        expected: gap.expected,
+                        resumed: gap.resumed,
+                    });
+                }
+            }
+        }
+    }
+
+    /// 广播数据包
+    fn broadcast_packet(ip: &str, packet: GT7TelemetryPacket, packet_sender: &broadcast::Sender<(String, GT7TelemetryPacket)>) {
+        if packet_sender.send((ip.to_string(), packet)).is_err() {
+            log::warn!("数据包广播队列已满，跳过数据包");
+        }
+    }
+
+    /// 单个连接的数据包接收任务
+    /// 
+    /// 异步等待数据到达，连接被移除或替换、客户端停止后立即退出
     async fn packet_receiver_task(
-        connections: Arc<Mutex<HashMap<String, ClientConnection>>>,
+        ip: String,
+        connection: Arc<ClientConnection>,
+        jitter_config: Option<JitterBufferConfig>,
+        mut recorder: Option<Arc<SessionRecorder>>,
         packet_sender: broadcast::Sender<(String, GT7TelemetryPacket)>,
-        is_running: Arc<Mutex<bool>>,
+        event_sender: broadcast::Sender<ConnectionEvent>,
+        shutdown: CancellationToken,
     ) {
-        let mut buffer = [0u8; crate::GT7_PACKET_SIZE * 2]; // 留点余量
+        let mut buffer = [0u8; crate::GT7_MAX_PACKET_SIZE * 2]; // 留点余量
+        let mut jitter = jitter_config.map(JitterBuffer::new);
+        let mut inbox = match &connection.inbox {
+            Some((_, receiver)) => Some(receiver.lock().await),
+            None => None,
+        };
         
-        while *is_running.lock().unwrap() {
-            let connections_map = {
-                let guard = connections.lock().unwrap();
-                guard.clone()
+        loop {
+            let deadline = jitter.as_ref().and_then(JitterBuffer::next_deadline);
+            let received = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = connection.removed.cancelled() => break,
+                // 暂存的数据包等待超时
+                _ = tokio::time::sleep_until(deadline.map_or_else(tokio::time::Instant::now, Into::into)),
+                    if deadline.is_some() =>
+                {
+                    if let Some(jitter) = jitter.as_mut() {
+                        Self::deliver(&ip, jitter.poll(Instant::now()), &packet_sender, &event_sender);
+                    }
+                    continue;
+                }
+                received = Self::recv_datagram(&connection, inbox.as_deref_mut(), &mut buffer) => received,
+            };
+            let (size, from) = match received {
+                Ok(received) => received,
+                Err(e) => {
+                    log::warn!("从 {} 接收数据包时出错: {}", ip, e);
+                    continue;
+                }
             };
 
-            for (ip, mut connection) in connections_map {
-                match connection.socket.recv(&mut buffer) {
-                    Ok(size) => {
-                        if size >= crate::GT7_PACKET_SIZE {
-                            match GT7TelemetryPacket::from_bytes(&buffer[..crate::GT7_PACKET_SIZE]) {
-                                Ok(packet) => {
-                                    // 验证数据包
-                                    if packet.validate().is_ok() {
-                                        connection.last_received = Instant::now();
-                                        connection.is_connected = true;
-                                        connection.packet_count += 1;
-
-                                        // 更新连接状态
-                                        {
-                                            let mut connections_guard = connections.lock().unwrap();
-                                            connections_guard.insert(ip.clone(), connection.clone());
-                                        }
-
-                                        // 广播数据包
-                                        if packet_sender.send((ip.clone(), packet)).is_err() {
-                                            log::warn!("数据包广播队列已满，跳过数据包");
-                                        }
-
-                                        log::debug!("接收到来自 {} 的数据包 #{}", ip, connection.packet_count);
-                                    } else {
-                                        log::warn!("来自 {} 的数据包验证失败", ip);
-                                    }
-                                }
-                                Err(e) => {
-                                    log::warn!("解析来自 {} 的数据包失败: {}", ip, e);
-                                }
-                            }
-                        } else {
-                            log::warn!("来自 {} 的数据包大小不正确: {} 字节", ip, size);
-                        }
-                    }
-                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                        // 超时，继续尝试其他连接
-                        continue;
-                    }
-                    Err(e) => {
-                        log::warn!("从 {} 接收数据包时出错: {}", ip, e);
-                    }
+            // 录制原始数据报 (写入失败只报告一次)
+            if let Some(active) = &recorder {
+                if let Err(e) = active.record(from, &buffer[..size]) {
+                    log::warn!("录制 {} 的数据包失败，停止录制: {}", ip, e);
+                    recorder = None;
                 }
             }
 
-            // 短暂休眠避免过度占用CPU
-            tokio::time::sleep(Duration::from_millis(1)).await;
+            if from.ip().to_canonical() != connection.address.ip() {
+                log::debug!("忽略来自 {} 的非预期数据", from);
+                continue;
+            }
+
+            let decoded = if PacketVariant::from_packet_size(size).is_none() {
+                Err(format!("数据包大小不正确: {} 字节", size))
+            } else {
+                GT7TelemetryPacket::from_bytes(&buffer[..size])
+                    .and_then(|packet| packet.validate().map(|_| packet))
+                    .map_err(|e| e.to_string())
+            };
+
+            let packet = match decoded {
+                Ok(packet) => packet,
+                Err(error) => {
+                    log::warn!("解析来自 {} 的数据包失败: {}", ip, error);
+                    let _ = event_sender.send(ConnectionEvent::DecodeError {
+                        ip: ip.clone(),
+                        error,
+                        total_errors: connection.record_decode_error(),
+                    });
+                    continue;
+                }
+            };
+
+            // 更新连接状态
+            let silence = connection.since_last_received();
+            let variant = packet.variant;
+            let (packet_count, was_connected) = connection.record_packet();
+            {
+                let mut stats = connection.stats.lock().unwrap();
+                if !was_connected {
+                    stats.reset_stream();
+                }
+                stats.record_packet(packet.packet_id, Instant::now());
+            }
+            if packet_count == 1 {
+                let _ = event_sender.send(ConnectionEvent::FirstPacket { ip: ip.clone(), variant });
+            } else if !was_connected {
+                log::info!("GT7设备 {} 已恢复连接", ip);
+                let _ = event_sender.send(ConnectionEvent::Recovered { ip: ip.clone(), downtime: silence });
+            }
+
+            // 广播数据包
+            match jitter.as_mut() {
+                Some(jitter) => {
+                    Self::deliver(&ip, jitter.push(packet, Instant::now()), &packet_sender, &event_sender)
+                }
+                None => Self::broadcast_packet(&ip, packet, &packet_sender),
+            }
+
+            log::debug!("接收到来自 {} 的数据包 #{}", ip, packet_count);
+        }
+
+        log::debug!("{} 的接收任务已退出", ip);
+    }
+
+    /// 接收一个数据报: 共享模式下来自分发任务，否则直接从连接的套接字接收
+    async fn recv_datagram(
+        connection: &ClientConnection,
+        inbox: Option<&mut mpsc::Receiver<Datagram>>,
+        buffer: &mut [u8],
+    ) -> std::io::Result<(usize, SocketAddr)> {
+        let Some(inbox) = inbox else {
+            return connection.socket.recv_from(buffer).await;
+        };
+        match inbox.recv().await {
+            Some((data, from)) => {
+                let size = data.len().min(buffer.len());
+                buffer[..size].copy_from_slice(&data[..size]);
+                Ok((size, from))
+            }
+            // 发送端由连接自身持有，不会关闭
+            None => std::future::pending().await,
         }
     }
 
     /// 心跳发送任务
     async fn heartbeat_sender_task(
-        connections: Arc<Mutex<HashMap<String, ClientConnection>>>,
+        connections: ConnectionMap,
         heartbeat_interval_ms: u64,
-        is_running: Arc<Mutex<bool>>,
+        packet_variant: PacketVariant,
+        event_sender: broadcast::Sender<ConnectionEvent>,
+        shutdown: CancellationToken,
     ) {
-        let mut interval = interval(Duration::from_millis(heartbeat_interval_ms));
+        let heartbeat_interval = Duration::from_millis(heartbeat_interval_ms);
+        let mut interval = interval(heartbeat_interval);
         
-        while *is_running.lock().unwrap() {
-            interval.tick().await;
-
-            let connections_map = {
-                let guard = connections.lock().unwrap();
-                guard.clone()
-            };
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
 
-            for (ip, mut connection) in connections_map {
-                let now = Instant::now();
-                if now.duration_since(connection.last_heartbeat) >= Duration::from_millis(heartbeat_interval_ms) {
-                    match connection.socket.send_to(crate::GT7_HEARTBEAT, connection.address) {
+            for (ip, connection) in Self::snapshot(&connections) {
+                // 发送前取时间，避免发送耗时让下一次检查误以为未到间隔；
+                // 只跳过本周期内刚发过心跳的连接，容忍定时器抖动
+                let sent_at = connection.now_micros();
+                if connection.since_last_heartbeat() >= heartbeat_interval / 2 {
+                    match connection.socket.send_to(packet_variant.heartbeat(), connection.address).await {
                         Ok(_) => {
-                            connection.last_heartbeat = now;
-                            
                             // 更新连接状态
-                            {
-                                let mut connections_guard = connections.lock().unwrap();
-                                connections_guard.insert(ip.clone(), connection);
+                            connection.record_heartbeat(sent_at);
+                            if !connection.is_connected() {
+                                connection.stats.lock().unwrap().record_heartbeat(Instant::now());
                             }
                             
                             log::debug!("发送心跳到 {}", ip);
                         }
                         Err(e) => {
                             log::warn!("发送心跳到 {} 失败: {}", ip, e);
+                            let _ = event_sender.send(ConnectionEvent::HeartbeatFailed {
+                                ip,
+                                error: e.to_string(),
+                                consecutive_failures: connection.record_heartbeat_failure(),
+                            });
                         }
                     }
                 }
@@ -297,29 +788,57 @@ impl GT7TelemetryClient {
 
     /// 连接监控任务
     async fn connection_monitor_task(
-        connections: Arc<Mutex<HashMap<String, ClientConnection>>>,
+        connections: ConnectionMap,
         timeout_duration: Duration,
-        is_running: Arc<Mutex<bool>>,
+        event_sender: broadcast::Sender<ConnectionEvent>,
+        shutdown: CancellationToken,
     ) {
         let mut interval = interval(Duration::from_secs(1));
         
-        while *is_running.lock().unwrap() {
-            interval.tick().await;
-
-            let mut connections_guard = connections.lock().unwrap();
-            let now = Instant::now();
-            
-            for (ip, connection) in connections_guard.iter_mut() {
-                let time_since_last = now.duration_since(connection.last_received);
-                if time_since_last > timeout_duration && connection.is_connected {
-                    connection.is_connected = false;
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            for (ip, connection) in Self::snapshot(&connections) {
+                let time_since_last = connection.since_last_received();
+                if time_since_last > timeout_duration && connection.is_connected.swap(false, Ordering::AcqRel) {
                     log::warn!("GT7设备 {} 连接超时 ({}秒)", ip, time_since_last.as_secs());
+                    let _ = event_sender.send(ConnectionEvent::TimedOut { ip, silence: time_since_last });
                 }
             }
         }
     }
 }
 
+/// 绑定允许端口复用的UDP套接字 (SO_REUSEADDR，Unix上另加SO_REUSEPORT)
+/// 
+/// 注意: Linux上多个进程用SO_REUSEPORT绑定同一端口时，单播数据报会分配给其中一个套接字而不是复制
+pub(crate) fn bind_reusable_socket(address: SocketAddr) -> Result<UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let bind = || -> std::io::Result<UdpSocket> {
+        let socket = Socket::new(Domain::for_address(address), Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        socket.set_reuse_port(true)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&address.into())?;
+        UdpSocket::from_std(socket.into())
+    };
+    bind().map_err(|e| GT7Error::network_error(address.to_string(), e.to_string()))
+}
+
+impl Drop for GT7TelemetryClient {
+    /// 未调用 `stop` 就丢弃客户端时，仍通知后台任务退出
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.get_mut().unwrap().take() {
+            runtime.shutdown.cancel();
+        }
+    }
+}
+
 /// 简化的单IP客户端
 /// 
 /// 用于只需要连接一个GT7设备的场景
@@ -333,7 +852,7 @@ impl SimpleGT7Client {
     pub async fn new(ip: String, port: Option<u16>) -> Result<(Self, broadcast::Receiver<(String, GT7TelemetryPacket)>)> {
         let config = TelemetryConfig {
             console_ip: ip.clone(),
-            port: port.unwrap_or(crate::GT7_TELEMETRY_PORT),
+            port: port.unwrap_or(crate::GT7_HEARTBEAT_PORT),
             ..Default::default()
         };
 
@@ -390,4 +909,578 @@ mod tests {
         let result = client.add_connection("invalid.ip".to_string(), None).await;
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    /// 在本机启动一个直接回复心跳来源端口的模拟器
+    async fn start_simulator() -> u16 {
+        use crate::simulator::{ConsoleSimulator, ScriptedLap, SimulatorConfig};
+
+        let config = SimulatorConfig {
+            bind_address: "127.0.0.1:0".parse().unwrap(),
+            reply_port: None,
+            ..Default::default()
+        };
+        let simulator = ConsoleSimulator::bind(config, Box::new(ScriptedLap::new(0))).await.unwrap();
+        let port = simulator.local_addr().unwrap().port();
+        tokio::spawn(simulator.run());
+        port
+    }
+
+    /// 绑定在本机回环地址上、从不应答的"主机"，测试发出的心跳不会进入真实网络
+    async fn silent_console() -> (UdpSocket, u16) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        (socket, port)
+    }
+
+    /// 统计一段时间内收到的来自指定IP的数据包数量
+    async fn count_packets(
+        receiver: &mut broadcast::Receiver<(String, GT7TelemetryPacket)>,
+        ip: &str,
+        duration: Duration,
+    ) -> usize {
+        let deadline = tokio::time::Instant::now() + duration;
+        let mut count = 0;
+        while let Ok(Ok((from, _))) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+            if from == ip {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn test_receives_packets_from_simulator() {
+        let port = start_simulator().await;
+        let (client, mut receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+
+        // 等待首个心跳生效后统计1秒内的数据包 (模拟器60Hz)
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let count = count_packets(&mut receiver, "127.0.0.1", Duration::from_secs(1)).await;
+        assert!(count >= 40, "1秒内只收到 {} 个数据包", count);
+        assert_eq!(client.get_connection_status().get("127.0.0.1"), Some(&true));
+
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_sent_every_interval() {
+        let console = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = console.local_addr().unwrap().port();
+        let config = TelemetryConfig { heartbeat_interval: 50, ..Default::default() };
+        let (client, _) = GT7TelemetryClient::new(config).unwrap();
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+
+        // 1秒内应收到约20个心跳，发送耗时不应导致跳过周期
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+        let mut buffer = [0u8; 16];
+        let mut count = 0;
+        while let Ok(Ok(_)) = tokio::time::timeout_at(deadline, console.recv_from(&mut buffer)).await {
+            count += 1;
+        }
+        assert!(count >= 18, "1秒内只收到 {} 个心跳", count);
+
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_silent_connection_does_not_delay_others() {
+        let port = start_simulator().await;
+        let (client, mut receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+
+        // 无应答的设备不应拖慢其它连接的接收
+        let (_console, silent_port) = silent_console().await;
+        client.add_connection("127.0.0.2".to_string(), Some(silent_port)).await.unwrap();
+        client.add_connection("127.0.0.3".to_string(), Some(silent_port)).await.unwrap();
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let count = count_packets(&mut receiver, "127.0.0.1", Duration::from_secs(1)).await;
+        assert!(count >= 40, "1秒内只收到 {} 个数据包", count);
+
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_connection_added_while_running() {
+        let port = start_simulator().await;
+        let (client, mut receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        client.start().await.unwrap();
+
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        let count = count_packets(&mut receiver, "127.0.0.1", Duration::from_secs(1)).await;
+        assert!(count > 0);
+
+        client.stop().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_record_packet_is_not_lost() {
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let connection = Arc::new(ClientConnection::new("127.0.0.1:33739".parse().unwrap(), socket));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let connection = Arc::clone(&connection);
+                tokio::spawn(async move {
+                    for i in 0..1000 {
+                        connection.record_packet();
+                        if i % 10 == 0 {
+                            connection.record_heartbeat(connection.now_micros());
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(connection.packet_count(), 8000);
+        assert!(connection.is_connected());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_packet_count_exact_under_concurrent_churn() {
+        const PACKETS: u64 = 500;
+
+        let config = TelemetryConfig { heartbeat_interval: 1, ..Default::default() };
+        let (client, mut receiver) = GT7TelemetryClient::new(config).unwrap();
+        let client = Arc::new(client);
+        let (_console, console_port) = silent_console().await;
+        client.add_connection("127.0.0.1".to_string(), Some(console_port)).await.unwrap();
+        client.start().await.unwrap();
+
+        let target = client.connections.lock().unwrap()["127.0.0.1"].socket.local_addr().unwrap();
+
+        // 心跳、监控和连接增删与接收同时进行
+        let churn = {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move {
+                for i in 0..50 {
+                    let ip = format!("127.0.0.{}", i % 5 + 2);
+                    client.add_connection(ip.clone(), Some(console_port)).await.unwrap();
+                    tokio::task::yield_now().await;
+                    client.remove_connection(&ip).await.unwrap();
+                }
+            })
+        };
+
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for packet_id in 0..PACKETS as u32 {
+            let bytes = crate::PacketBuilder::new().packet_id(packet_id).build_bytes();
+            sender.send_to(&bytes, target).await.unwrap();
+            if packet_id % 50 == 0 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+        churn.await.unwrap();
+
+        let received = count_packets(&mut receiver, "127.0.0.1", Duration::from_millis(500)).await;
+        let connection = Arc::clone(&client.connections.lock().unwrap()["127.0.0.1"]);
+        assert_eq!(connection.packet_count(), received as u64);
+        assert_eq!(received as u64, PACKETS);
+
+        client.stop().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_removed_connections_stay_removed() {
+        let config = TelemetryConfig { heartbeat_interval: 1, ..Default::default() };
+        let (client, _receiver) = GT7TelemetryClient::new(config).unwrap();
+        let client = Arc::new(client);
+        let (_console, console_port) = silent_console().await;
+        client.start().await.unwrap();
+
+        let tasks: Vec<_> = (0..4)
+            .map(|worker| {
+                let client = Arc::clone(&client);
+                tokio::spawn(async move {
+                    for round in 0..25 {
+                        let ip = format!("127.0.{}.{}", 100 + worker, round % 3 + 1);
+                        client.add_connection(ip.clone(), Some(console_port)).await.unwrap();
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                        client.remove_connection(&ip).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // 给后台任务足够时间运行，移除不能被撤销
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(client.get_connection_status().is_empty());
+
+        client.stop().await;
+    }
+
+    /// 后台任务结束后不再持有连接表和连接状态
+    fn assert_no_tasks_alive(client: &GT7TelemetryClient) {
+        assert!(!client.is_running());
+        assert_eq!(Arc::strong_count(&client.connections), 1);
+        for (ip, connection) in GT7TelemetryClient::snapshot(&client.connections) {
+            // 连接表 + 快照各持有一份
+            assert_eq!(Arc::strong_count(&connection), 2, "{} 的接收任务仍在运行", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_waits_for_all_tasks() {
+        let (client, _receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        let (_console, port) = silent_console().await;
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        client.add_connection("127.0.0.2".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+        assert!(client.is_running());
+        assert!(Arc::strong_count(&client.connections) > 1);
+
+        client.stop().await;
+        assert_no_tasks_alive(&client);
+
+        // 重复停止不做任何事
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_stop_does_not_wait_for_heartbeat_interval() {
+        let config = TelemetryConfig { heartbeat_interval: 60_000, ..Default::default() };
+        let (client, _receiver) = GT7TelemetryClient::new(config).unwrap();
+        let (_console, port) = silent_console().await;
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        tokio::time::timeout(Duration::from_millis(200), client.stop())
+            .await
+            .expect("stop 应立即返回");
+        assert_no_tasks_alive(&client);
+    }
+
+    #[tokio::test]
+    async fn test_start_stop_cycles() {
+        let port = start_simulator().await;
+        let (client, mut receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+
+        for _ in 0..3 {
+            client.start().await.unwrap();
+            assert!(client.start().await.is_err());
+
+            let count = count_packets(&mut receiver, "127.0.0.1", Duration::from_millis(500)).await;
+            assert!(count > 0);
+
+            client.stop().await;
+            assert_no_tasks_alive(&client);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_cancels_tasks() {
+        let (client, _receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        let (_console, port) = silent_console().await;
+        client.add_connection("127.0.0.1".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+
+        let connections = Arc::clone(&client.connections);
+        drop(client);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(Arc::strong_count(&connections), 1);
+    }
+
+    /// 等待下一个指定IP的连接事件
+    async fn next_event(receiver: &mut broadcast::Receiver<ConnectionEvent>, ip: &str) -> ConnectionEvent {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+                .await
+                .expect("等待连接事件超时")
+                .unwrap();
+            if event.ip() == ip {
+                return event;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_lifecycle_events() {
+        let config = TelemetryConfig { timeout: 1, ..Default::default() };
+        let (client, _receiver) = GT7TelemetryClient::new(config).unwrap();
+        let mut events = client.subscribe_events();
+
+        client.add_connection("127.0.0.1".to_string(), None).await.unwrap();
+        assert!(matches!(next_event(&mut events, "127.0.0.1").await, ConnectionEvent::Added { .. }));
+        client.start().await.unwrap();
+
+        let target = client.connections.lock().unwrap()["127.0.0.1"].socket.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let packet = crate::PacketBuilder::new().variant(PacketVariant::B).build_bytes();
+
+        sender.send_to(&packet, target).await.unwrap();
+        assert_eq!(
+            next_event(&mut events, "127.0.0.1").await,
+            ConnectionEvent::FirstPacket { ip: "127.0.0.1".to_string(), variant: PacketVariant::B }
+        );
+
+        // 损坏的数据包
+        sender.send_to(&[0u8; 296], target).await.unwrap();
+        sender.send_to(&[0u8; 10], target).await.unwrap();
+        for expected in 1..=2 {
+            match next_event(&mut events, "127.0.0.1").await {
+                ConnectionEvent::DecodeError { total_errors, .. } => assert_eq!(total_errors, expected),
+                other => panic!("意外的事件: {:?}", other),
+            }
+        }
+
+        // 停止发送后超时，再次发送后恢复
+        match next_event(&mut events, "127.0.0.1").await {
+            ConnectionEvent::TimedOut { silence, .. } => assert!(silence > Duration::from_secs(1)),
+            other => panic!("意外的事件: {:?}", other),
+        }
+        sender.send_to(&packet, target).await.unwrap();
+        match next_event(&mut events, "127.0.0.1").await {
+            ConnectionEvent::Recovered { downtime, .. } => assert!(downtime > Duration::from_secs(1)),
+            other => panic!("意外的事件: {:?}", other),
+        }
+
+        client.remove_connection("127.0.0.1").await.unwrap();
+        assert_eq!(
+            next_event(&mut events, "127.0.0.1").await,
+            ConnectionEvent::Removed { ip: "127.0.0.1".to_string(), packet_count: 2 }
+        );
+
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_replacing_connection_emits_removed() {
+        let (client, _receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        let mut events = client.subscribe_events();
+
+        client.add_connection("192.168.1.30".to_string(), None).await.unwrap();
+        client.add_connection("192.168.1.30".to_string(), Some(40000)).await.unwrap();
+
+        assert!(matches!(next_event(&mut events, "192.168.1.30").await, ConnectionEvent::Added { .. }));
+        assert!(matches!(next_event(&mut events, "192.168.1.30").await, ConnectionEvent::Removed { .. }));
+        match next_event(&mut events, "192.168.1.30").await {
+            ConnectionEvent::Added { address, .. } => assert_eq!(address.port(), 40000),
+            other => panic!("意外的事件: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_stats() {
+        let (client, _receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        let mut events = client.subscribe_events();
+        client.add_connection("127.0.0.1".to_string(), None).await.unwrap();
+        assert!(client.connection_stats("192.168.1.1").is_none());
+        client.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let target = client.connections.lock().unwrap()["127.0.0.1"].socket.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for packet_id in [1, 2, 4, 5, 3, 5, 6] {
+            let bytes = crate::PacketBuilder::new().packet_id(packet_id).build_bytes();
+            sender.send_to(&bytes, target).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        sender.send_to(&[0u8; 296], target).await.unwrap();
+        while !matches!(next_event(&mut events, "127.0.0.1").await, ConnectionEvent::DecodeError { .. }) {}
+
+        let stats = client.connection_stats("127.0.0.1").unwrap();
+        assert!(stats.is_connected);
+        assert_eq!(stats.packets_received, 7);
+        assert_eq!(stats.dropped_packets, 0);
+        assert_eq!(stats.out_of_order_packets, 1);
+        assert_eq!(stats.duplicate_packets, 1);
+        assert_eq!(stats.decode_failures, 1);
+        assert_eq!(stats.last_packet_id, Some(6));
+        assert!(stats.heartbeat_latency.is_some());
+        assert!(stats.packets_per_second > 0.0);
+        assert_eq!(client.all_connection_stats().len(), 1);
+
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_jitter_buffer_delivers_in_order() {
+        let config = TelemetryConfig {
+            jitter_buffer: Some(JitterBufferConfig { reorder_window: 8, max_delay_ms: 100 }),
+            ..Default::default()
+        };
+        let (client, mut receiver) = GT7TelemetryClient::new(config).unwrap();
+        let mut events = client.subscribe_events();
+        client.add_connection("127.0.0.1".to_string(), None).await.unwrap();
+        client.start().await.unwrap();
+
+        let target = client.connections.lock().unwrap()["127.0.0.1"].socket.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for packet_id in [1, 3, 2, 2, 1, 5, 6] {
+            let bytes = crate::PacketBuilder::new().packet_id(packet_id).build_bytes();
+            sender.send_to(&bytes, target).await.unwrap();
+        }
+
+        let mut delivered = Vec::new();
+        while delivered.len() < 5 {
+            let (_, packet) = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().unwrap();
+            delivered.push(packet.packet_id);
+        }
+        assert_eq!(delivered, [1, 2, 3, 5, 6]);
+
+        loop {
+            if let ConnectionEvent::SequenceGap { expected, resumed, .. } = next_event(&mut events, "127.0.0.1").await {
+                assert_eq!((expected, resumed), (4, 5));
+                break;
+            }
+        }
+
+        client.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_address_policy_applied() {
+        let (client, _) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        assert!(matches!(
+            client.add_connection("172.32.0.1".to_string(), None).await,
+            Err(GT7Error::AddressNotAllowed { .. })
+        ));
+        assert!(client.add_connection("172.999.1.1".to_string(), None).await.is_err());
+        client.add_connection("localhost".to_string(), None).await.unwrap();
+
+        let config = TelemetryConfig {
+            allowed_networks: vec!["172.32.0.0/16".parse().unwrap()],
+            ..Default::default()
+        };
+        let (client, _) = GT7TelemetryClient::new(config).unwrap();
+        client.add_connection("172.32.0.1".to_string(), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_receives_packets_by_hostname_and_ipv6() {
+        let port = start_simulator().await;
+        let (client, mut receiver) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        client.add_connection("localhost".to_string(), Some(port)).await.unwrap();
+        client.start().await.unwrap();
+        assert!(count_packets(&mut receiver, "localhost", Duration::from_millis(500)).await > 0);
+        client.stop().await;
+
+        // IPv6需要显式开启
+        let (client, _) = GT7TelemetryClient::new(TelemetryConfig::default()).unwrap();
+        assert!(client.add_connection("::1".to_string(), None).await.is_err());
+        let config = TelemetryConfig { allow_ipv6: true, ..Default::default() };
+        let (client, _) = GT7TelemetryClient::new(config).unwrap();
+        client.add_connection("::1".to_string(), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_socket_demultiplexes_consoles() {
+        use crate::simulator::{ConsoleSimulator, ScriptedLap, SimulatorConfig};
+
+        // 分发按来源IP区分主机，需要第二个回环地址；macOS/BSD默认只有127.0.0.1
+        if std::net::UdpSocket::bind("127.0.0.2:0").is_err() {
+            eprintln!("跳过 test_shared_socket_demultiplexes_consoles: 无法绑定127.0.0.2");
+            return;
+        }
+
+        // 先找一个空闲端口作为共享端口，模拟器像主机一样固定回复到该端口
+        let shared_port = std::net::UdpSocket::bind("0.0.0.0:0").unwrap().local_addr().unwrap().port();
+        let mut simulator_ports = Vec::new();
+        for ip in ["127.0.0.1", "127.0.0.2"] {
+            let config = SimulatorConfig {
+                bind_address: format!("{}:0", ip).parse().unwrap(),
+                reply_port: Some(shared_port),
+                ..Default::default()
+            };
+            let simulator = ConsoleSimulator::bind(config, Box::new(ScriptedLap::new(0))).await.unwrap();
+            simulator_ports.push(simulator.local_addr().unwrap().port());
+            tokio::spawn(simulator.run());
+        }
+
+        let config = TelemetryConfig { shared_socket_port: Some(shared_port), ..Default::default() };
+        let (client, mut receiver) = GT7TelemetryClient::new(config).unwrap();
+        client.add_connection("127.0.0.1".to_string(), Some(simulator_ports[0])).await.unwrap();
+        client.start().await.unwrap();
+        // 运行中添加的连接同样使用共享套接字
+        client.add_connection("127.0.0.2".to_string(), Some(simulator_ports[1])).await.unwrap();
+
+        {
+            let connections = client.connections.lock().unwrap();
+            assert!(Arc::ptr_eq(&connections["127.0.0.1"].socket, &connections["127.0.0.2"].socket));
+            assert_eq!(connections["127.0.0.1"].socket.local_addr().unwrap().port(), shared_port);
+        }
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        while let Ok(Ok((ip, _))) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+            *counts.entry(ip).or_default() += 1;
+        }
+        assert!(counts.get("127.0.0.1").is_some_and(|&count| count > 20), "{:?}", counts);
+        assert!(counts.get("127.0.0.2").is_some_and(|&count| count > 20), "{:?}", counts);
+
+        client.stop().await;
+        assert_no_tasks_alive(&client);
+
+        // 重启后继续从共享套接字接收
+        client.start().await.unwrap();
+        assert!(count_packets(&mut receiver, "127.0.0.2", Duration::from_millis(500)).await > 0);
+        client.stop().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shared_socket_port_can_be_reused() {
+        let first = bind_reusable_socket("0.0.0.0:0".parse().unwrap()).unwrap();
+        let address = first.local_addr().unwrap();
+        assert!(bind_reusable_socket(address).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_enable_logging_records_each_session() {
+        use crate::recorder::RecordingReader;
+
+        let dir = std::env::temp_dir().join(format!("gt7-client-recording-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = TelemetryConfig {
+            enable_logging: true,
+            log_file_path: Some(dir.to_string_lossy().into_owned()),
+            packet_variant: PacketVariant::Tilde,
+            ..Default::default()
+        };
+        let (client, mut receiver) = GT7TelemetryClient::new(config).unwrap();
+        client.add_connection("127.0.0.1".to_string(), None).await.unwrap();
+
+        let target = client.connections.lock().unwrap()["127.0.0.1"].socket.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for session in 0..2u32 {
+            client.start().await.unwrap();
+            for packet_id in 0..5 {
+                let bytes = crate::PacketBuilder::new().packet_id(session * 100 + packet_id).build_bytes();
+                sender.send_to(&bytes, target).await.unwrap();
+            }
+            // 无法解析的数据报同样被录制
+            sender.send_to(b"garbage", target).await.unwrap();
+            assert_eq!(count_packets(&mut receiver, "127.0.0.1", Duration::from_millis(200)).await, 5);
+            client.stop().await;
+        }
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        for file in &files {
+            let mut reader = RecordingReader::open(file).unwrap();
+            assert_eq!(reader.header().variant, PacketVariant::Tilde);
+            let records: Vec<_> = reader.by_ref().collect();
+            assert!(!reader.is_truncated());
+            assert_eq!(records.len(), 6);
+            assert_eq!(records[5].data, b"garbage");
+            assert!(records.iter().all(|record| record.source.ip() == sender.local_addr().unwrap().ip()));
+            assert!(records.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
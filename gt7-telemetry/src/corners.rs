@@ -0,0 +1,505 @@
+//! 弯道识别与逐弯分析
+//!
+//! [`CornerAnalyzer`] 用参考圈的横摆角速度和曲率 (横摆角速度/车速) 把赛道划分为弯道和直道，
+//! 然后对任意一圈给出每个弯的入弯速度、最低速度和弯心位置、出弯速度、刹车点、给油点，
+//! 以及相对参考圈在该弯所在区间损失的时间。
+//!
+//! 每个弯的计时区间从前一段直道的中点到后一段直道的中点，所有区间首尾相接覆盖整圈，
+//! 因此各弯损失时间之和等于整圈的时间差。位置用沿赛道的距离表示: 提供 [`TrackMap`] 时
+//! 使用地图投影，否则使用车速积分的距离并按参考圈长度缩放
+
+use crate::error::{Result, GT7Error};
+use crate::laps::{Lap, LapSample};
+use crate::sectors::{distance_profile, time_at};
+use crate::track_map::TrackMap;
+use crate::types::Vector3;
+use std::time::Duration;
+
+/// 弯道识别配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct CornerConfig {
+    /// 弯道的最小横摆角速度 (rad/s)
+    pub min_yaw_rate: f32,
+    /// 弯道的最小曲率 (1/m，即最大转弯半径的倒数)
+    pub min_curvature: f32,
+    /// 弯道的最小航向变化 (rad)，小于该值的视为直道上的修正
+    pub min_heading_change: f32,
+    /// 同向弯道间隔小于该距离时合并为一个弯 (米)
+    pub merge_distance: f32,
+    /// 横摆角速度平滑窗口 (采样数)
+    pub smoothing: usize,
+    /// 视为开始刹车的刹车踏板开度 (0-1)
+    pub brake_threshold: f32,
+    /// 视为开始给油的油门开度 (0-1)
+    pub throttle_threshold: f32,
+}
+
+impl Default for CornerConfig {
+    fn default() -> Self {
+        Self {
+            min_yaw_rate: 0.1,
+            min_curvature: 1.0 / 500.0,
+            min_heading_change: 15f32.to_radians(),
+            merge_distance: 30.0,
+            smoothing: 9,
+            brake_threshold: 0.1,
+            throttle_threshold: 0.2,
+        }
+    }
+}
+
+/// 赛道区段类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// 弯道
+    Corner,
+    /// 直道
+    Straight,
+}
+
+/// 赛道区段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackSegment {
+    /// 区段类型
+    pub kind: SegmentKind,
+    /// 弯道编号 (从1开始，直道为0)
+    pub number: usize,
+    /// 起点距离 (米)
+    pub start: f32,
+    /// 终点距离 (米)
+    pub end: f32,
+    /// 区段内的航向变化 (rad，符号与横摆角速度相同)
+    pub heading_change: f32,
+}
+
+/// 一圈在某个弯的表现
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CornerReport {
+    /// 弯道编号 (从1开始)
+    pub number: usize,
+    /// 入弯速度 (km/h)
+    pub entry_speed_kmh: f32,
+    /// 弯中最低速度 (km/h)
+    pub min_speed_kmh: f32,
+    /// 弯心 (最低速度点) 的距离 (米)
+    pub apex_distance: f32,
+    /// 弯心的世界坐标
+    pub apex_position: Vector3,
+    /// 出弯速度 (km/h)
+    pub exit_speed_kmh: f32,
+    /// 刹车点距离 (米)，没有刹车时为 `None`
+    pub braking_point: Option<f32>,
+    /// 弯心后的给油点距离 (米)
+    pub throttle_pickup: Option<f32>,
+    /// 该弯计时区间的用时
+    pub time: Duration,
+    /// 相对参考圈损失的时间 (秒，负数表示更快)
+    pub time_lost: f32,
+}
+
+/// 一圈的距离和时间
+#[derive(Debug, Clone)]
+struct Trace<'a> {
+    samples: &'a [LapSample],
+    /// 各采样的距离，与 `samples` 一一对应
+    distances: Vec<f32>,
+    /// (距离, 时间)，距离单调不减
+    profile: Vec<(f32, Duration)>,
+}
+
+/// 弯道分析器
+#[derive(Debug, Clone)]
+pub struct CornerAnalyzer {
+    config: CornerConfig,
+    track_map: Option<TrackMap>,
+    length: f32,
+    segments: Vec<TrackSegment>,
+    /// 各弯计时区间的边界，比弯道数多1
+    zones: Vec<f32>,
+    reference_profile: Vec<(f32, Duration)>,
+}
+
+impl CornerAnalyzer {
+    /// 用参考圈识别弯道，距离使用车速积分
+    pub fn new(reference: &Lap, config: CornerConfig) -> Result<Self> {
+        Self::build(reference, config, None)
+    }
+
+    /// 用参考圈识别弯道，距离使用赛道地图投影
+    pub fn with_track_map(reference: &Lap, config: CornerConfig, track_map: TrackMap) -> Result<Self> {
+        Self::build(reference, config, Some(track_map))
+    }
+
+    fn build(reference: &Lap, config: CornerConfig, track_map: Option<TrackMap>) -> Result<Self> {
+        if reference.samples.len() < 2 {
+            return Err(GT7Error::config_error(
+                "reference",
+                reference.samples.len().to_string(),
+                "参考圈至少需要两个采样",
+            ));
+        }
+        let mut analyzer = Self {
+            config,
+            track_map,
+            length: 0.0,
+            segments: Vec::new(),
+            zones: Vec::new(),
+            reference_profile: Vec::new(),
+        };
+        let trace = analyzer.trace(reference);
+        analyzer.length = trace.distances.last().copied().unwrap_or_default();
+        if analyzer.length <= 0.0 {
+            return Err(GT7Error::config_error("reference", "0", "参考圈没有行驶距离"));
+        }
+        analyzer.segments = analyzer.detect(&trace);
+        analyzer.zones = zone_boundaries(&analyzer.segments, analyzer.length);
+        analyzer.reference_profile = trace.profile;
+        log::info!(
+            "识别到 {} 个弯道，参考圈长度 {:.0}m",
+            analyzer.zones.len().saturating_sub(1),
+            analyzer.length
+        );
+        Ok(analyzer)
+    }
+
+    /// 参考圈长度 (米)
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// 弯道和直道，按距离排列
+    pub fn segments(&self) -> &[TrackSegment] {
+        &self.segments
+    }
+
+    /// 弯道
+    pub fn corners(&self) -> impl Iterator<Item = &TrackSegment> {
+        self.segments.iter().filter(|segment| segment.kind == SegmentKind::Corner)
+    }
+
+    /// 分析一圈在每个弯的表现
+    pub fn analyze(&self, lap: &Lap) -> Vec<CornerReport> {
+        let trace = self.trace(lap);
+        if trace.samples.is_empty() {
+            return Vec::new();
+        }
+        self.corners()
+            .zip(self.zones.windows(2))
+            .map(|(corner, zone)| self.report(&trace, corner, zone[0], zone[1]))
+            .collect()
+    }
+
+    fn report(&self, trace: &Trace, corner: &TrackSegment, zone_start: f32, zone_end: f32) -> CornerReport {
+        let index_at = |distance: f32| trace.distances.partition_point(|&d| d < distance).min(trace.samples.len() - 1);
+        let speed_kmh = |index: usize| trace.samples[index].packet.car_info.speed * 3.6;
+
+        let start = index_at(corner.start);
+        let end = index_at(corner.end).max(start);
+        let apex = (start..=end)
+            .min_by(|&a, &b| speed_kmh(a).total_cmp(&speed_kmh(b)))
+            .unwrap_or(start);
+
+        let zone_first = index_at(zone_start);
+        let zone_last = index_at(zone_end);
+        let engine = |index: usize| &trace.samples[index].packet.car_info.engine;
+        let braking_point = (zone_first..=apex)
+            .find(|&index| engine(index).brake >= self.config.brake_threshold)
+            .map(|index| trace.distances[index]);
+        let throttle_pickup = (apex..=zone_last.max(apex))
+            .find(|&index| engine(index).throttle >= self.config.throttle_threshold)
+            .map(|index| trace.distances[index]);
+
+        let zone_time = |profile: &[(f32, Duration)]| {
+            let from = time_at(profile, zone_start).unwrap_or_default();
+            let to = time_at(profile, zone_end).unwrap_or_default();
+            to.saturating_sub(from)
+        };
+        let time = zone_time(&trace.profile);
+        let reference_time = zone_time(&self.reference_profile);
+
+        CornerReport {
+            number: corner.number,
+            entry_speed_kmh: speed_kmh(start),
+            min_speed_kmh: speed_kmh(apex),
+            apex_distance: trace.distances[apex],
+            apex_position: trace.samples[apex].packet.car_info.position.world,
+            exit_speed_kmh: speed_kmh(end),
+            braking_point,
+            throttle_pickup,
+            time,
+            time_lost: time.as_secs_f32() - reference_time.as_secs_f32(),
+        }
+    }
+
+    /// 计算一圈各采样的距离
+    fn trace<'a>(&self, lap: &'a Lap) -> Trace<'a> {
+        let samples = lap.samples.as_slice();
+        let distances = match &self.track_map {
+            Some(map) => map_distances(map, samples),
+            None => {
+                let integrated: Vec<f32> = distance_profile(samples).into_iter().map(|(d, _)| d).collect();
+                // 按参考圈长度缩放，使不同走线的同一个弯落在相同的距离 (构建参考圈时不缩放)
+                let total = integrated.last().copied().unwrap_or_default();
+                let scale = if self.length > 0.0 && total > 0.0 { self.length / total } else { 1.0 };
+                integrated.into_iter().map(|d| d * scale).collect()
+            }
+        };
+        // 时间曲线要求距离单调不减
+        let mut furthest = f32::MIN;
+        let profile = samples
+            .iter()
+            .zip(&distances)
+            .map(|(sample, &distance)| {
+                furthest = furthest.max(distance);
+                (furthest, sample.elapsed)
+            })
+            .collect();
+        Trace { samples, distances, profile }
+    }
+
+    /// 按横摆角速度和曲率识别弯道
+    fn detect(&self, trace: &Trace) -> Vec<TrackSegment> {
+        let samples = trace.samples;
+        let yaw = smooth(
+            &samples.iter().map(|sample| sample.packet.car_info.position.angular_velocity.y).collect::<Vec<_>>(),
+            self.config.smoothing,
+        );
+
+        // (起始采样, 结束采样, 航向变化)
+        let mut runs: Vec<(usize, usize, f32)> = Vec::new();
+        let mut current: Option<(usize, usize, f32)> = None;
+        for index in 0..samples.len() {
+            let speed = samples[index].packet.car_info.speed.max(1.0);
+            let cornering = yaw[index].abs() >= self.config.min_yaw_rate
+                && yaw[index].abs() / speed >= self.config.min_curvature;
+            let dt = match index {
+                0 => 0.0,
+                _ => samples[index].elapsed.saturating_sub(samples[index - 1].elapsed).as_secs_f32(),
+            };
+            match (&mut current, cornering) {
+                (Some(run), true) if run.2.signum() == yaw[index].signum() || run.2 == 0.0 => {
+                    run.1 = index;
+                    run.2 += yaw[index] * dt;
+                }
+                (_, true) => {
+                    runs.extend(current.take());
+                    current = Some((index, index, yaw[index] * dt));
+                }
+                (_, false) => runs.extend(current.take()),
+            }
+        }
+        runs.extend(current);
+
+        // 合并相距很近的同向弯
+        let mut merged: Vec<(usize, usize, f32)> = Vec::new();
+        for run in runs {
+            match merged.last_mut() {
+                Some(last)
+                    if last.2.signum() == run.2.signum()
+                        && trace.distances[run.0] - trace.distances[last.1] < self.config.merge_distance =>
+                {
+                    last.1 = run.1;
+                    last.2 += run.2;
+                }
+                _ => merged.push(run),
+            }
+        }
+
+        let mut segments = Vec::new();
+        let mut position = 0.0;
+        let push_straight = |segments: &mut Vec<TrackSegment>, start: f32, end: f32| {
+            if end > start {
+                segments.push(TrackSegment { kind: SegmentKind::Straight, number: 0, start, end, heading_change: 0.0 });
+            }
+        };
+        for (start, end, heading_change) in merged {
+            if heading_change.abs() < self.config.min_heading_change {
+                continue;
+            }
+            let (start, end) = (trace.distances[start], trace.distances[end]);
+            push_straight(&mut segments, position, start);
+            let number = segments.iter().filter(|segment| segment.kind == SegmentKind::Corner).count() + 1;
+            segments.push(TrackSegment { kind: SegmentKind::Corner, number, start, end, heading_change });
+            position = end;
+        }
+        push_straight(&mut segments, position, self.length);
+        segments
+    }
+}
+
+/// 用赛道地图投影得到各采样的距离 (跨过地图起点时展开，使距离连续)
+fn map_distances(map: &TrackMap, samples: &[LapSample]) -> Vec<f32> {
+    let mut distances = Vec::with_capacity(samples.len());
+    let mut previous: Option<f32> = None;
+    for sample in samples {
+        let position = sample.packet.car_info.position.world;
+        let near = previous.unwrap_or_default();
+        let Some(projection) = map.project_near(position, near) else {
+            distances.push(near);
+            continue;
+        };
+        let mut distance = projection.distance;
+        if map.closed && map.length > 0.0 {
+            // 展开到离上一个距离最近的一圈
+            let reference = previous.unwrap_or_default();
+            distance += ((reference - distance) / map.length).round() * map.length;
+        }
+        previous = Some(distance);
+        distances.push(distance);
+    }
+    distances
+}
+
+/// 各弯计时区间的边界: 起点、相邻弯之间直道的中点、终点
+fn zone_boundaries(segments: &[TrackSegment], length: f32) -> Vec<f32> {
+    let corners: Vec<&TrackSegment> = segments.iter().filter(|segment| segment.kind == SegmentKind::Corner).collect();
+    if corners.is_empty() {
+        return Vec::new();
+    }
+    let mut zones = vec![0.0];
+    zones.extend(corners.windows(2).map(|pair| (pair[0].end + pair[1].start) / 2.0));
+    zones.push(length);
+    zones
+}
+
+/// 居中滑动平均
+fn smooth(values: &[f32], window: usize) -> Vec<f32> {
+    let half = window / 2;
+    (0..values.len())
+        .map(|index| {
+            let from = index.saturating_sub(half);
+            let to = (index + half + 1).min(values.len());
+            values[from..to].iter().sum::<f32>() / (to - from) as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+    use crate::laps::LapKind;
+    use crate::track_map::TrackMapBuilder;
+
+    /// 两段直道 (200米) 和两个180°弯 (半径40米) 组成的椭圆赛道。
+    /// 弯前60米重刹减速到弯速，弯心之后全油门加速
+    fn drive(corner_speeds: [f32; 2]) -> Lap {
+        const STRAIGHT: f32 = 200.0;
+        const RADIUS: f32 = 40.0;
+        const STRAIGHT_SPEED: f32 = 50.0;
+        let corner_length = std::f32::consts::PI * RADIUS;
+        let length = 2.0 * (STRAIGHT + corner_length);
+
+        let dt = 1.0 / 60.0;
+        let mut samples = Vec::new();
+        let (mut distance, mut heading, mut x, mut z) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+        let mut frame = 0u32;
+        while distance < length {
+            // 当前所在区段: 直道0、弯1、直道2、弯3
+            let piece_start = |piece: usize| match piece {
+                0 => 0.0,
+                1 => STRAIGHT,
+                2 => STRAIGHT + corner_length,
+                _ => 2.0 * STRAIGHT + corner_length,
+            };
+            let piece = (0..4).rev().find(|&piece| distance >= piece_start(piece)).unwrap();
+            let in_corner = piece % 2 == 1;
+            let corner_speed = corner_speeds[piece / 2];
+            let (speed, throttle, brake) = if in_corner {
+                let into = distance - piece_start(piece);
+                (corner_speed, if into > corner_length / 2.0 { 1.0 } else { 0.0 }, 0.0)
+            } else {
+                let to_corner = piece_start(piece + 1) - distance;
+                let previous_corner_speed = if piece == 0 { STRAIGHT_SPEED } else { corner_speeds[0] };
+                let accelerated = (previous_corner_speed + (distance - piece_start(piece)) * 0.5).min(STRAIGHT_SPEED);
+                let next_corner_speed = if piece == 0 { corner_speeds[0] } else { corner_speeds[1] };
+                if to_corner < 60.0 {
+                    let braking = next_corner_speed + (STRAIGHT_SPEED - next_corner_speed) * to_corner / 60.0;
+                    (accelerated.min(braking), 0.0, 1.0)
+                } else {
+                    (accelerated, 1.0, 0.0)
+                }
+            };
+            let yaw_rate = if in_corner { speed / RADIUS } else { 0.0 };
+
+            let mut packet = PacketBuilder::new()
+                .packet_id(frame)
+                .speed_kmh(speed * 3.6)
+                .position(x, 0.0, z)
+                .throttle(throttle)
+                .brake(brake)
+                .build();
+            packet.car_info.position.angular_velocity = Vector3::new(0.0, yaw_rate, 0.0);
+            samples.push(LapSample { elapsed: Duration::from_secs_f32(frame as f32 * dt), packet });
+
+            distance += speed * dt;
+            heading += yaw_rate * dt;
+            x += heading.cos() * speed * dt;
+            z += heading.sin() * speed * dt;
+            frame += 1;
+        }
+
+        let computed_time = samples.last().unwrap().elapsed;
+        Lap {
+            number: 2,
+            kind: LapKind::Flying,
+            samples,
+            official_time: None,
+            computed_time,
+            invalidation: None,
+            fuel_used: 0.0,
+            max_speed_kmh: 180.0,
+            min_speed_kmh: 0.0,
+            dropped_packets: 0,
+        }
+    }
+
+    #[test]
+    fn test_detects_corners_and_straights() {
+        let reference = drive([20.0, 25.0]);
+        let analyzer = CornerAnalyzer::new(&reference, CornerConfig::default()).unwrap();
+        let kinds: Vec<_> = analyzer.segments().iter().map(|segment| segment.kind).collect();
+        use SegmentKind::*;
+        assert_eq!(kinds, [Straight, Corner, Straight, Corner]);
+        let corners: Vec<_> = analyzer.corners().copied().collect();
+        assert!((corners[0].start - 200.0).abs() < 10.0, "{:?}", corners[0]);
+        assert!((corners[0].heading_change - std::f32::consts::PI).abs() < 0.2, "{:?}", corners[0]);
+        assert_eq!(corners[1].number, 2);
+
+        let reports = analyzer.analyze(&reference);
+        assert_eq!(reports.len(), 2);
+        let first = &reports[0];
+        assert!((first.min_speed_kmh - 72.0).abs() < 0.5);
+        assert!((first.entry_speed_kmh - 72.0).abs() < 5.0, "{:?}", first);
+        assert!((first.braking_point.unwrap() - 140.0).abs() < 5.0, "{:?}", first);
+        // 弯道中点之后给油
+        let middle = 200.0 + std::f32::consts::PI * 20.0;
+        assert!((first.throttle_pickup.unwrap() - middle).abs() < 5.0, "{:?}", first);
+        assert!(reports.iter().all(|report| report.time_lost.abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_time_lost_against_reference() {
+        let reference = drive([20.0, 25.0]);
+        let slower = drive([20.0, 18.0]);
+        let analyzer = CornerAnalyzer::new(&reference, CornerConfig::default()).unwrap();
+        let reports = analyzer.analyze(&slower);
+        assert!(reports[0].time_lost.abs() < 0.1, "{:?}", reports[0]);
+        assert!(reports[1].time_lost > 1.0, "{:?}", reports[1]);
+        assert!((reports[1].min_speed_kmh - 64.8).abs() < 0.5);
+        let total: f32 = reports.iter().map(|report| report.time_lost).sum();
+        let lap_delta = slower.computed_time.as_secs_f32() - reference.computed_time.as_secs_f32();
+        assert!((total - lap_delta).abs() < 0.05, "{} vs {}", total, lap_delta);
+    }
+
+    #[test]
+    fn test_track_map_distances() {
+        let reference = drive([20.0, 25.0]);
+        let map = TrackMapBuilder::new("oval", 2.0).add_lap(&reference).build().unwrap();
+        let analyzer = CornerAnalyzer::with_track_map(&reference, CornerConfig::default(), map).unwrap();
+        assert_eq!(analyzer.corners().count(), 2);
+        let second = analyzer.corners().nth(1).unwrap();
+        let expected = 400.0 + std::f32::consts::PI * 40.0;
+        assert!((second.start - expected).abs() < 10.0, "{:?}", second);
+    }
+}
@@ -0,0 +1,219 @@
+//! GT7遥测数据包解密
+//!
+//! GT7发送的遥测数据包使用Salsa20加密 (参考gt7telemetry)
+//! 密钥固定，IV由数据包0x40偏移处的4字节种子派生
+
+use crate::error::{Result, GT7Error};
+use crate::types::PacketVariant;
+use salsa20::cipher::{KeyIvInit, StreamCipher};
+use salsa20::Salsa20;
+
+/// Salsa20密钥 ("Simulator Interface Packet GT7 ver 0.0"的前32字节)
+pub const GT7_SALSA20_KEY: &[u8; 32] = b"Simulator Interface Packet GT7 v";
+
+/// 解密后数据包的魔术字节 ("G7S0")
+pub const GT7_PACKET_MAGIC: u32 = 0x47375330;
+
+/// IV种子在数据包中的偏移
+pub const GT7_IV_OFFSET: usize = 0x40;
+
+/// IV种子的异或掩码 (心跳"A"，其它格式见 [`PacketVariant::iv_xor`])
+pub const GT7_IV_XOR: u32 = 0xDEADBEAF;
+
+/// 由IV种子派生Salsa20的8字节nonce
+///
+/// nonce = (种子 ^ 掩码) 的小端字节 + 种子的小端字节
+fn derive_nonce(seed: u32, xor_mask: u32) -> [u8; 8] {
+    let mut nonce = [0u8; 8];
+    nonce[..4].copy_from_slice(&(seed ^ xor_mask).to_le_bytes());
+    nonce[4..].copy_from_slice(&seed.to_le_bytes());
+    nonce
+}
+
+/// 对数据应用GT7的Salsa20密钥流 (加密和解密是同一操作)
+fn apply_keystream(data: &mut [u8], seed: u32, xor_mask: u32) {
+    let nonce = derive_nonce(seed, xor_mask);
+    let mut cipher = Salsa20::new(GT7_SALSA20_KEY.into(), &nonce.into());
+    cipher.apply_keystream(data);
+}
+
+/// 读取数据包中的IV种子
+fn read_iv_seed(data: &[u8]) -> Result<u32> {
+    data.get(GT7_IV_OFFSET..GT7_IV_OFFSET + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| GT7Error::packet_parse_error("IV种子", GT7_IV_OFFSET, 4))
+}
+
+/// 解密GT7遥测数据包
+///
+/// # 参数
+///
+/// * `data` - 从网络接收的加密数据
+/// * `variant` - 数据包格式 (决定IV异或掩码)
+///
+/// # 返回
+///
+/// 解密后的明文数据，魔术字节已验证
+pub fn decrypt_packet(data: &[u8], variant: PacketVariant) -> Result<Vec<u8>> {
+    let seed = read_iv_seed(data)?;
+
+    let mut plain = data.to_vec();
+    apply_keystream(&mut plain, seed, variant.iv_xor());
+
+    let magic = u32::from_le_bytes([plain[0], plain[1], plain[2], plain[3]]);
+    if magic != GT7_PACKET_MAGIC {
+        return Err(GT7Error::invalid_packet_format("magic"));
+    }
+
+    Ok(plain)
+}
+
+/// 加密GT7遥测数据包 (模拟主机发送的数据)
+///
+/// # 参数
+///
+/// * `plain` - 明文数据 (应以魔术字节开头)
+/// * `variant` - 数据包格式 (决定IV异或掩码)
+/// * `seed` - IV种子，加密后写入0x40偏移处
+pub fn encrypt_packet(plain: &[u8], variant: PacketVariant, seed: u32) -> Result<Vec<u8>> {
+    if plain.len() < GT7_IV_OFFSET + 4 {
+        return Err(GT7Error::packet_parse_error("IV种子", GT7_IV_OFFSET, 4));
+    }
+
+    let mut data = plain.to_vec();
+    apply_keystream(&mut data, seed, variant.iv_xor());
+    data[GT7_IV_OFFSET..GT7_IV_OFFSET + 4].copy_from_slice(&seed.to_le_bytes());
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 由独立的Salsa20实现生成的加密数据包
+    ///
+    /// 明文: 296字节，魔术字节 "G7S0"，0x70处 packet_id = 1234，其余为0
+    /// IV种子: 0x12345678
+    const ENCRYPTED_VECTOR: &[&str] = &[
+        "d732167a5ee1f35e1f1e7eeebd89834618ea7403795ab10b46e16357f013a4e0",
+        "fa87a81822addccf1b60daf1e777d31ded16c8cef2da66bbaed7b79378806a87",
+        "78563412e03058379e6a490cc9d440b0cae9c2208d458b38f422d95796ad2ba5",
+        "1496b79fb7d56f41a12e4b6b2a8248948d04e6af98a1d465bdcdcc4bdbea70e9",
+        "79684bb3227b4c8ba1b4e150af9c7b3583776f5b3b68c517c4d5c51dfe0e221c",
+        "0ed2d88e290150f14cf7454536c8dd0dc4642a6f3b8ebedb01a70e1dce8eb1e3",
+        "09212490ea6f18c6a0a9eb175fc1bbeca0a0f8db419e910acfb7ec38660eb737",
+        "7cf8766a371b60b9990d5dadf66b4053c409a9be8a31b809507f4c621882aa1c",
+        "5a31f1266a9e838e013e0d5d64fea8cd52d4d49769114345e00266b51d5f3fd4",
+        "22a08894d5cabd67",
+    ];
+
+    /// 心跳"~"格式的加密数据包 (344字节)
+    ///
+    /// 明文: packet_id = 77，sway = 0.25，过滤后油门 = 200，能量回收 = 1.5
+    /// IV种子: 0xCAFEBABE
+    const ENCRYPTED_TILDE_VECTOR: &[&str] = &[
+        "78a02c1e9d22ffae72602bc184676b4d82bf1a08cf38c5d2fdb880750f837d79",
+        "7807e5451922fc277cb3389165428495183081cee189b16af4bd49c3d408cd13",
+        "bebafecafba28c3b4092471c3036eacb852ecc9bd7e62892fb710633cf2d6b2a",
+        "18ad5b6fab58f5858bf378fd805d5575770e105532bbed6a5295b6e4d45095d2",
+        "03a39620067de0a4383daf0d6c341b8a5227903a0dc4553921b255d8d6d6fbb4",
+        "c50a0b126a29cdc48e716de99309a4e3acd5e2fc15ff39d0b423e46c8899a4c3",
+        "c2534d5eda61234e1309e98cdac3c12677fb4a9d7bc1bba4f28b208a70273d91",
+        "a67e8896f0f35f1d8c11c2d46c0b3e354fcc23d26441d9a94bfe3186577d68a1",
+        "c3f107cc6480c6efbc7569ece4373a2ecbfa8bf5da91754f57225c7d3146d143",
+        "6bd0185042607d91541db7bf5d49a611f43e721582e947b1a3078a2067a91d08",
+        "cd0cceb78e71e344f3d3397919dbc498dd2d759d2c6b4727",
+    ];
+
+    fn decode_hex(lines: &[&str]) -> Vec<u8> {
+        let hex: String = lines.concat();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_nonce_derivation() {
+        let nonce = derive_nonce(0x12345678, GT7_IV_XOR);
+        assert_eq!(nonce, [0xD7, 0xE8, 0x99, 0xCC, 0x78, 0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn test_salsa20_keystream() {
+        // ECRYPT Salsa20/20 测试向量 (Set 1, vector 0)
+        let mut key = [0u8; 32];
+        key[0] = 0x80;
+        let mut cipher = Salsa20::new(&key.into(), &[0u8; 8].into());
+        let mut block = [0u8; 8];
+        cipher.apply_keystream(&mut block);
+        assert_eq!(block, [0xE3, 0xBE, 0x8F, 0xDD, 0x8B, 0xEC, 0xA2, 0xE3]);
+    }
+
+    #[test]
+    fn test_decrypt_vector() {
+        let data = decode_hex(ENCRYPTED_VECTOR);
+        assert_eq!(data.len(), crate::GT7_PACKET_SIZE);
+
+        let plain = decrypt_packet(&data, PacketVariant::A).unwrap();
+        assert_eq!(&plain[0..4], b"0S7G");
+        assert_eq!(i32::from_le_bytes([plain[0x70], plain[0x71], plain[0x72], plain[0x73]]), 1234);
+
+        // 除魔术字节、IV种子位置和packet_id外其余明文均为0
+        for (i, byte) in plain.iter().enumerate() {
+            let skip = i < 4 || (GT7_IV_OFFSET..GT7_IV_OFFSET + 4).contains(&i) || (0x70..0x74).contains(&i);
+            if !skip {
+                assert_eq!(*byte, 0, "偏移 0x{:X} 处应为0", i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_packet_from_encrypted_bytes() {
+        let data = decode_hex(ENCRYPTED_VECTOR);
+        let packet = crate::GT7TelemetryPacket::from_bytes(&data).unwrap();
+        assert_eq!(packet.packet_id, 1234);
+    }
+
+    #[test]
+    fn test_tilde_packet_from_encrypted_bytes() {
+        let data = decode_hex(ENCRYPTED_TILDE_VECTOR);
+        assert_eq!(data.len(), PacketVariant::Tilde.packet_size());
+
+        // 使用错误的IV掩码无法解密
+        assert!(decrypt_packet(&data, PacketVariant::B).is_err());
+
+        let packet = crate::GT7TelemetryPacket::from_bytes(&data).unwrap();
+        assert_eq!(packet.variant, PacketVariant::Tilde);
+        assert_eq!(packet.packet_id, 77);
+        assert_eq!(packet.motion.unwrap().sway, 0.25);
+
+        let inputs = packet.inputs.unwrap();
+        assert_eq!(inputs.throttle_filtered, 200.0 / 255.0);
+        assert_eq!(inputs.energy_recovery, 1.5);
+    }
+
+    #[test]
+    fn test_encrypt_matches_vector() {
+        let mut plain = vec![0u8; crate::GT7_PACKET_SIZE];
+        plain[0..4].copy_from_slice(&GT7_PACKET_MAGIC.to_le_bytes());
+        plain[0x70..0x74].copy_from_slice(&1234i32.to_le_bytes());
+
+        let data = encrypt_packet(&plain, PacketVariant::A, 0x12345678).unwrap();
+        assert_eq!(data, decode_hex(ENCRYPTED_VECTOR));
+    }
+
+    #[test]
+    fn test_decrypt_rejects_bad_magic() {
+        let mut data = decode_hex(ENCRYPTED_VECTOR);
+        data[0] ^= 0xFF;
+        assert!(matches!(decrypt_packet(&data, PacketVariant::A), Err(GT7Error::InvalidPacketFormat { .. })));
+    }
+
+    #[test]
+    fn test_decrypt_rejects_short_data() {
+        let data = [0u8; 0x20];
+        assert!(decrypt_packet(&data, PacketVariant::A).unwrap_err().is_packet_error());
+    }
+}
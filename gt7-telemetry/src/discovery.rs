@@ -0,0 +1,455 @@
+//! 局域网主机发现
+//!
+//! 两种方式查找局域网内的GT7主机:
+//! - 向子网内的主机 (或广播地址) 的33739端口发送心跳，记录回复了合法遥测数据包的主机
+//! - PlayStation SRCH 发现协议 (PS4: UDP 987，PS5: UDP 9302)，获取主机名、机型、
+//!   系统版本和待机状态
+
+use crate::client::bind_reusable_socket;
+use crate::error::{Result, GT7Error};
+use crate::packet::GT7TelemetryPacket;
+use crate::types::{GameStateType, PacketVariant};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::time::{interval, timeout_at};
+
+/// PS4 SRCH发现端口
+pub const PS4_DISCOVERY_PORT: u16 = 987;
+
+/// PS5 SRCH发现端口
+pub const PS5_DISCOVERY_PORT: u16 = 9302;
+
+/// PS4 SRCH协议版本
+const PS4_PROTOCOL_VERSION: &str = "00020020";
+
+/// PS5 SRCH协议版本
+const PS5_PROTOCOL_VERSION: &str = "00030010";
+
+/// 子网扫描允许的最短前缀长度 (最多65534个主机)
+const MIN_SCAN_PREFIX: u8 = 16;
+
+/// 遥测扫描选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryScanOptions {
+    /// 主机心跳端口
+    pub heartbeat_port: u16,
+    /// 本地接收端口 (主机发送到心跳来源IP的33740端口; None表示使用随机端口，用于回复来源端口的模拟器)
+    pub listen_port: Option<u16>,
+    /// 请求的数据包格式
+    pub variant: PacketVariant,
+    /// 扫描总时长
+    pub timeout: Duration,
+    /// 向尚未回复的主机重发心跳的间隔
+    pub resend_interval: Duration,
+}
+
+impl Default for TelemetryScanOptions {
+    fn default() -> Self {
+        Self {
+            heartbeat_port: crate::GT7_HEARTBEAT_PORT,
+            listen_port: Some(crate::GT7_TELEMETRY_PORT),
+            variant: PacketVariant::default(),
+            timeout: Duration::from_secs(2),
+            resend_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// 回复了遥测数据的主机
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredConsole {
+    /// 主机地址
+    pub address: IpAddr,
+    /// 数据包格式
+    pub variant: PacketVariant,
+    /// 首个数据包的序号
+    pub packet_id: u32,
+    /// 车辆代码
+    pub car_code: u32,
+    /// 游戏状态
+    pub game_state: GameStateType,
+    /// 从开始扫描到收到首个数据包的时间
+    pub response_time: Duration,
+}
+
+/// SRCH发现选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct SrchOptions {
+    /// PS4发现端口
+    pub ps4_port: u16,
+    /// PS5发现端口
+    pub ps5_port: u16,
+    /// 发现总时长
+    pub timeout: Duration,
+    /// 向尚未回复的主机重发请求的间隔
+    pub resend_interval: Duration,
+}
+
+impl Default for SrchOptions {
+    fn default() -> Self {
+        Self {
+            ps4_port: PS4_DISCOVERY_PORT,
+            ps5_port: PS5_DISCOVERY_PORT,
+            timeout: Duration::from_secs(2),
+            resend_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// 主机类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HostType {
+    PS4,
+    PS5,
+    /// 其它设备类型
+    Other(String),
+}
+
+/// 主机电源状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerStatus {
+    /// 已开机 (HTTP 200)
+    Awake,
+    /// 待机 (HTTP 620)
+    Standby,
+    /// 未知状态码
+    Unknown(u16),
+}
+
+/// SRCH协议发现的PlayStation主机
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayStationInfo {
+    /// 主机地址
+    pub address: IpAddr,
+    /// 主机ID
+    pub host_id: String,
+    /// 主机名
+    pub host_name: String,
+    /// 主机类型
+    pub host_type: HostType,
+    /// 系统版本 (如 "07020001")
+    pub system_version: String,
+    /// 电源状态
+    pub status: PowerStatus,
+    /// 远程请求端口
+    pub request_port: Option<u16>,
+    /// 正在运行的应用名称
+    pub running_app_name: Option<String>,
+    /// 正在运行的应用ID
+    pub running_app_title_id: Option<String>,
+}
+
+impl PlayStationInfo {
+    /// 是否正在运行GT7 (按应用名称判断)
+    pub fn is_running_gt7(&self) -> bool {
+        self.running_app_name
+            .as_deref()
+            .is_some_and(|name| name.contains("Gran Turismo 7"))
+    }
+}
+
+/// 列出IPv4子网内的所有主机地址 (不含网络地址和广播地址)
+pub fn subnet_hosts(network: Ipv4Addr, prefix_len: u8) -> Result<Vec<IpAddr>> {
+    if !(MIN_SCAN_PREFIX..=32).contains(&prefix_len) {
+        return Err(GT7Error::config_error(
+            "prefix_len",
+            prefix_len.to_string(),
+            format!("子网前缀长度必须在 {}-32 之间", MIN_SCAN_PREFIX),
+        ));
+    }
+
+    let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+    let first = u32::from(network) & mask;
+    let last = first | !mask;
+    let hosts = if prefix_len >= 31 { first..=last } else { first + 1..=last - 1 };
+    Ok(hosts.map(|ip| IpAddr::V4(Ipv4Addr::from(ip))).collect())
+}
+
+/// 构造SRCH请求
+pub fn srch_request(protocol_version: &str) -> Vec<u8> {
+    format!("SRCH * HTTP/1.1\ndevice-discovery-protocol-version:{}\n", protocol_version).into_bytes()
+}
+
+/// 解析SRCH回复
+pub fn parse_srch_response(address: IpAddr, data: &[u8]) -> Result<PlayStationInfo> {
+    let text = std::str::from_utf8(data).map_err(|_| GT7Error::invalid_packet_format("srch"))?;
+    let mut lines = text.lines();
+
+    // 状态行: "HTTP/1.1 200 Ok" 或 "HTTP/1.1 620 Server Standby"
+    let status_code: u16 = lines
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| GT7Error::invalid_packet_format("srch_status"))?;
+    let status = match status_code {
+        200 => PowerStatus::Awake,
+        620 => PowerStatus::Standby,
+        code => PowerStatus::Unknown(code),
+    };
+
+    let headers: HashMap<&str, &str> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+    let header = |key: &str| headers.get(key).map(|value| value.to_string());
+
+    let host_type = match header("host-type").as_deref() {
+        Some("PS4") => HostType::PS4,
+        Some("PS5") => HostType::PS5,
+        Some(other) => HostType::Other(other.to_string()),
+        None => return Err(GT7Error::invalid_packet_format("host-type")),
+    };
+
+    Ok(PlayStationInfo {
+        address,
+        host_id: header("host-id").unwrap_or_default(),
+        host_name: header("host-name").unwrap_or_default(),
+        host_type,
+        system_version: header("system-version").unwrap_or_default(),
+        status,
+        request_port: header("host-request-port").and_then(|port| port.parse().ok()),
+        running_app_name: header("running-app-name"),
+        running_app_title_id: header("running-app-titleid"),
+    })
+}
+
+/// 向目标主机发送心跳，返回回复了合法遥测数据包的主机
+///
+/// `targets` 可以包含子网广播地址 (见 [`subnet_hosts`])
+///
+/// 接收端口允许复用，客户端通过 `shared_socket_port` 占用同一端口时扫描仍可进行
+/// (Linux上回复可能被分配给客户端的套接字)
+pub async fn scan_telemetry(targets: &[IpAddr], options: &TelemetryScanOptions) -> Result<Vec<DiscoveredConsole>> {
+    let socket = bind_probe_socket(targets, options.listen_port.unwrap_or(0)).await?;
+    let heartbeat = options.variant.heartbeat().to_vec();
+    let requests: Vec<_> = targets
+        .iter()
+        .map(|&ip| (SocketAddr::new(ip, options.heartbeat_port), heartbeat.clone()))
+        .collect();
+
+    let started = Instant::now();
+    let found = probe(&socket, &requests, options.timeout, options.resend_interval, |from, data| {
+        let packet = GT7TelemetryPacket::from_bytes(data).ok()?;
+        Some(DiscoveredConsole {
+            address: from.ip(),
+            variant: packet.variant,
+            packet_id: packet.packet_id,
+            car_code: packet.car_info.car_code,
+            game_state: packet.game_state.state_type,
+            response_time: started.elapsed(),
+        })
+    })
+    .await;
+
+    log::info!("遥测扫描完成: {} 个目标中 {} 个主机有回复", targets.len(), found.len());
+    Ok(found)
+}
+
+/// 使用SRCH协议查找PlayStation主机
+///
+/// 同时向每个目标的PS4和PS5发现端口发送请求
+pub async fn discover_playstations(targets: &[IpAddr], options: &SrchOptions) -> Result<Vec<PlayStationInfo>> {
+    let socket = bind_probe_socket(targets, 0).await?;
+    let requests: Vec<_> = targets
+        .iter()
+        .flat_map(|&ip| {
+            [
+                (SocketAddr::new(ip, options.ps4_port), srch_request(PS4_PROTOCOL_VERSION)),
+                (SocketAddr::new(ip, options.ps5_port), srch_request(PS5_PROTOCOL_VERSION)),
+            ]
+        })
+        .collect();
+
+    let found = probe(&socket, &requests, options.timeout, options.resend_interval, |from, data| {
+        parse_srch_response(from.ip(), data)
+            .inspect_err(|e| log::debug!("忽略来自 {} 的无效SRCH回复: {}", from, e))
+            .ok()
+    })
+    .await;
+
+    log::info!("SRCH发现完成: 找到 {} 台主机", found.len());
+    Ok(found)
+}
+
+/// 绑定探测用的套接字 (允许广播，与客户端的共享套接字一样允许端口复用)
+async fn bind_probe_socket(targets: &[IpAddr], port: u16) -> Result<UdpSocket> {
+    let ip = if !targets.is_empty() && targets.iter().all(IpAddr::is_ipv6) {
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    };
+    let socket = bind_reusable_socket(SocketAddr::new(ip, port))?;
+    socket.set_broadcast(true)?;
+    Ok(socket)
+}
+
+/// 周期性地向尚未回复的目标发送请求，收集每个来源IP的第一个有效回复
+async fn probe<T>(
+    socket: &UdpSocket,
+    requests: &[(SocketAddr, Vec<u8>)],
+    duration: Duration,
+    resend_interval: Duration,
+    mut parse: impl FnMut(SocketAddr, &[u8]) -> Option<T>,
+) -> Vec<T> {
+    let deadline = tokio::time::Instant::now() + duration;
+    let mut resend = interval(resend_interval);
+    let mut found: HashMap<IpAddr, T> = HashMap::new();
+    let mut order = Vec::new();
+    let mut buffer = [0u8; 2048];
+
+    loop {
+        tokio::select! {
+            _ = resend.tick() => {
+                for (target, payload) in requests {
+                    if found.contains_key(&target.ip()) {
+                        continue;
+                    }
+                    if let Err(e) = socket.send_to(payload, target).await {
+                        log::debug!("发送探测到 {} 失败: {}", target, e);
+                    }
+                }
+            }
+            received = timeout_at(deadline, socket.recv_from(&mut buffer)) => {
+                let (size, from) = match received {
+                    Ok(Ok(received)) => received,
+                    Ok(Err(e)) => {
+                        log::debug!("接收探测回复失败: {}", e);
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                if found.contains_key(&from.ip()) {
+                    continue;
+                }
+                if let Some(result) = parse(from, &buffer[..size]) {
+                    log::debug!("发现主机: {}", from.ip());
+                    order.push(from.ip());
+                    found.insert(from.ip(), result);
+                }
+            }
+        }
+    }
+
+    order.into_iter().filter_map(|ip| found.remove(&ip)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{ConsoleSimulator, ScriptedLap, SimulatorConfig};
+
+    const PS5_RESPONSE: &str = "HTTP/1.1 200 Ok\n\
+        host-id:0123456789AB\n\
+        host-type:PS5\n\
+        host-name:Living Room\n\
+        host-request-port:997\n\
+        device-discovery-protocol-version:00030010\n\
+        system-version:09000000\n\
+        running-app-name:Gran Turismo 7\n\
+        running-app-titleid:PPSA01316\n";
+
+    #[test]
+    fn test_subnet_hosts() {
+        let hosts = subnet_hosts(Ipv4Addr::new(192, 168, 1, 77), 24).unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(hosts[253], IpAddr::V4(Ipv4Addr::new(192, 168, 1, 254)));
+        assert_eq!(subnet_hosts(Ipv4Addr::new(10, 0, 0, 5), 32).unwrap().len(), 1);
+        assert!(subnet_hosts(Ipv4Addr::new(10, 0, 0, 0), 8).is_err());
+    }
+
+    #[test]
+    fn test_parse_srch_response() {
+        let address = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 30));
+        let info = parse_srch_response(address, PS5_RESPONSE.as_bytes()).unwrap();
+        assert_eq!(info.host_type, HostType::PS5);
+        assert_eq!(info.host_name, "Living Room");
+        assert_eq!(info.system_version, "09000000");
+        assert_eq!(info.status, PowerStatus::Awake);
+        assert_eq!(info.request_port, Some(997));
+        assert!(info.is_running_gt7());
+
+        let standby = "HTTP/1.1 620 Server Standby\nhost-type:PS4\nhost-name:PS4-123\nsystem-version:07020001\n";
+        let info = parse_srch_response(address, standby.as_bytes()).unwrap();
+        assert_eq!(info.host_type, HostType::PS4);
+        assert_eq!(info.status, PowerStatus::Standby);
+        assert_eq!(info.running_app_name, None);
+
+        assert!(parse_srch_response(address, b"garbage").is_err());
+        assert!(parse_srch_response(address, b"HTTP/1.1 200 Ok\nhost-name:x\n").is_err());
+    }
+
+    /// 本地SRCH应答程序，只回复指定协议版本的请求
+    async fn start_srch_responder(protocol_version: &'static str, response: &'static str) -> u16 {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            while let Ok((size, from)) = socket.recv_from(&mut buffer).await {
+                let request = String::from_utf8_lossy(&buffer[..size]);
+                if request.starts_with("SRCH") && request.contains(protocol_version) {
+                    let _ = socket.send_to(response.as_bytes(), from).await;
+                }
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_discover_playstations_against_local_responder() {
+        let ps5_port = start_srch_responder(PS5_PROTOCOL_VERSION, PS5_RESPONSE).await;
+        let ps4_port = start_srch_responder(PS4_PROTOCOL_VERSION, "not a srch reply").await;
+        let options = SrchOptions {
+            ps4_port,
+            ps5_port,
+            timeout: Duration::from_millis(300),
+            ..Default::default()
+        };
+
+        let found = discover_playstations(&[IpAddr::V4(Ipv4Addr::LOCALHOST)], &options).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].host_type, HostType::PS5);
+        assert_eq!(found[0].host_id, "0123456789AB");
+    }
+
+    #[tokio::test]
+    async fn test_scan_telemetry_against_simulator() {
+        let config = SimulatorConfig {
+            bind_address: "127.0.0.1:0".parse().unwrap(),
+            reply_port: None,
+            ..Default::default()
+        };
+        let simulator = ConsoleSimulator::bind(config, Box::new(ScriptedLap::new(3))).await.unwrap();
+        let port = simulator.local_addr().unwrap().port();
+        tokio::spawn(simulator.run());
+
+        let options = TelemetryScanOptions {
+            heartbeat_port: port,
+            listen_port: None,
+            variant: PacketVariant::B,
+            timeout: Duration::from_millis(500),
+            ..Default::default()
+        };
+        // 127.0.0.2 上没有模拟器，不应出现在结果中
+        let targets = [IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2))];
+
+        let found = scan_telemetry(&targets, &options).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(found[0].variant, PacketVariant::B);
+        assert_eq!(found[0].game_state, GameStateType::InRace);
+    }
+
+    #[tokio::test]
+    async fn test_probe_socket_shares_port_with_client() {
+        // 客户端的共享套接字占用接收端口时，扫描仍能绑定同一端口
+        let shared = bind_reusable_socket("0.0.0.0:0".parse().unwrap()).unwrap();
+        let port = shared.local_addr().unwrap().port();
+        let probe = bind_probe_socket(&[IpAddr::V4(Ipv4Addr::LOCALHOST)], port).await.unwrap();
+        assert_eq!(probe.local_addr().unwrap().port(), port);
+    }
+}
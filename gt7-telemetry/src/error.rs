@@ -39,9 +39,13 @@ pub enum GT7Error {
     PacketVersionMismatch { expected: u16, actual: u16 },
 
     /// 无效的IP地址
-    #[error("无效的IP地址: {ip} (必须是有效的IPv4地址)")]
+    #[error("无效的IP地址: {ip} (必须是有效的IP地址或可解析的主机名)")]
     InvalidIPAddress { ip: String },
 
+    /// 地址不在允许范围内
+    #[error("地址 {ip} 不在允许范围内 (仅允许局域网地址和配置的网段)")]
+    AddressNotAllowed { ip: String },
+
     /// 无效的端口
     #[error("无效的端口: {port} (有效范围: 1-65535)")]
     InvalidPort { port: u16 },
@@ -126,6 +130,11 @@ impl GT7Error {
         Self::InvalidIPAddress { ip: ip.into() }
     }
 
+    /// 创建地址不允许错误
+    pub fn address_not_allowed(ip: impl Into<String>) -> Self {
+        Self::AddressNotAllowed { ip: ip.into() }
+    }
+
     /// 创建无效端口错误
     pub fn invalid_port(port: u16) -> Self {
         Self::InvalidPort { port }
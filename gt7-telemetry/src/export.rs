@@ -0,0 +1,739 @@
+//! 遥测数据导出
+//!
+//! 把嵌套的 [`GT7TelemetryPacket`] 展开成列式的行，写出CSV、JSON Lines或Parquet文件，
+//! 方便在pandas或Excel中分析。支持选择导出的列以及按圈拆分文件
+
+use crate::error::{Result, GT7Error};
+use crate::packet::GT7TelemetryPacket;
+use crate::replay::ReplaySource;
+use crate::recorder::RECORDING_EXTENSION;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// CSV (带表头，缺失值为空)
+    #[default]
+    Csv,
+    /// JSON Lines (每行一个对象，缺失值为null)
+    JsonLines,
+    /// Apache Parquet (需要 `parquet` 特性)
+    Parquet,
+}
+
+impl ExportFormat {
+    /// 文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+            Self::Parquet => "parquet",
+        }
+    }
+
+    /// 根据文件扩展名识别格式
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        path.as_ref().extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = GT7Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "ndjson" | "json-lines" => Ok(Self::JsonLines),
+            "parquet" | "pq" => Ok(Self::Parquet),
+            _ => Err(GT7Error::config_error("format", s, "支持的格式: csv, jsonl, parquet")),
+        }
+    }
+}
+
+/// 列的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// 布尔值
+    Bool,
+    /// 整数
+    Int,
+    /// 浮点数
+    Float,
+}
+
+/// 单元格的值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    /// 布尔值
+    Bool(bool),
+    /// 整数
+    Int(i64),
+    /// 浮点数
+    Float(f32),
+    /// 缺失 (例如不在赛道上时的圈数、A格式数据包的运动数据)
+    Null,
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+macro_rules! impl_int_value {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for Value {
+            fn from(value: $ty) -> Self {
+                Self::Int(value as i64)
+            }
+        })*
+    };
+}
+
+impl_int_value!(i8, u8, u16, u32, u64);
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Null => Ok(()),
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Bool(value) => value.into(),
+            Value::Int(value) => value.into(),
+            // NaN和无穷大在JSON中没有表示，输出为null
+            Value::Float(value) => serde_json::Number::from_f64(f64::from(value)).map_or(Self::Null, Self::Number),
+            Value::Null => Self::Null,
+        }
+    }
+}
+
+/// 导出列
+#[derive(Clone, Copy)]
+pub struct Column {
+    /// 列名
+    pub name: &'static str,
+    /// 数据类型
+    pub kind: ColumnType,
+    extract: fn(&GT7TelemetryPacket) -> Value,
+}
+
+impl Column {
+    /// 取出数据包中该列的值
+    pub fn value(&self, packet: &GT7TelemetryPacket) -> Value {
+        (self.extract)(packet)
+    }
+}
+
+impl fmt::Debug for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Column").field("name", &self.name).field("kind", &self.kind).finish()
+    }
+}
+
+macro_rules! column {
+    ($name:expr, $kind:ident, |$p:ident| $value:expr) => {
+        Column {
+            name: $name,
+            kind: ColumnType::$kind,
+            extract: |$p| Value::from($value),
+        }
+    };
+}
+
+macro_rules! tyre_columns {
+    ($corner:ident, $prefix:literal) => {
+        [
+            column!(concat!("tyre_", $prefix, "_temperature"), Float, |p| p.car_info.tires.$corner.temperature),
+            column!(concat!("tyre_", $prefix, "_wear"), Float, |p| p.car_info.tires.$corner.wear),
+            column!(concat!("tyre_", $prefix, "_suspension_travel"), Float, |p| p.car_info.tires.$corner.suspension_travel),
+            column!(concat!("tyre_", $prefix, "_wheel_speed"), Float, |p| p.car_info.tires.$corner.wheel_speed),
+            column!(concat!("tyre_", $prefix, "_radius"), Float, |p| p.car_info.tires.$corner.radius),
+        ]
+    };
+}
+
+const BASE_COLUMNS: &[Column] = &[
+    column!("packet_id", Int, |p| p.packet_id),
+    column!("time_of_day_ms", Int, |p| p.timestamp),
+    // 状态
+    column!("car_on_track", Bool, |p| p.game_state.flags.car_on_track),
+    column!("paused", Bool, |p| p.game_state.is_paused),
+    column!("loading", Bool, |p| p.game_state.flags.loading_or_processing),
+    column!("in_gear", Bool, |p| p.game_state.flags.in_gear),
+    column!("rev_limiter_alert", Bool, |p| p.game_state.flags.rev_limiter_alert),
+    column!("handbrake", Bool, |p| p.game_state.flags.handbrake),
+    column!("asm_active", Bool, |p| p.game_state.flags.asm_active),
+    column!("tcs_active", Bool, |p| p.game_state.flags.tcs_active),
+    // 比赛
+    column!("current_lap", Int, |p| p.game_state.race_info.as_ref().map(|r| r.current_lap)),
+    column!("total_laps", Int, |p| p.game_state.race_info.as_ref().map(|r| r.total_laps)),
+    column!("race_position", Int, |p| p.game_state.race_info.as_ref().map(|r| r.position)),
+    column!("total_participants", Int, |p| p.game_state.race_info.as_ref().map(|r| r.total_participants)),
+    column!("best_lap_time_ms", Int, |p| p.game_state.race_info.as_ref().and_then(|r| r.best_lap_time)),
+    column!("last_lap_time_ms", Int, |p| p.game_state.race_info.as_ref().and_then(|r| r.last_lap_time)),
+    column!("current_lap_time_ms", Int, |p| p.game_state.race_info.as_ref().map(|r| r.current_lap_time)),
+    column!("track_progress", Float, |p| p.game_state.race_info.as_ref().map(|r| r.track_progress)),
+    // 车辆
+    column!("car_code", Int, |p| p.car_info.car_code),
+    column!("speed", Float, |p| p.car_info.speed),
+    column!("speed_kmh", Float, |p| p.car_info.speed * 3.6),
+    column!("max_speed_kmh", Int, |p| p.car_info.max_speed),
+    column!("ride_height", Float, |p| p.car_info.ride_height),
+    column!("position_x", Float, |p| p.car_info.position.world.x),
+    column!("position_y", Float, |p| p.car_info.position.world.y),
+    column!("position_z", Float, |p| p.car_info.position.world.z),
+    column!("velocity_x", Float, |p| p.car_info.position.velocity.x),
+    column!("velocity_y", Float, |p| p.car_info.position.velocity.y),
+    column!("velocity_z", Float, |p| p.car_info.position.velocity.z),
+    column!("angular_velocity_x", Float, |p| p.car_info.position.angular_velocity.x),
+    column!("angular_velocity_y", Float, |p| p.car_info.position.angular_velocity.y),
+    column!("angular_velocity_z", Float, |p| p.car_info.position.angular_velocity.z),
+    column!("pitch", Float, |p| p.car_info.position.rotation.x),
+    column!("yaw", Float, |p| p.car_info.position.rotation.y),
+    column!("roll", Float, |p| p.car_info.position.rotation.z),
+    column!("orientation", Float, |p| p.car_info.position.orientation),
+    // 发动机与输入
+    column!("rpm", Float, |p| p.car_info.engine.rpm),
+    column!("max_rpm", Float, |p| p.car_info.engine.max_rpm),
+    column!("min_alert_rpm", Float, |p| p.car_info.engine.min_alert_rpm),
+    column!("throttle", Float, |p| p.car_info.engine.throttle),
+    column!("brake", Float, |p| p.car_info.engine.brake),
+    column!("clutch", Float, |p| p.car_info.engine.clutch),
+    column!("clutch_engagement", Float, |p| p.car_info.engine.clutch_engagement),
+    column!("rpm_after_clutch", Float, |p| p.car_info.engine.rpm_after_clutch),
+    column!("gear", Int, |p| p.car_info.engine.gear),
+    column!("suggested_gear", Int, |p| p.car_info.engine.suggested_gear),
+    column!("boost", Float, |p| p.car_info.engine.boost),
+    column!("oil_pressure", Float, |p| p.car_info.engine.oil_pressure),
+    column!("oil_temperature", Float, |p| p.car_info.engine.oil_temperature),
+    column!("water_temperature", Float, |p| p.car_info.engine.water_temperature),
+    column!("fuel_remaining", Float, |p| p.car_info.engine.fuel_remaining),
+    column!("fuel_capacity", Float, |p| p.car_info.engine.fuel_capacity),
+    column!("fuel_level", Float, |p| p.car_info.engine.fuel_level),
+    column!("fuel_consumption", Float, |p| p.car_info.engine.fuel_consumption),
+    // 变速箱
+    column!("top_speed_ratio", Float, |p| p.car_info.transmission.top_speed_ratio),
+    column!("gear_ratio_1", Float, |p| p.car_info.transmission.gear_ratios[0]),
+    column!("gear_ratio_2", Float, |p| p.car_info.transmission.gear_ratios[1]),
+    column!("gear_ratio_3", Float, |p| p.car_info.transmission.gear_ratios[2]),
+    column!("gear_ratio_4", Float, |p| p.car_info.transmission.gear_ratios[3]),
+    column!("gear_ratio_5", Float, |p| p.car_info.transmission.gear_ratios[4]),
+    column!("gear_ratio_6", Float, |p| p.car_info.transmission.gear_ratios[5]),
+    column!("gear_ratio_7", Float, |p| p.car_info.transmission.gear_ratios[6]),
+    column!("gear_ratio_8", Float, |p| p.car_info.transmission.gear_ratios[7]),
+    // 路面
+    column!("road_plane_x", Float, |p| p.track_info.road_plane.x),
+    column!("road_plane_y", Float, |p| p.track_info.road_plane.y),
+    column!("road_plane_z", Float, |p| p.track_info.road_plane.z),
+    column!("road_plane_distance", Float, |p| p.track_info.road_plane_distance),
+    // 附加运动数据 (心跳"B"及以上)
+    column!("wheel_rotation", Float, |p| p.motion.as_ref().map(|m| m.wheel_rotation)),
+    column!("sway", Float, |p| p.motion.as_ref().map(|m| m.sway)),
+    column!("heave", Float, |p| p.motion.as_ref().map(|m| m.heave)),
+    column!("surge", Float, |p| p.motion.as_ref().map(|m| m.surge)),
+    // 附加输入数据 (心跳"~")
+    column!("throttle_filtered", Float, |p| p.inputs.as_ref().map(|i| i.throttle_filtered)),
+    column!("brake_filtered", Float, |p| p.inputs.as_ref().map(|i| i.brake_filtered)),
+    column!("energy_recovery", Float, |p| p.inputs.as_ref().map(|i| i.energy_recovery)),
+];
+
+const TYRE_COLUMNS: [[Column; 5]; 4] = [
+    tyre_columns!(front_left, "fl"),
+    tyre_columns!(front_right, "fr"),
+    tyre_columns!(rear_left, "rl"),
+    tyre_columns!(rear_right, "rr"),
+];
+
+/// 所有可导出的列 (按默认导出顺序)
+pub fn all_columns() -> Vec<&'static Column> {
+    BASE_COLUMNS.iter().chain(TYRE_COLUMNS.iter().flatten()).collect()
+}
+
+/// 按列名选择列，`names` 为空时返回全部列
+pub fn select_columns(names: &[impl AsRef<str>]) -> Result<Vec<&'static Column>> {
+    let all = all_columns();
+    if names.is_empty() {
+        return Ok(all);
+    }
+    names
+        .iter()
+        .map(|name| {
+            let name = name.as_ref().trim();
+            all.iter()
+                .find(|column| column.name == name)
+                .copied()
+                .ok_or_else(|| GT7Error::config_error("columns", name, "未知的列"))
+        })
+        .collect()
+}
+
+/// 导出选项
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// 导出格式
+    pub format: ExportFormat,
+    /// 导出的列名 (为空时导出全部列)
+    pub columns: Vec<String>,
+    /// 是否每圈写一个文件 (`<名称>-lap<圈数>.<扩展名>`，不在赛道上的数据包归入第0圈)
+    pub split_laps: bool,
+}
+
+/// 单个表格文件的写入器
+pub struct TableWriter<W: Write + Send = BufWriter<File>> {
+    columns: Vec<&'static Column>,
+    sink: Sink<W>,
+    rows: u64,
+}
+
+enum Sink<W: Write + Send> {
+    Csv(Box<csv::Writer<W>>),
+    JsonLines(W),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<parquet_sink::ParquetSink<W>>),
+}
+
+impl TableWriter {
+    /// 创建导出文件
+    pub fn create(path: impl AsRef<Path>, format: ExportFormat, columns: Vec<&'static Column>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .map_err(|e| GT7Error::file_error(format!("创建导出文件 {}: {}", path.display(), e)))?;
+        Self::new(BufWriter::new(file), format, columns)
+    }
+}
+
+impl<W: Write + Send> TableWriter<W> {
+    /// 写入到任意输出
+    pub fn new(writer: W, format: ExportFormat, columns: Vec<&'static Column>) -> Result<Self> {
+        let sink = match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(columns.iter().map(|column| column.name)).map_err(csv_error)?;
+                Sink::Csv(Box::new(writer))
+            }
+            ExportFormat::JsonLines => Sink::JsonLines(writer),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => Sink::Parquet(Box::new(parquet_sink::ParquetSink::new(writer, &columns)?)),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => {
+                return Err(GT7Error::config_error("format", "parquet", "编译时未启用parquet特性"))
+            }
+        };
+        Ok(Self { columns, sink, rows: 0 })
+    }
+
+    /// 导出的列
+    pub fn columns(&self) -> &[&'static Column] {
+        &self.columns
+    }
+
+    /// 已写入的行数
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// 写入一个数据包
+    pub fn write(&mut self, packet: &GT7TelemetryPacket) -> Result<()> {
+        let values = self.columns.iter().map(|column| column.value(packet));
+        match &mut self.sink {
+            Sink::Csv(writer) => {
+                writer.write_record(values.map(|value| value.to_string())).map_err(csv_error)?;
+            }
+            Sink::JsonLines(writer) => {
+                let row: serde_json::Map<String, serde_json::Value> = self.columns
+                    .iter()
+                    .zip(values)
+                    .map(|(column, value)| (column.name.to_string(), value.into()))
+                    .collect();
+                serde_json::to_writer(&mut *writer, &row)?;
+                writer.write_all(b"\n")?;
+            }
+            #[cfg(feature = "parquet")]
+            Sink::Parquet(sink) => sink.push(values)?,
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// 写完剩余数据并返回底层输出
+    pub fn finish(self) -> Result<W> {
+        let mut writer = match self.sink {
+            Sink::Csv(writer) => writer
+                .into_inner()
+                .map_err(|e| GT7Error::file_error(format!("写入CSV: {}", e.error())))?,
+            Sink::JsonLines(writer) => writer,
+            #[cfg(feature = "parquet")]
+            Sink::Parquet(sink) => sink.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+fn csv_error(error: csv::Error) -> GT7Error {
+    GT7Error::file_error(format!("写入CSV: {}", error))
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_sink {
+    use super::{Column, ColumnType, Value};
+    use crate::error::{Result, GT7Error};
+    use arrow_array::{ArrayRef, BooleanArray, Float32Array, Int64Array, RecordBatch};
+    use arrow_schema::{DataType, Field, Schema, SchemaRef};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    /// 每个行组缓存的行数
+    const BATCH_ROWS: usize = 4096;
+
+    enum ColumnBuffer {
+        Bool(Vec<Option<bool>>),
+        Int(Vec<Option<i64>>),
+        Float(Vec<Option<f32>>),
+    }
+
+    impl ColumnBuffer {
+        fn push(&mut self, value: Value) {
+            match (self, value) {
+                (Self::Bool(values), Value::Bool(value)) => values.push(Some(value)),
+                (Self::Int(values), Value::Int(value)) => values.push(Some(value)),
+                (Self::Float(values), Value::Float(value)) => values.push(Some(value)),
+                (Self::Bool(values), _) => values.push(None),
+                (Self::Int(values), _) => values.push(None),
+                (Self::Float(values), _) => values.push(None),
+            }
+        }
+
+        fn take(&mut self) -> ArrayRef {
+            match self {
+                Self::Bool(values) => Arc::new(BooleanArray::from(std::mem::take(values))),
+                Self::Int(values) => Arc::new(Int64Array::from(std::mem::take(values))),
+                Self::Float(values) => Arc::new(Float32Array::from(std::mem::take(values))),
+            }
+        }
+    }
+
+    pub(super) struct ParquetSink<W: Write + Send> {
+        writer: ArrowWriter<W>,
+        schema: SchemaRef,
+        buffers: Vec<ColumnBuffer>,
+        buffered: usize,
+    }
+
+    impl<W: Write + Send> ParquetSink<W> {
+        pub(super) fn new(writer: W, columns: &[&'static Column]) -> Result<Self> {
+            let fields: Vec<Field> = columns
+                .iter()
+                .map(|column| {
+                    let data_type = match column.kind {
+                        ColumnType::Bool => DataType::Boolean,
+                        ColumnType::Int => DataType::Int64,
+                        ColumnType::Float => DataType::Float32,
+                    };
+                    Field::new(column.name, data_type, true)
+                })
+                .collect();
+            let schema = Arc::new(Schema::new(fields));
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let writer = ArrowWriter::try_new(writer, schema.clone(), Some(properties)).map_err(parquet_error)?;
+            let buffers = columns
+                .iter()
+                .map(|column| match column.kind {
+                    ColumnType::Bool => ColumnBuffer::Bool(Vec::new()),
+                    ColumnType::Int => ColumnBuffer::Int(Vec::new()),
+                    ColumnType::Float => ColumnBuffer::Float(Vec::new()),
+                })
+                .collect();
+            Ok(Self { writer, schema, buffers, buffered: 0 })
+        }
+
+        pub(super) fn push(&mut self, values: impl Iterator<Item = Value>) -> Result<()> {
+            for (buffer, value) in self.buffers.iter_mut().zip(values) {
+                buffer.push(value);
+            }
+            self.buffered += 1;
+            if self.buffered >= BATCH_ROWS {
+                self.flush()?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            if self.buffered == 0 {
+                return Ok(());
+            }
+            let arrays = self.buffers.iter_mut().map(ColumnBuffer::take).collect();
+            let batch = RecordBatch::try_new(self.schema.clone(), arrays)
+                .map_err(|e| GT7Error::file_error(format!("生成Parquet行组: {}", e)))?;
+            self.writer.write(&batch).map_err(parquet_error)?;
+            self.buffered = 0;
+            Ok(())
+        }
+
+        pub(super) fn finish(mut self) -> Result<W> {
+            self.flush()?;
+            self.writer.into_inner().map_err(parquet_error)
+        }
+    }
+
+    fn parquet_error(error: parquet::errors::ParquetError) -> GT7Error {
+        GT7Error::file_error(format!("写入Parquet: {}", error))
+    }
+}
+
+/// 遥测导出器
+///
+/// 按选项把数据包写入一个文件，或按圈拆分写入多个文件
+pub struct Exporter {
+    output: PathBuf,
+    options: ExportOptions,
+    columns: Vec<&'static Column>,
+    /// 圈数 -> 写入器 (不拆分时只有一个，键为0)
+    writers: BTreeMap<u16, (PathBuf, TableWriter)>,
+}
+
+impl Exporter {
+    /// 创建导出器
+    ///
+    /// # 参数
+    ///
+    /// * `output` - 输出文件路径；按圈拆分时作为文件名前缀
+    /// * `options` - 导出选项
+    pub fn create(output: impl AsRef<Path>, options: ExportOptions) -> Result<Self> {
+        let columns = select_columns(&options.columns)?;
+        let mut exporter = Self {
+            output: output.as_ref().to_path_buf(),
+            options,
+            columns,
+            writers: BTreeMap::new(),
+        };
+        if !exporter.options.split_laps {
+            // 不拆分时即使没有数据也输出带表头的文件
+            let writer = TableWriter::create(&exporter.output, exporter.options.format, exporter.columns.clone())?;
+            exporter.writers.insert(0, (exporter.output.clone(), writer));
+        }
+        Ok(exporter)
+    }
+
+    /// 写入一个数据包
+    pub fn write(&mut self, packet: &GT7TelemetryPacket) -> Result<()> {
+        let lap = if self.options.split_laps {
+            packet.game_state.race_info.as_ref().map_or(0, |race| race.current_lap)
+        } else {
+            0
+        };
+        let (_, writer) = match self.writers.entry(lap) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let path = lap_path(&self.output, self.options.format, lap);
+                let writer = TableWriter::create(&path, self.options.format, self.columns.clone())?;
+                entry.insert((path, writer))
+            }
+        };
+        writer.write(packet)
+    }
+
+    /// 写完所有文件，返回写出的文件路径 (按圈数排序)
+    pub fn finish(self) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::with_capacity(self.writers.len());
+        for (path, writer) in self.writers.into_values() {
+            let rows = writer.rows();
+            writer.finish()?;
+            log::info!("导出 {} 行到 {}", rows, path.display());
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// 按圈拆分时的文件路径
+fn lap_path(output: &Path, format: ExportFormat, lap: u16) -> PathBuf {
+    let stem = output.file_stem().and_then(|stem| stem.to_str()).unwrap_or("telemetry");
+    output.with_file_name(format!("{}-lap{}.{}", stem, lap, format.extension()))
+}
+
+/// 读取数据包文件
+///
+/// `.gt7r` 录制文件 (跳过无法解析的数据报)，其他扩展名按JSON Lines (每行一个 [`GT7TelemetryPacket`]) 读取
+pub fn read_packets(path: impl AsRef<Path>) -> Result<Vec<GT7TelemetryPacket>> {
+    let path = path.as_ref();
+    if path.extension().is_some_and(|extension| extension == RECORDING_EXTENSION) {
+        let (replay, _) = ReplaySource::open(path)?;
+        return Ok(replay.frames().iter().map(|frame| frame.packet.clone()).collect());
+    }
+
+    let file = File::open(path).map_err(|e| GT7Error::file_error(format!("打开 {}: {}", path.display(), e)))?;
+    let mut packets = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        packets.push(serde_json::from_str(&line)?);
+    }
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+    use crate::types::PacketVariant;
+
+    fn packets() -> Vec<GT7TelemetryPacket> {
+        (0..6u32)
+            .map(|packet_id| {
+                PacketBuilder::new()
+                    .variant(PacketVariant::B)
+                    .packet_id(packet_id)
+                    .lap(packet_id as u16 / 3 + 1, 2)
+                    .speed_kmh(180.0)
+                    .build()
+            })
+            .collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gt7-export-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_columns() {
+        let all = all_columns();
+        let mut names: Vec<_> = all.iter().map(|column| column.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), all.len(), "列名重复");
+        assert!(names.contains(&"tyre_rr_radius"));
+
+        let selected = select_columns(&["packet_id", "speed_kmh"]).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(matches!(select_columns(&["nope"]), Err(GT7Error::ConfigError { .. })));
+
+        let packet = PacketBuilder::new().speed_kmh(36.0).build();
+        assert!(matches!(selected[1].value(&packet), Value::Float(kmh) if (kmh - 36.0).abs() < 0.01));
+        let motion = select_columns(&["sway"]).unwrap()[0];
+        assert_eq!(motion.value(&packet), Value::Null);
+    }
+
+    #[test]
+    fn test_csv_and_json_lines() {
+        let columns = select_columns(&["packet_id", "current_lap", "in_gear", "surge"]).unwrap();
+        let mut writer = TableWriter::new(Vec::new(), ExportFormat::Csv, columns.clone()).unwrap();
+        let mut packet = PacketBuilder::new().packet_id(7).lap(2, 3).build();
+        writer.write(&packet).unwrap();
+        packet.game_state.race_info = None;
+        writer.write(&packet).unwrap();
+        let csv = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "packet_id,current_lap,in_gear,surge");
+        assert!(lines[1].starts_with("7,2,"));
+        assert!(lines[2].starts_with("7,,"));
+
+        let mut writer = TableWriter::new(Vec::new(), ExportFormat::JsonLines, columns).unwrap();
+        writer.write(&packet).unwrap();
+        let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let row: serde_json::Value = serde_json::from_str(json.trim()).unwrap();
+        assert_eq!(row["packet_id"], 7);
+        assert!(row["current_lap"].is_null());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() {
+        use arrow_array::{Array, Float32Array, Int64Array};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let columns = select_columns(&["packet_id", "speed_kmh", "sway"]).unwrap();
+        let mut writer = TableWriter::new(Vec::new(), ExportFormat::Parquet, columns).unwrap();
+        for packet in packets() {
+            writer.write(&packet).unwrap();
+        }
+        writer.write(&PacketBuilder::new().packet_id(99).build()).unwrap();
+        let bytes = bytes::Bytes::from(writer.finish().unwrap());
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(bytes).unwrap().build().unwrap();
+        let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 7);
+        let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(ids.value(6), 99);
+        let speed = batch.column(1).as_any().downcast_ref::<Float32Array>().unwrap();
+        assert!((speed.value(0) - 180.0).abs() < 0.01);
+        let sway = batch.column(2).as_any().downcast_ref::<Float32Array>().unwrap();
+        assert!(!sway.is_null(0));
+        assert!(sway.is_null(6));
+    }
+
+    #[test]
+    fn test_split_laps() {
+        let dir = temp_dir("split");
+        let options = ExportOptions {
+            format: ExportFormat::JsonLines,
+            columns: vec!["packet_id".into(), "current_lap".into()],
+            split_laps: true,
+        };
+        let mut exporter = Exporter::create(dir.join("session.jsonl"), options).unwrap();
+        for packet in packets() {
+            exporter.write(&packet).unwrap();
+        }
+        let paths = exporter.finish().unwrap();
+        assert_eq!(paths, [dir.join("session-lap1.jsonl"), dir.join("session-lap2.jsonl")]);
+
+        let lap2 = read_lines(&paths[1]);
+        assert_eq!(lap2.len(), 3);
+        assert_eq!(lap2[0]["packet_id"], 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn read_lines(path: &Path) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!("CSV".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_path("laps/run.parquet"), Some(ExportFormat::Parquet));
+        assert_eq!(ExportFormat::from_path("run.jsonl"), Some(ExportFormat::JsonLines));
+        assert_eq!(ExportFormat::from_path("run.txt"), None);
+    }
+}
@@ -0,0 +1,474 @@
+//! 燃油策略
+//!
+//! GT7数据包只给出剩余燃油和油箱容量 (`EngineInfo::fuel_consumption` 始终为0)，
+//! [`FuelStrategy`] 在 [`LapTracker`] 之上测量每个有效圈的实际油耗，据此估算剩余燃油可跑的圈数、
+//! 跑完 `RaceInfo::total_laps` 还需要的燃油、燃油模式 (Fuel Map) 建议和进站窗口，
+//! 并在燃油不足、进站窗口打开、加油等情况下发出 [`FuelEvent`]。
+//!
+//! 电动车的 `fuel_remaining` 是电量百分比，此时所有"升"都应理解为百分比
+
+use crate::laps::{Lap, LapTracker};
+use crate::packet::GT7TelemetryPacket;
+use tokio::sync::broadcast;
+
+/// 最稀的燃油模式 (1为标准模式)
+pub const LEANEST_FUEL_MAP: u8 = 6;
+
+/// 燃油增加超过该值视为加油 (升)
+const REFUEL_THRESHOLD: f32 = 0.5;
+
+/// 燃油策略配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelConfig {
+    /// 计算平均油耗使用的最近有效圈数
+    pub average_laps: usize,
+    /// 完赛时保留的燃油 (圈)
+    pub reserve_laps: f32,
+    /// 剩余燃油少于该圈数时发出警告
+    pub low_fuel_laps: f32,
+    /// 每调稀一级燃油模式节省的油耗比例 (近似值，因车而异)
+    pub saving_per_map_step: f32,
+}
+
+impl Default for FuelConfig {
+    fn default() -> Self {
+        Self {
+            average_laps: 5,
+            reserve_laps: 0.5,
+            low_fuel_laps: 2.0,
+            saving_per_map_step: 0.05,
+        }
+    }
+}
+
+/// 燃油模式建议
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuelMapAdvice {
+    /// 不进站跑完需要的油耗 (升/圈)
+    pub target_consumption: f32,
+    /// 需要节省的油耗比例 (0-1)
+    pub saving: f32,
+    /// 建议的燃油模式 (1-6)
+    pub fuel_map: u8,
+}
+
+/// 进站窗口
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitWindow {
+    /// 最早在该圈结束时进站 (一次加油即可完赛)
+    pub earliest_lap: u16,
+    /// 最晚在该圈结束时进站 (之后燃油不够再跑一圈)
+    pub latest_lap: u16,
+    /// 完赛需要的进站次数
+    pub stops: u32,
+    /// 在最晚进站圈进站时需要加的燃油 (升，不超过油箱剩余空间)
+    pub fuel_to_add: f32,
+}
+
+/// 燃油状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuelStatus {
+    /// 当前圈数
+    pub lap: u16,
+    /// 剩余燃油 (升)
+    pub fuel_remaining: f32,
+    /// 油箱容量 (升)
+    pub fuel_capacity: f32,
+    /// 平均油耗 (升/圈)，还没有有效圈时为 `None`
+    pub consumption_per_lap: Option<f32>,
+    /// 剩余燃油可跑的圈数
+    pub laps_of_fuel: Option<f32>,
+    /// 比赛剩余圈数 (含当前圈未跑完的部分)，不是计圈比赛时为 `None`
+    pub race_laps_remaining: Option<f32>,
+    /// 跑完比赛 (含保留燃油) 还缺的燃油 (升)，燃油足够时为0
+    pub fuel_to_finish: Option<f32>,
+    /// 不进站完赛的燃油模式建议 (燃油足够或调稀也不够时为 `None`)
+    pub fuel_map: Option<FuelMapAdvice>,
+    /// 进站窗口 (燃油足够时为 `None`)
+    pub pit_window: Option<PitWindow>,
+}
+
+/// 燃油事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum FuelEvent {
+    /// 完成一圈并测量了油耗
+    LapCompleted {
+        /// 圈数
+        lap: u16,
+        /// 本圈油耗 (升)
+        fuel_used: f32,
+        /// 该圈是否计入平均油耗 (只有有效的计时圈计入)
+        counted: bool,
+        /// 更新后的平均油耗 (升/圈)
+        average: Option<f32>,
+    },
+    /// 加油
+    Refuelled {
+        /// 加油量 (升)
+        added: f32,
+        /// 加油后的燃油 (升)
+        fuel_remaining: f32,
+    },
+    /// 剩余燃油不足 `low_fuel_laps` 圈
+    LowFuel {
+        /// 剩余燃油可跑的圈数
+        laps_of_fuel: f32,
+    },
+    /// 按当前油耗无法跑完比赛
+    CannotFinish {
+        /// 缺少的燃油 (升)
+        fuel_short: f32,
+        /// 不进站完赛的燃油模式建议
+        fuel_map: Option<FuelMapAdvice>,
+        /// 进站窗口
+        pit_window: Option<PitWindow>,
+    },
+    /// 进入进站窗口
+    PitWindowOpen(PitWindow),
+}
+
+/// 燃油策略
+#[derive(Debug)]
+pub struct FuelStrategy {
+    config: FuelConfig,
+    tracker: LapTracker,
+    /// 计入平均的每圈油耗，最近的在最后
+    usage: Vec<f32>,
+    /// 计入平均的每圈用时 (秒)
+    lap_times: Vec<f32>,
+    last_fuel: Option<f32>,
+    status: Option<FuelStatus>,
+    low_fuel_warned: bool,
+    cannot_finish_warned: bool,
+    pit_window_announced: bool,
+    event_sender: broadcast::Sender<FuelEvent>,
+}
+
+impl FuelStrategy {
+    /// 创建燃油策略
+    pub fn new(config: FuelConfig) -> Self {
+        let (event_sender, _) = broadcast::channel(256);
+        Self {
+            config,
+            tracker: LapTracker::new(),
+            usage: Vec::new(),
+            lap_times: Vec::new(),
+            last_fuel: None,
+            status: None,
+            low_fuel_warned: false,
+            cannot_finish_warned: false,
+            pit_window_announced: false,
+            event_sender,
+        }
+    }
+
+    /// 订阅燃油事件，订阅前发生的事件不会补发
+    pub fn subscribe(&self) -> broadcast::Receiver<FuelEvent> {
+        self.event_sender.subscribe()
+    }
+
+    /// 最近一次的燃油状态
+    pub fn status(&self) -> Option<FuelStatus> {
+        self.status
+    }
+
+    /// 平均油耗 (升/圈)
+    pub fn consumption_per_lap(&self) -> Option<f32> {
+        let recent = &self.usage[self.usage.len().saturating_sub(self.config.average_laps.max(1))..];
+        (!recent.is_empty()).then(|| recent.iter().sum::<f32>() / recent.len() as f32)
+    }
+
+    /// 处理一个数据包，返回产生的事件 (同时发送给订阅者)
+    pub fn push(&mut self, packet: &GT7TelemetryPacket) -> Vec<FuelEvent> {
+        let mut events = Vec::new();
+        let previous_samples = self.tracker.current_samples().len();
+        let last_sample_fuel = self.tracker.current_samples().last().map(|sample| sample.packet.car_info.engine.fuel_remaining);
+        let completed = self.tracker.push(packet);
+        let samples = self.tracker.current_samples();
+        // 数据包被忽略 (重复、乱序或暂停) 时不会成为当前圈的最后一个采样
+        let accepted = completed.is_some() || samples.last().is_some_and(|sample| sample.packet.packet_id == packet.packet_id);
+        // 倒带会恢复燃油，不能当作加油
+        let rewound = accepted && completed.is_none() && samples.len() <= previous_samples;
+        if let Some(lap) = completed {
+            // 圈内油耗不含最后一个采样到计时线的一小段
+            let tail = last_sample_fuel.map_or(0.0, |fuel| (fuel - packet.car_info.engine.fuel_remaining).max(0.0));
+            self.complete_lap(&lap, lap.fuel_used + tail, &mut events);
+        }
+
+        if accepted && !packet.game_state.is_paused {
+            let fuel = packet.car_info.engine.fuel_remaining;
+            let added = self.last_fuel.map(|last| fuel - last).filter(|&added| added > REFUEL_THRESHOLD && !rewound);
+            if let Some(added) = added {
+                log::info!("加油 {:.1}L，当前燃油 {:.1}L", added, fuel);
+                self.low_fuel_warned = false;
+                self.cannot_finish_warned = false;
+                self.pit_window_announced = false;
+                events.push(FuelEvent::Refuelled { added, fuel_remaining: fuel });
+            }
+            self.last_fuel = Some(fuel);
+            self.update_status(packet, &mut events);
+        }
+
+        for event in &events {
+            let _ = self.event_sender.send(event.clone());
+        }
+        events
+    }
+
+    /// 清除油耗记录 (换车或换赛道时)
+    pub fn reset(&mut self) {
+        let event_sender = self.event_sender.clone();
+        *self = Self::new(self.config.clone());
+        self.event_sender = event_sender;
+    }
+
+    fn complete_lap(&mut self, lap: &Lap, fuel_used: f32, events: &mut Vec<FuelEvent>) {
+        let counted = lap.is_valid() && fuel_used > 0.0;
+        if counted {
+            self.usage.push(fuel_used);
+            self.lap_times.push(lap.time().as_secs_f32());
+        }
+        let average = self.consumption_per_lap();
+        log::debug!("第 {} 圈油耗 {:.2}L，平均 {:?}", lap.number, fuel_used, average);
+        events.push(FuelEvent::LapCompleted { lap: lap.number, fuel_used, counted, average });
+    }
+
+    fn update_status(&mut self, packet: &GT7TelemetryPacket, events: &mut Vec<FuelEvent>) {
+        let Some(race) = &packet.game_state.race_info else {
+            return;
+        };
+        let engine = &packet.car_info.engine;
+        let consumption = self.consumption_per_lap();
+        let laps_of_fuel = consumption.map(|per_lap| engine.fuel_remaining / per_lap);
+
+        let race_laps_remaining = (race.total_laps > 0 && race.current_lap > 0).then(|| {
+            let laps = f32::from(race.total_laps.saturating_sub(race.current_lap)) + 1.0;
+            (laps - self.current_lap_progress()).max(0.0)
+        });
+
+        let mut status = FuelStatus {
+            lap: race.current_lap,
+            fuel_remaining: engine.fuel_remaining,
+            fuel_capacity: engine.fuel_capacity,
+            consumption_per_lap: consumption,
+            laps_of_fuel,
+            race_laps_remaining,
+            fuel_to_finish: None,
+            fuel_map: None,
+            pit_window: None,
+        };
+
+        if let (Some(per_lap), Some(race_laps)) = (consumption, race_laps_remaining) {
+            let needed = (race_laps + self.config.reserve_laps) * per_lap;
+            let short = (needed - engine.fuel_remaining).max(0.0);
+            status.fuel_to_finish = Some(short);
+            if short > 0.0 {
+                status.fuel_map = self.fuel_map_advice(engine.fuel_remaining, race_laps, per_lap);
+                status.pit_window =
+                    self.pit_window(race.current_lap, race.total_laps, race_laps, engine.fuel_remaining, engine.fuel_capacity, per_lap);
+            }
+        }
+
+        if let Some(laps) = laps_of_fuel {
+            if laps < self.config.low_fuel_laps && !self.low_fuel_warned {
+                self.low_fuel_warned = true;
+                log::warn!("燃油只够 {:.1} 圈", laps);
+                events.push(FuelEvent::LowFuel { laps_of_fuel: laps });
+            }
+        }
+        if let Some(short) = status.fuel_to_finish.filter(|&short| short > 0.0) {
+            if !self.cannot_finish_warned {
+                self.cannot_finish_warned = true;
+                log::warn!("按当前油耗无法完赛，缺少 {:.1}L", short);
+                events.push(FuelEvent::CannotFinish {
+                    fuel_short: short,
+                    fuel_map: status.fuel_map,
+                    pit_window: status.pit_window,
+                });
+            }
+        }
+        if let Some(window) = status.pit_window {
+            if race.current_lap >= window.earliest_lap && !self.pit_window_announced {
+                self.pit_window_announced = true;
+                log::info!("进站窗口: 第 {} - {} 圈", window.earliest_lap, window.latest_lap);
+                events.push(FuelEvent::PitWindowOpen(window));
+            }
+        }
+
+        self.status = Some(status);
+    }
+
+    /// 当前圈已完成的比例 (按平均圈速估算)
+    fn current_lap_progress(&self) -> f32 {
+        let recent = &self.lap_times[self.lap_times.len().saturating_sub(self.config.average_laps.max(1))..];
+        let Some(elapsed) = self.tracker.current_elapsed() else {
+            return 0.0;
+        };
+        if recent.is_empty() {
+            return 0.0;
+        }
+        let average = recent.iter().sum::<f32>() / recent.len() as f32;
+        (elapsed.as_secs_f32() / average).clamp(0.0, 1.0)
+    }
+
+    /// 调稀燃油模式不进站完赛
+    fn fuel_map_advice(&self, fuel: f32, race_laps: f32, per_lap: f32) -> Option<FuelMapAdvice> {
+        let target_consumption = fuel / (race_laps + self.config.reserve_laps);
+        let saving = 1.0 - target_consumption / per_lap;
+        if self.config.saving_per_map_step <= 0.0 {
+            return None;
+        }
+        let steps = (saving / self.config.saving_per_map_step).ceil();
+        (steps < f32::from(LEANEST_FUEL_MAP)).then(|| FuelMapAdvice {
+            target_consumption,
+            saving,
+            fuel_map: 1 + steps as u8,
+        })
+    }
+
+    /// 在第 `lap` 圈结束时进站的窗口
+    ///
+    /// `race_laps` 是比赛剩余圈数 (含当前圈未跑完的部分)，与 `fuel_to_finish` 使用同一个估算
+    fn pit_window(&self, lap: u16, total_laps: u16, race_laps: f32, fuel: f32, capacity: f32, per_lap: f32) -> Option<PitWindow> {
+        if capacity <= 0.0 {
+            return None;
+        }
+        let reserve = self.config.reserve_laps;
+        // 当前圈还没跑完的部分
+        let rest_of_lap = (race_laps - f32::from(total_laps.saturating_sub(lap))).clamp(0.0, 1.0);
+        // 跑完第L圈 (还要跑 rest_of_lap + L - lap 圈) 后仍留有保留燃油
+        let latest = lap + (fuel / per_lap - reserve - rest_of_lap).floor().max(0.0) as u16;
+        // 在第L圈结束时进站，之后还有 total_laps - L 圈，油箱装满要够用
+        let earliest = (f32::from(total_laps) + reserve - capacity / per_lap).ceil().max(f32::from(lap)) as u16;
+        let needed = (race_laps + reserve) * per_lap - fuel;
+        let stops = (needed / capacity).ceil().max(1.0) as u32;
+        let fuel_at_stop = (fuel - (rest_of_lap + f32::from(latest - lap)) * per_lap).max(0.0);
+        Some(PitWindow {
+            earliest_lap: earliest.min(latest),
+            latest_lap: latest,
+            stops,
+            fuel_to_add: needed.clamp(0.0, capacity - fuel_at_stop),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::LapDriver;
+
+    /// 每圈60帧，每圈油耗 `per_lap` 升
+    struct Driver {
+        laps: LapDriver,
+        fuel: f32,
+    }
+
+    impl Driver {
+        fn new(fuel: f32) -> Self {
+            Self { laps: LapDriver::default(), fuel }
+        }
+
+        fn drive(&mut self, strategy: &mut FuelStrategy, lap: u16, total_laps: u16, per_lap: f32) -> Vec<FuelEvent> {
+            let start = self.fuel;
+            self.fuel -= per_lap;
+            self.laps
+                .lap(lap, total_laps, 60, |builder, frame| builder.fuel(start - per_lap * frame as f32 / 60.0, 50.0))
+                .iter()
+                .flat_map(|packet| strategy.push(packet))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_measures_consumption() {
+        let mut strategy = FuelStrategy::new(FuelConfig::default());
+        let mut driver = Driver::new(40.0);
+        driver.drive(&mut strategy, 1, 0, 3.0);
+        driver.drive(&mut strategy, 2, 0, 3.0);
+        let events = driver.drive(&mut strategy, 3, 0, 2.0);
+        // 出站圈不计入
+        assert_eq!(
+            events.iter().filter(|event| matches!(event, FuelEvent::LapCompleted { counted: true, .. })).count(),
+            1
+        );
+        driver.drive(&mut strategy, 4, 0, 2.0);
+        let average = strategy.consumption_per_lap().unwrap();
+        assert!((average - 2.5).abs() < 0.01, "{}", average);
+
+        let status = strategy.status().unwrap();
+        // 不是计圈比赛
+        assert_eq!(status.race_laps_remaining, None);
+        assert_eq!(status.fuel_to_finish, None);
+        assert!((status.laps_of_fuel.unwrap() - status.fuel_remaining / 2.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_pit_window_and_warnings() {
+        let mut strategy = FuelStrategy::new(FuelConfig::default());
+        let mut receiver = strategy.subscribe();
+        // 20圈比赛，每圈3升。出站圈不计入油耗，第3圈开始时才知道油耗
+        let mut driver = Driver::new(43.0);
+        driver.drive(&mut strategy, 1, 20, 3.0);
+        driver.drive(&mut strategy, 2, 20, 3.0);
+        let events = driver.drive(&mut strategy, 3, 20, 3.0);
+        let Some(FuelEvent::CannotFinish { fuel_short, fuel_map, pit_window }) =
+            events.iter().find(|event| matches!(event, FuelEvent::CannotFinish { .. })).cloned()
+        else {
+            panic!("{:?}", events);
+        };
+        // 第3圈开始时剩余37升，还要跑18.5圈 (含保留)，共55.5升
+        assert!((fuel_short - 18.5).abs() < 0.1, "{}", fuel_short);
+        assert_eq!(fuel_map, None);
+        let window = pit_window.unwrap();
+        assert_eq!(window.stops, 1);
+        // 满箱50升够16.67圈，最早在第4圈结束时进站
+        assert_eq!(window.earliest_lap, 4);
+        // 37升可跑12.33圈，留半圈保留后最晚在第13圈结束时进站
+        assert_eq!(window.latest_lap, 13);
+        assert!((window.fuel_to_add - 18.5).abs() < 0.1, "{:?}", window);
+        assert!(receiver.try_recv().is_ok());
+
+        // 第3圈最后一个数据包: 已跑完的部分不应再计入，窗口和加油量与完赛缺口一致
+        let status = strategy.status().unwrap();
+        let window = status.pit_window.unwrap();
+        assert_eq!((window.earliest_lap, window.latest_lap), (4, 13), "{:?}", status);
+        assert!((window.fuel_to_add - status.fuel_to_finish.unwrap()).abs() < 0.05, "{:?}", status);
+        assert!((window.fuel_to_add - 18.5).abs() < 0.1, "{:?}", window);
+
+        let mut opened = Vec::new();
+        for lap in 4..=5 {
+            opened.extend(driver.drive(&mut strategy, lap, 20, 3.0));
+        }
+        assert!(opened.iter().any(|event| matches!(event, FuelEvent::PitWindowOpen(window) if window.earliest_lap == 4)));
+
+        // 进站加满
+        driver.fuel = 50.0;
+        let events = driver.drive(&mut strategy, 6, 20, 3.0);
+        assert!(events.iter().any(|event| matches!(event, FuelEvent::Refuelled { .. })));
+        assert_eq!(strategy.status().unwrap().fuel_to_finish, Some(0.0));
+    }
+
+    #[test]
+    fn test_fuel_map_advice_and_low_fuel() {
+        let mut strategy = FuelStrategy::new(FuelConfig::default());
+        // 5圈比赛，每圈3升。第3圈开始时剩余9.5升，跑完3.5圈 (含保留) 需要节省约10%
+        let mut driver = Driver::new(15.5);
+        driver.drive(&mut strategy, 1, 5, 3.0);
+        driver.drive(&mut strategy, 2, 5, 3.0);
+        let mut events = driver.drive(&mut strategy, 3, 5, 3.0);
+        let advice = events
+            .iter()
+            .find_map(|event| match event {
+                FuelEvent::CannotFinish { fuel_map, .. } => *fuel_map,
+                _ => None,
+            })
+            .unwrap();
+        assert!((advice.saving - 0.095).abs() < 0.01, "{:?}", advice);
+        assert_eq!(advice.fuel_map, 3);
+
+        for lap in 4..=5 {
+            events.extend(driver.drive(&mut strategy, lap, 5, 3.0));
+        }
+        assert_eq!(events.iter().filter(|event| matches!(event, FuelEvent::LowFuel { .. })).count(), 1);
+    }
+}
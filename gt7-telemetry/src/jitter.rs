@@ -0,0 +1,264 @@
+//! 按序号整理数据包的抖动缓冲
+//!
+//! UDP可能重复或乱序投递数据包。缓冲按 `packet_id` 丢弃重复和过期的数据包，
+//! 并暂存少量数据包等待缺失的序号，保证输出序号严格递增；
+//! 等待超时或超出窗口时跳过缺失的序号并给出缺口通知
+
+use crate::packet::GT7TelemetryPacket;
+use crate::types::JitterBufferConfig;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// 序号向后跳变超过该值时视为主机重新开始计数
+const SEQUENCE_RESET_THRESHOLD: i64 = 1000;
+
+/// 序号缺口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceGap {
+    /// 期望的下一个序号
+    pub expected: u32,
+    /// 实际继续输出的序号
+    pub resumed: u32,
+}
+
+impl SequenceGap {
+    /// 缺失的数据包数量
+    pub fn missing(&self) -> u32 {
+        self.resumed.wrapping_sub(self.expected)
+    }
+}
+
+/// 缓冲输出
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)] // 绝大多数输出都是数据包
+pub enum Release {
+    /// 按序输出的数据包
+    Packet(GT7TelemetryPacket),
+    /// 之后的数据包与之前的不连续
+    Gap(SequenceGap),
+}
+
+/// 抖动缓冲
+#[derive(Debug)]
+pub struct JitterBuffer {
+    config: JitterBufferConfig,
+    /// 下一个要输出的扩展序号 (处理u32回绕)
+    next: Option<i64>,
+    /// 暂存的数据包: 扩展序号 -> (到达时间, 数据包)
+    pending: BTreeMap<i64, (Instant, GT7TelemetryPacket)>,
+    /// 丢弃的重复或过期数据包数
+    discarded: u64,
+}
+
+impl JitterBuffer {
+    /// 创建抖动缓冲
+    pub fn new(config: JitterBufferConfig) -> Self {
+        Self {
+            config,
+            next: None,
+            pending: BTreeMap::new(),
+            discarded: 0,
+        }
+    }
+
+    /// 丢弃的重复或过期数据包数
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// 当前暂存的数据包数
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 是否没有暂存的数据包
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// 放入一个数据包，返回可以按序输出的内容
+    pub fn push(&mut self, packet: GT7TelemetryPacket, now: Instant) -> Vec<Release> {
+        let mut released = Vec::new();
+
+        let Some(next) = self.next else {
+            // 第一个数据包直接作为起点
+            self.next = Some(i64::from(packet.packet_id) + 1);
+            released.push(Release::Packet(packet));
+            return released;
+        };
+
+        let seq = Self::extend(next, packet.packet_id);
+        if seq < next - SEQUENCE_RESET_THRESHOLD {
+            // 主机重新计数: 先输出暂存的数据包，再从新序号开始
+            self.flush_into(&mut released);
+            released.push(Release::Gap(SequenceGap {
+                expected: self.next.unwrap_or(next) as u32,
+                resumed: packet.packet_id,
+            }));
+            self.next = Some(i64::from(packet.packet_id) + 1);
+            released.push(Release::Packet(packet));
+            return released;
+        }
+
+        if seq < next || self.pending.contains_key(&seq) {
+            self.discarded += 1;
+            return released;
+        }
+
+        self.pending.insert(seq, (now, packet));
+        self.release_in_order(&mut released);
+        while self.pending.len() > self.config.reorder_window {
+            self.skip_gap(&mut released);
+        }
+        self.poll_into(now, &mut released);
+        released
+    }
+
+    /// 输出等待超时的数据包
+    pub fn poll(&mut self, now: Instant) -> Vec<Release> {
+        let mut released = Vec::new();
+        self.poll_into(now, &mut released);
+        released
+    }
+
+    /// 最早暂存的数据包的超时时刻
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|(arrived, _)| *arrived)
+            .min()
+            .map(|arrived| arrived + self.max_delay())
+    }
+
+    /// 输出所有暂存的数据包 (跳过缺口)
+    pub fn flush(&mut self) -> Vec<Release> {
+        let mut released = Vec::new();
+        self.flush_into(&mut released);
+        released
+    }
+
+    fn max_delay(&self) -> Duration {
+        Duration::from_millis(self.config.max_delay_ms)
+    }
+
+    fn poll_into(&mut self, now: Instant, released: &mut Vec<Release>) {
+        while self.next_deadline().is_some_and(|deadline| deadline <= now) {
+            self.skip_gap(released);
+        }
+    }
+
+    fn flush_into(&mut self, released: &mut Vec<Release>) {
+        while !self.pending.is_empty() {
+            self.skip_gap(released);
+        }
+    }
+
+    /// 放弃等待缺失的序号，从最小的暂存序号继续输出
+    fn skip_gap(&mut self, released: &mut Vec<Release>) {
+        let (Some(next), Some(&first)) = (self.next, self.pending.keys().next()) else {
+            return;
+        };
+        if first > next {
+            released.push(Release::Gap(SequenceGap {
+                expected: next as u32,
+                resumed: first as u32,
+            }));
+            self.next = Some(first);
+        }
+        self.release_in_order(released);
+    }
+
+    fn release_in_order(&mut self, released: &mut Vec<Release>) {
+        while let Some(next) = self.next {
+            let Some((_, packet)) = self.pending.remove(&next) else {
+                break;
+            };
+            released.push(Release::Packet(packet));
+            self.next = Some(next + 1);
+        }
+    }
+
+    /// 把u32序号扩展到 `next` 附近，处理回绕
+    fn extend(next: i64, packet_id: u32) -> i64 {
+        next + i64::from(packet_id.wrapping_sub(next as u32) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+
+    fn packet(packet_id: u32) -> GT7TelemetryPacket {
+        PacketBuilder::new().packet_id(packet_id).build()
+    }
+
+    fn ids(released: &[Release]) -> Vec<String> {
+        released
+            .iter()
+            .map(|release| match release {
+                Release::Packet(packet) => packet.packet_id.to_string(),
+                Release::Gap(gap) => format!("gap{}-{}", gap.expected, gap.resumed),
+            })
+            .collect()
+    }
+
+    fn feed(buffer: &mut JitterBuffer, packet_ids: &[u32], now: Instant) -> Vec<String> {
+        packet_ids
+            .iter()
+            .flat_map(|&id| ids(&buffer.push(packet(id), now)))
+            .collect()
+    }
+
+    #[test]
+    fn test_reorders_within_window() {
+        let mut buffer = JitterBuffer::new(JitterBufferConfig { reorder_window: 3, max_delay_ms: 50 });
+        let now = Instant::now();
+        assert_eq!(feed(&mut buffer, &[1, 3, 4, 2, 5], now), ["1", "2", "3", "4", "5"]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_drops_duplicates_and_stale() {
+        let mut buffer = JitterBuffer::new(JitterBufferConfig { reorder_window: 3, max_delay_ms: 50 });
+        let now = Instant::now();
+        assert_eq!(feed(&mut buffer, &[1, 2, 2, 4, 4, 1, 3], now), ["1", "2", "3", "4"]);
+        assert_eq!(buffer.discarded(), 3);
+    }
+
+    #[test]
+    fn test_window_overflow_skips_gap() {
+        let mut buffer = JitterBuffer::new(JitterBufferConfig { reorder_window: 2, max_delay_ms: 1000 });
+        let now = Instant::now();
+        assert_eq!(feed(&mut buffer, &[1, 3, 4, 5, 2], now), ["1", "gap2-3", "3", "4", "5"]);
+        assert_eq!(buffer.discarded(), 1);
+    }
+
+    #[test]
+    fn test_zero_window_never_holds() {
+        let mut buffer = JitterBuffer::new(JitterBufferConfig { reorder_window: 0, max_delay_ms: 50 });
+        let now = Instant::now();
+        assert_eq!(feed(&mut buffer, &[1, 3, 2, 4], now), ["1", "gap2-3", "3", "4"]);
+    }
+
+    #[test]
+    fn test_timeout_releases_held_packets() {
+        let mut buffer = JitterBuffer::new(JitterBufferConfig { reorder_window: 5, max_delay_ms: 50 });
+        let start = Instant::now();
+        assert_eq!(feed(&mut buffer, &[1, 3], start), ["1"]);
+        assert_eq!(buffer.next_deadline(), Some(start + Duration::from_millis(50)));
+        assert!(buffer.poll(start + Duration::from_millis(49)).is_empty());
+        assert_eq!(ids(&buffer.poll(start + Duration::from_millis(50))), ["gap2-3", "3"]);
+        assert_eq!(buffer.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_sequence_wrap_and_reset() {
+        let mut buffer = JitterBuffer::new(JitterBufferConfig::default());
+        let now = Instant::now();
+        assert_eq!(feed(&mut buffer, &[u32::MAX, 0, 1], now), [u32::MAX.to_string().as_str(), "0", "1"]);
+
+        let mut buffer = JitterBuffer::new(JitterBufferConfig::default());
+        assert_eq!(feed(&mut buffer, &[50_000, 50_002, 7], now), ["50000", "gap50001-50002", "50002", "gap50003-7", "7"]);
+    }
+}
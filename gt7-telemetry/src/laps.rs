@@ -0,0 +1,409 @@
+//! 圈数识别与计时
+//!
+//! [`LapTracker`] 按接收顺序消费数据包，把数据流切分成圈 ([`Lap`])。
+//! 圈的边界来自 `RaceInfo::current_lap` 的递增；离开赛道、倒带 (游戏内时间倒退)
+//! 和重新开始 (圈数回退或数据包序号重置) 都会被识别并记录在圈上。
+//! 计算圈速按数据包序号以60Hz换算，不受丢包和暂停影响
+
+use crate::packet::GT7TelemetryPacket;
+use crate::GT7_PACKET_RATE;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 序号向后跳变超过该值时视为主机重新开始计数
+const SEQUENCE_RESET_THRESHOLD: i32 = 1000;
+
+/// 游戏内时间倒退超过该值时视为跨过午夜，而不是倒带 (毫秒)
+const TIME_OF_DAY_WRAP_MS: u64 = 12 * 60 * 60 * 1000;
+
+/// 圈的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LapKind {
+    /// 出站圈: 每段连续在赛道上的数据的第一圈 (开始追踪、回到赛道或重新开始后)，起点不在计时线上
+    OutLap,
+    /// 计时圈: 从计时线开始
+    Flying,
+    /// 进站圈: 从计时线开始，但没有再次通过计时线就离开了赛道
+    InLap,
+}
+
+/// 圈无效的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LapInvalidation {
+    /// 不是完整的一圈 (出站圈、进站圈)
+    Incomplete,
+    /// 圈内使用了倒带
+    Rewind,
+    /// 圈内重新开始了比赛
+    Restart,
+}
+
+/// 圈内的一个采样
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LapSample {
+    /// 相对圈开始的时间
+    pub elapsed: Duration,
+    /// 数据包
+    pub packet: GT7TelemetryPacket,
+}
+
+/// 一圈的记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lap {
+    /// 圈数 (`RaceInfo::current_lap`)
+    pub number: u16,
+    /// 圈的类型
+    pub kind: LapKind,
+    /// 圈内所有采样 (倒带时丢弃被撤销的部分)
+    pub samples: Vec<LapSample>,
+    /// 游戏给出的圈速 (通过计时线时数据包中的上一圈时间)
+    pub official_time: Option<Duration>,
+    /// 按数据包序号计算的圈速
+    pub computed_time: Duration,
+    /// 无效原因 (有效圈为 `None`)
+    pub invalidation: Option<LapInvalidation>,
+    /// 消耗的燃油 (升，不计入加油)
+    pub fuel_used: f32,
+    /// 最高车速 (km/h)
+    pub max_speed_kmh: f32,
+    /// 最低车速 (km/h)
+    pub min_speed_kmh: f32,
+    /// 圈内丢失的数据包数
+    pub dropped_packets: u32,
+}
+
+impl Lap {
+    /// 是否是有效的计时圈
+    pub fn is_valid(&self) -> bool {
+        self.invalidation.is_none()
+    }
+
+    /// 圈速: 优先使用游戏给出的圈速
+    pub fn time(&self) -> Duration {
+        self.official_time.unwrap_or(self.computed_time)
+    }
+}
+
+/// 进行中的圈
+#[derive(Debug)]
+struct LapInProgress {
+    number: u16,
+    kind: LapKind,
+    samples: Vec<LapSample>,
+    /// 圈开始以来经过的数据包间隔数
+    ticks: u64,
+    invalidation: Option<LapInvalidation>,
+    fuel_used: f32,
+    dropped_packets: u32,
+    /// 圈开始时数据包中的上一圈时间，用于判断通过计时线时圈速是否已更新
+    last_lap_time_at_start: Option<u32>,
+}
+
+impl LapInProgress {
+    fn start(packet: &GT7TelemetryPacket, number: u16, kind: LapKind) -> Self {
+        Self {
+            number,
+            kind,
+            samples: vec![LapSample { elapsed: Duration::ZERO, packet: packet.clone() }],
+            ticks: 0,
+            invalidation: None,
+            fuel_used: 0.0,
+            dropped_packets: 0,
+            last_lap_time_at_start: packet.game_state.race_info.as_ref().and_then(|race| race.last_lap_time),
+        }
+    }
+
+    fn push(&mut self, packet: &GT7TelemetryPacket, ticks: u64) {
+        let Some(last) = self.samples.last() else {
+            return;
+        };
+
+        let rewound_ms = last.packet.timestamp.saturating_sub(packet.timestamp);
+        if rewound_ms > 0 && rewound_ms < TIME_OF_DAY_WRAP_MS {
+            // 倒带: 丢弃被撤销的采样，从倒带点继续计时
+            self.samples.retain(|sample| sample.packet.timestamp <= packet.timestamp);
+            self.ticks = self.samples.last().map_or(0, |sample| ticks_of(sample.elapsed));
+            self.invalidation = Some(LapInvalidation::Rewind);
+            log::debug!("第 {} 圈倒带 {}ms", self.number, rewound_ms);
+        } else {
+            // 只计入燃油减少，加油不抵消消耗
+            let fuel_drop = last.packet.car_info.engine.fuel_remaining - packet.car_info.engine.fuel_remaining;
+            self.fuel_used += fuel_drop.max(0.0);
+            self.dropped_packets += (ticks - 1) as u32;
+        }
+
+        self.ticks += ticks;
+        self.samples.push(LapSample { elapsed: elapsed_of(self.ticks), packet: packet.clone() });
+    }
+
+    fn into_lap(mut self, kind: LapKind, official_time: Option<Duration>, end_ticks: u64) -> Lap {
+        if self.invalidation.is_none() && kind != LapKind::Flying {
+            self.invalidation = Some(LapInvalidation::Incomplete);
+        }
+        let speeds = self.samples.iter().map(|sample| sample.packet.car_info.speed * 3.6);
+        let max_speed_kmh = speeds.clone().fold(0.0, f32::max);
+        let min_speed_kmh = speeds.fold(f32::INFINITY, f32::min);
+        Lap {
+            number: self.number,
+            kind,
+            samples: self.samples,
+            official_time,
+            computed_time: elapsed_of(end_ticks),
+            invalidation: self.invalidation,
+            fuel_used: self.fuel_used,
+            max_speed_kmh,
+            min_speed_kmh,
+            dropped_packets: self.dropped_packets,
+        }
+    }
+
+    /// 没有通过计时线就结束的圈
+    fn abandon(mut self, invalidation: Option<LapInvalidation>) -> Lap {
+        if invalidation.is_some() {
+            self.invalidation = invalidation;
+        }
+        let kind = match self.kind {
+            LapKind::Flying => LapKind::InLap,
+            kind => kind,
+        };
+        let ticks = self.ticks;
+        self.into_lap(kind, None, ticks)
+    }
+}
+
+/// 圈数追踪器
+///
+/// 每次 [`push`](Self::push) 最多完成一圈。暂停的数据包被忽略且不计时
+#[derive(Debug, Default)]
+pub struct LapTracker {
+    current: Option<LapInProgress>,
+    last_packet_id: Option<u32>,
+}
+
+impl LapTracker {
+    /// 创建追踪器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前圈数
+    pub fn current_lap(&self) -> Option<u16> {
+        self.current.as_ref().map(|lap| lap.number)
+    }
+
+    /// 当前圈的类型
+    pub fn current_kind(&self) -> Option<LapKind> {
+        self.current.as_ref().map(|lap| lap.kind)
+    }
+
+    /// 当前圈已经过的时间
+    pub fn current_elapsed(&self) -> Option<Duration> {
+        self.current.as_ref().map(|lap| elapsed_of(lap.ticks))
+    }
+
+    /// 当前圈的采样
+    pub fn current_samples(&self) -> &[LapSample] {
+        self.current.as_ref().map_or(&[], |lap| &lap.samples)
+    }
+
+    /// 处理一个数据包，返回刚结束的圈
+    pub fn push(&mut self, packet: &GT7TelemetryPacket) -> Option<Lap> {
+        let delta = self.last_packet_id.map(|id| packet.packet_id.wrapping_sub(id) as i32);
+        let mut restarted = None;
+        let ticks = match delta {
+            Some(delta) if delta <= -SEQUENCE_RESET_THRESHOLD => {
+                // 主机重新计数
+                restarted = self.current.take().map(|lap| lap.abandon(Some(LapInvalidation::Restart)));
+                1
+            }
+            // 重复或乱序
+            Some(delta) if delta <= 0 => return None,
+            Some(delta) => delta as u64,
+            None => 1,
+        };
+        self.last_packet_id = Some(packet.packet_id);
+
+        if packet.game_state.is_paused {
+            return restarted;
+        }
+        let race = match &packet.game_state.race_info {
+            Some(race) if packet.game_state.flags.car_on_track => race,
+            _ => {
+                // 离开赛道，结束当前圈
+                return restarted.or_else(|| self.current.take().map(|lap| lap.abandon(None)));
+            }
+        };
+
+        let Some(current) = self.current.as_mut() else {
+            self.current = Some(LapInProgress::start(packet, race.current_lap, LapKind::OutLap));
+            return restarted;
+        };
+
+        if race.current_lap < current.number {
+            // 圈数回退: 重新开始了比赛
+            let lap = self.current.take().map(|lap| lap.abandon(Some(LapInvalidation::Restart)));
+            self.current = Some(LapInProgress::start(packet, race.current_lap, LapKind::OutLap));
+            return lap;
+        }
+
+        if race.current_lap > current.number {
+            // 通过计时线
+            let official_time = race
+                .last_lap_time
+                .filter(|&ms| Some(ms) != current.last_lap_time_at_start)
+                .map(|ms| Duration::from_millis(u64::from(ms)));
+            let end_ticks = current.ticks + ticks;
+            let kind = current.kind;
+            let lap = self.current.take().map(|lap| lap.into_lap(kind, official_time, end_ticks));
+            self.current = Some(LapInProgress::start(packet, race.current_lap, LapKind::Flying));
+            return lap;
+        }
+
+        current.push(packet, ticks);
+        None
+    }
+
+    /// 数据流结束，返回进行中的圈
+    pub fn finish(&mut self) -> Option<Lap> {
+        self.current.take().map(|lap| lap.abandon(None))
+    }
+}
+
+fn elapsed_of(ticks: u64) -> Duration {
+    Duration::from_nanos(ticks * 1_000_000_000 / u64::from(GT7_PACKET_RATE))
+}
+
+fn ticks_of(elapsed: Duration) -> u64 {
+    // 四舍五入抵消elapsed_of的截断
+    ((elapsed.as_nanos() * u128::from(GT7_PACKET_RATE) + 500_000_000) / 1_000_000_000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+
+    struct Stream {
+        packet_id: u32,
+        time_of_day: u32,
+        fuel: f32,
+    }
+
+    impl Stream {
+        fn new() -> Self {
+            Self { packet_id: 0, time_of_day: 0, fuel: 50.0 }
+        }
+
+        /// 下一帧 (游戏内时间按60Hz前进)
+        fn next(&mut self, lap: u16, last_lap_ms: Option<u32>) -> GT7TelemetryPacket {
+            self.packet_id += 1;
+            self.time_of_day += 17;
+            self.fuel -= 0.01;
+            PacketBuilder::new()
+                .packet_id(self.packet_id)
+                .time_of_day(self.time_of_day)
+                .lap(lap, 5)
+                .lap_times(last_lap_ms, last_lap_ms)
+                .fuel(self.fuel, 100.0)
+                .speed_kmh(100.0 + (self.packet_id % 60) as f32)
+                .build()
+        }
+
+        fn run(&mut self, tracker: &mut LapTracker, frames: u32, lap: u16, last_lap_ms: Option<u32>) -> Vec<Lap> {
+            (0..frames).filter_map(|_| tracker.push(&self.next(lap, last_lap_ms))).collect()
+        }
+    }
+
+    #[test]
+    fn test_out_lap_and_flying_laps() {
+        let mut tracker = LapTracker::new();
+        let mut stream = Stream::new();
+        assert!(stream.run(&mut tracker, 300, 1, None).is_empty());
+        let laps = stream.run(&mut tracker, 600, 2, Some(90_000));
+        assert_eq!(laps.len(), 1);
+        let out_lap = &laps[0];
+        assert_eq!((out_lap.number, out_lap.kind), (1, LapKind::OutLap));
+        assert_eq!(out_lap.invalidation, Some(LapInvalidation::Incomplete));
+        assert_eq!(out_lap.samples.len(), 300);
+        assert_eq!(out_lap.computed_time, Duration::from_secs(5));
+
+        let laps = stream.run(&mut tracker, 10, 3, Some(10_010));
+        let lap = &laps[0];
+        assert_eq!((lap.number, lap.kind), (2, LapKind::Flying));
+        assert!(lap.is_valid());
+        assert_eq!(lap.computed_time, Duration::from_secs(10));
+        assert_eq!(lap.official_time, Some(Duration::from_millis(10_010)));
+        assert_eq!(lap.time(), Duration::from_millis(10_010));
+        assert!((lap.fuel_used - 5.99).abs() < 0.01, "{}", lap.fuel_used);
+        assert!((lap.max_speed_kmh - 159.0).abs() < 0.1);
+        assert!((lap.min_speed_kmh - 100.0).abs() < 0.1);
+        assert_eq!(tracker.current_lap(), Some(3));
+    }
+
+    #[test]
+    fn test_packet_loss_and_pause() {
+        let mut tracker = LapTracker::new();
+        let mut stream = Stream::new();
+        stream.run(&mut tracker, 1, 1, None);
+        stream.run(&mut tracker, 60, 2, Some(1));
+        // 丢失10帧
+        stream.packet_id += 10;
+        stream.run(&mut tracker, 50, 2, Some(1));
+        // 暂停2秒不计时
+        for _ in 0..120 {
+            let mut packet = stream.next(2, Some(1));
+            packet.game_state.is_paused = true;
+            assert!(tracker.push(&packet).is_none());
+        }
+        let lap = stream.run(&mut tracker, 1, 3, Some(2_000)).remove(0);
+        assert_eq!(lap.dropped_packets, 10);
+        assert_eq!(lap.samples.len(), 110);
+        assert_eq!(lap.computed_time, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_rewind_invalidates_lap() {
+        let mut tracker = LapTracker::new();
+        let mut stream = Stream::new();
+        stream.run(&mut tracker, 1, 1, None);
+        stream.run(&mut tracker, 120, 2, Some(1));
+        // 倒带1秒 (60帧)
+        stream.time_of_day -= 60 * 17;
+        stream.run(&mut tracker, 59, 2, Some(1));
+        let lap = stream.run(&mut tracker, 1, 3, Some(2_000)).remove(0);
+        assert_eq!(lap.invalidation, Some(LapInvalidation::Rewind));
+        assert_eq!(lap.samples.len(), 120);
+        assert_eq!(lap.computed_time, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_restart_and_leaving_track() {
+        let mut tracker = LapTracker::new();
+        let mut stream = Stream::new();
+        stream.run(&mut tracker, 1, 1, None);
+        stream.run(&mut tracker, 1, 2, Some(1));
+        stream.run(&mut tracker, 1, 3, Some(2));
+        // 回到第1圈
+        let lap = stream.run(&mut tracker, 1, 1, None).remove(0);
+        assert_eq!((lap.number, lap.kind), (3, LapKind::InLap));
+        assert_eq!(lap.invalidation, Some(LapInvalidation::Restart));
+
+        // 计时圈中离开赛道
+        stream.run(&mut tracker, 1, 2, Some(3));
+        let mut off_track = stream.next(2, Some(3));
+        off_track.game_state.race_info = None;
+        let lap = tracker.push(&off_track).unwrap();
+        assert_eq!((lap.number, lap.kind), (2, LapKind::InLap));
+        assert_eq!(lap.invalidation, Some(LapInvalidation::Incomplete));
+        assert_eq!(lap.official_time, None);
+        assert!(tracker.current_lap().is_none());
+
+        // 序号重置
+        stream.packet_id = 5_000;
+        stream.run(&mut tracker, 1, 2, Some(3));
+        stream.packet_id = 0;
+        let lap = stream.run(&mut tracker, 1, 2, Some(3)).remove(0);
+        assert_eq!(lap.invalidation, Some(LapInvalidation::Restart));
+        assert!(tracker.finish().is_some());
+    }
+}
@@ -4,29 +4,78 @@
 //! 支持实时监控游戏状态、车辆信息、赛道情况等
 
 pub mod error;
+pub mod fuel;
+pub mod export;
+pub mod address;
 pub mod packet;
+pub mod recorder;
+pub mod replay;
+pub mod sectors;
+pub mod builder;
+pub mod corners;
 pub mod client;
+pub mod crypto;
+pub mod discovery;
+pub mod jitter;
+pub mod laps;
+pub mod motec;
+pub mod simulator;
+pub mod stats;
+pub mod track_map;
 pub mod types;
+pub mod tyres;
 
 pub use error::{GT7Error, Result};
 pub use packet::{GT7TelemetryPacket, GameState, CarInfo, TrackInfo};
-pub use client::GT7TelemetryClient;
+pub use builder::PacketBuilder;
+pub use client::{ConnectionEvent, GT7TelemetryClient};
+pub use laps::{Lap, LapTracker};
+pub use replay::ReplaySource;
+pub use stats::ConnectionStats;
 pub use types::*;
 
-/// GT7默认遥测端口 (参考gt7telemetry)
+/// GT7主机接收心跳的端口 (参考gt7telemetry)
+pub const GT7_HEARTBEAT_PORT: u16 = 33739;
+
+/// GT7默认遥测端口 (主机将数据包发送到心跳来源IP的该端口，参考gt7telemetry)
 pub const GT7_TELEMETRY_PORT: u16 = 33740;
 
-/// GT7遥测数据包大小 (参考gt7telemetry)
+/// GT7发送遥测数据包的频率 (Hz)
+pub const GT7_PACKET_RATE: u32 = 60;
+
+/// GT7遥测数据包大小 (参考gt7telemetry，心跳"A")
 pub const GT7_PACKET_SIZE: usize = 296;
 
-/// GT7发送心跳包的魔术字节 (参考gt7telemetry)
+/// GT7遥测数据包最大大小 (心跳"~")
+pub const GT7_MAX_PACKET_SIZE: usize = 344;
+
+/// GT7发送心跳包的魔术字节 (参考gt7telemetry，心跳"A")
 pub const GT7_HEARTBEAT: &[u8] = b"A";
 
 /// GT7 IP地址范围验证
+/// 
+/// 按默认策略检查: 只接受回环、私有和链路本地的IPv4地址。
+/// 需要额外网段、IPv6或主机名时使用 [`address::AddressPolicy`]
 pub fn is_valid_gt7_ip(ip: &str) -> bool {
     // GT7通常在局域网内运行
-    ip.starts_with("192.168.") || 
-    ip.starts_with("10.") || 
-    ip.starts_with("172.") ||
-    ip == "127.0.0.1"
-}
\ No newline at end of file
+    ip.parse().is_ok_and(|ip| address::AddressPolicy::default().is_allowed(ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_gt7_ip() {
+        assert!(is_valid_gt7_ip("192.168.1.30"));
+        assert!(is_valid_gt7_ip("10.0.0.2"));
+        assert!(is_valid_gt7_ip("172.16.0.1"));
+        assert!(is_valid_gt7_ip("127.0.0.1"));
+        assert!(!is_valid_gt7_ip("172.32.0.1"));
+        assert!(!is_valid_gt7_ip("172.999.1.1"));
+        assert!(!is_valid_gt7_ip("8.8.8.8"));
+        assert!(!is_valid_gt7_ip("::1"));
+        assert!(!is_valid_gt7_ip("localhost"));
+        assert!(!is_valid_gt7_ip(""));
+    }
+}
@@ -0,0 +1,524 @@
+//! MoTeC i2 日志导出
+//!
+//! 把录制会话转换为MoTeC i2可以打开的 `.ld` 数据文件和 `.ldx` 附加文件 (圈速信标)。
+//! `.ld` 文件布局参考社区整理的格式 (ldparser): 文件头、赛事/场地/车辆信息块、
+//! 通道元数据链表，最后是各通道的采样数据。所有通道按GT7的60Hz发送频率以float32存储
+
+use crate::error::{Result, GT7Error};
+use crate::export;
+use crate::packet::GT7TelemetryPacket;
+use crate::recorder::RECORDING_EXTENSION;
+use crate::replay::ReplaySource;
+use byteorder::{LittleEndian, WriteBytesExt};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 通道采样频率 (Hz)，与GT7发送频率一致
+pub const MOTEC_SAMPLE_RATE: u16 = 60;
+
+/// 超过该数量的缺失数据包不再补齐 (视为暂停或重新开始)
+const MAX_GAP_FILL: u32 = MOTEC_SAMPLE_RATE as u32 * 5;
+
+const HEADER_SIZE: u32 = 1762;
+const EVENT_SIZE: u32 = 1154;
+const VENUE_SIZE: u32 = 1100;
+const VEHICLE_SIZE: u32 = 260;
+const CHANNEL_SIZE: u32 = 124;
+
+/// 日志元数据
+#[derive(Debug, Clone)]
+pub struct MotecMetadata {
+    /// 车手
+    pub driver: String,
+    /// 车辆 (为空时使用GT7车辆代码)
+    pub vehicle: String,
+    /// 赛道
+    pub venue: String,
+    /// 赛事名称
+    pub event: String,
+    /// 节次 (练习/排位/正赛)
+    pub session: String,
+    /// 备注
+    pub comment: String,
+    /// 日志开始时间 (本地时间)
+    pub started_at: NaiveDateTime,
+}
+
+impl Default for MotecMetadata {
+    fn default() -> Self {
+        Self {
+            driver: String::new(),
+            vehicle: String::new(),
+            venue: String::new(),
+            event: String::new(),
+            session: String::new(),
+            comment: String::new(),
+            started_at: Local::now().naive_local(),
+        }
+    }
+}
+
+/// 圈速信标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LapBeacon {
+    /// 信标之后开始的圈数
+    pub lap: u16,
+    /// 相对日志开始的时间
+    pub time: Duration,
+    /// 刚完成的一圈的时间 (来自数据包的上一圈时间)
+    pub completed_lap_time: Option<Duration>,
+}
+
+/// 导出的通道
+struct Channel {
+    name: &'static str,
+    short_name: &'static str,
+    unit: &'static str,
+    extract: fn(&GT7TelemetryPacket) -> f32,
+}
+
+const CHANNELS: &[Channel] = &[
+    Channel { name: "Ground Speed", short_name: "Speed", unit: "km/h", extract: |p| p.car_info.speed * 3.6 },
+    Channel { name: "Engine RPM", short_name: "RPM", unit: "rpm", extract: |p| p.car_info.engine.rpm },
+    Channel { name: "Throttle Pos", short_name: "Thr", unit: "%", extract: |p| p.car_info.engine.throttle * 100.0 },
+    Channel { name: "Brake Pos", short_name: "Brk", unit: "%", extract: |p| p.car_info.engine.brake * 100.0 },
+    Channel { name: "Gear", short_name: "Gear", unit: "", extract: |p| f32::from(p.car_info.engine.gear) },
+    Channel {
+        name: "Steered Angle",
+        short_name: "Steer",
+        unit: "deg",
+        extract: |p| p.motion.as_ref().map_or(0.0, |m| m.wheel_rotation.to_degrees()),
+    },
+    Channel {
+        name: "Yaw Rate",
+        short_name: "YawRate",
+        unit: "deg/s",
+        extract: |p| p.car_info.position.angular_velocity.y.to_degrees(),
+    },
+    Channel { name: "Tyre Temp FL", short_name: "TTmpFL", unit: "C", extract: |p| p.car_info.tires.front_left.temperature },
+    Channel { name: "Tyre Temp FR", short_name: "TTmpFR", unit: "C", extract: |p| p.car_info.tires.front_right.temperature },
+    Channel { name: "Tyre Temp RL", short_name: "TTmpRL", unit: "C", extract: |p| p.car_info.tires.rear_left.temperature },
+    Channel { name: "Tyre Temp RR", short_name: "TTmpRR", unit: "C", extract: |p| p.car_info.tires.rear_right.temperature },
+    Channel {
+        name: "Susp Pos FL",
+        short_name: "SuspFL",
+        unit: "mm",
+        extract: |p| p.car_info.tires.front_left.suspension_travel * 1000.0,
+    },
+    Channel {
+        name: "Susp Pos FR",
+        short_name: "SuspFR",
+        unit: "mm",
+        extract: |p| p.car_info.tires.front_right.suspension_travel * 1000.0,
+    },
+    Channel {
+        name: "Susp Pos RL",
+        short_name: "SuspRL",
+        unit: "mm",
+        extract: |p| p.car_info.tires.rear_left.suspension_travel * 1000.0,
+    },
+    Channel {
+        name: "Susp Pos RR",
+        short_name: "SuspRR",
+        unit: "mm",
+        extract: |p| p.car_info.tires.rear_right.suspension_travel * 1000.0,
+    },
+    Channel { name: "Fuel Level", short_name: "Fuel", unit: "l", extract: |p| p.car_info.engine.fuel_remaining },
+    Channel {
+        name: "Lap Number",
+        short_name: "Lap",
+        unit: "",
+        extract: |p| p.game_state.race_info.as_ref().map_or(0.0, |r| f32::from(r.current_lap)),
+    },
+];
+
+/// 整理好的MoTeC日志
+///
+/// 只保留车辆在赛道上且未暂停的数据包，少量丢包用前一个采样补齐以保持固定采样频率
+#[derive(Debug, Clone)]
+pub struct MotecLog<'a> {
+    samples: Vec<&'a GT7TelemetryPacket>,
+    beacons: Vec<LapBeacon>,
+}
+
+impl<'a> MotecLog<'a> {
+    /// 从按接收顺序排列的数据包创建
+    pub fn new(packets: &'a [GT7TelemetryPacket]) -> Self {
+        let mut samples: Vec<&GT7TelemetryPacket> = Vec::with_capacity(packets.len());
+        let mut beacons = Vec::new();
+        let mut last_id: Option<u32> = None;
+        // 上一个采样的圈数，None表示需要重新开始 (不跨越暂停补齐)
+        let mut last_lap: Option<u16> = None;
+
+        for packet in packets {
+            let delta = last_id.map(|id| packet.packet_id.wrapping_sub(id) as i32);
+            if delta.is_some_and(|delta| delta <= 0) {
+                // 重复或乱序
+                continue;
+            }
+            last_id = Some(packet.packet_id);
+
+            let race = match &packet.game_state.race_info {
+                Some(race) if !packet.game_state.is_paused => race,
+                _ => {
+                    last_lap = None;
+                    continue;
+                }
+            };
+
+            if let (Some(delta), Some(previous), Some(_)) = (delta, samples.last().copied(), last_lap) {
+                let missing = delta as u32 - 1;
+                if missing <= MAX_GAP_FILL {
+                    samples.extend(std::iter::repeat_n(previous, missing as usize));
+                }
+            }
+
+            if last_lap.is_some_and(|lap| race.current_lap > lap) {
+                beacons.push(LapBeacon {
+                    lap: race.current_lap,
+                    time: sample_time(samples.len()),
+                    completed_lap_time: race.last_lap_time.map(|ms| Duration::from_millis(u64::from(ms))),
+                });
+            }
+            last_lap = Some(race.current_lap);
+            samples.push(packet);
+        }
+
+        Self { samples, beacons }
+    }
+
+    /// 采样数
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// 是否没有采样
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 日志时长
+    pub fn duration(&self) -> Duration {
+        sample_time(self.samples.len())
+    }
+
+    /// 圈速信标
+    pub fn beacons(&self) -> &[LapBeacon] {
+        &self.beacons
+    }
+
+    /// 最快圈 (圈数, 圈速)
+    pub fn fastest_lap(&self) -> Option<(u16, Duration)> {
+        self.beacons
+            .iter()
+            .filter_map(|beacon| Some((beacon.lap.checked_sub(1)?, beacon.completed_lap_time?)))
+            .min_by_key(|&(_, time)| time)
+    }
+
+    /// 写出 `.ld` 数据文件
+    pub fn write_ld(&self, mut writer: impl Write, metadata: &MotecMetadata) -> Result<()> {
+        let event_ptr = HEADER_SIZE;
+        let venue_ptr = event_ptr + EVENT_SIZE;
+        let vehicle_ptr = venue_ptr + VENUE_SIZE;
+        let meta_ptr = vehicle_ptr + VEHICLE_SIZE;
+        let data_ptr = meta_ptr + CHANNEL_SIZE * CHANNELS.len() as u32;
+        let channel_bytes = self.samples.len() as u32 * 4;
+
+        let vehicle = match (metadata.vehicle.is_empty(), self.samples.first()) {
+            (true, Some(packet)) => format!("GT7 car {}", packet.car_info.car_code),
+            _ => metadata.vehicle.clone(),
+        };
+
+        let mut buf = Vec::with_capacity((data_ptr + channel_bytes * CHANNELS.len() as u32) as usize);
+
+        // 文件头
+        buf.write_u32::<LittleEndian>(0x40).unwrap();
+        zeros(&mut buf, 4);
+        buf.write_u32::<LittleEndian>(meta_ptr).unwrap();
+        buf.write_u32::<LittleEndian>(data_ptr).unwrap();
+        zeros(&mut buf, 20);
+        buf.write_u32::<LittleEndian>(event_ptr).unwrap();
+        zeros(&mut buf, 24);
+        for value in [1u16, 0x4240, 0xf] {
+            buf.write_u16::<LittleEndian>(value).unwrap();
+        }
+        buf.write_u32::<LittleEndian>(0x1f44).unwrap();
+        fixed_str(&mut buf, "ADL", 8);
+        buf.write_u16::<LittleEndian>(420).unwrap();
+        buf.write_u16::<LittleEndian>(0xadb0).unwrap();
+        buf.write_u32::<LittleEndian>(CHANNELS.len() as u32).unwrap();
+        zeros(&mut buf, 4);
+        fixed_str(&mut buf, &metadata.started_at.format("%d/%m/%Y").to_string(), 16);
+        zeros(&mut buf, 16);
+        fixed_str(&mut buf, &metadata.started_at.format("%H:%M:%S").to_string(), 16);
+        zeros(&mut buf, 16);
+        fixed_str(&mut buf, &metadata.driver, 64);
+        fixed_str(&mut buf, &vehicle, 64);
+        zeros(&mut buf, 64);
+        fixed_str(&mut buf, &metadata.venue, 64);
+        zeros(&mut buf, 64 + 1024);
+        // 启用Pro日志功能的标记
+        buf.write_u32::<LittleEndian>(0xc81a4).unwrap();
+        zeros(&mut buf, 66);
+        fixed_str(&mut buf, &metadata.comment, 64);
+        zeros(&mut buf, 126);
+        debug_assert_eq!(buf.len() as u32, event_ptr);
+
+        // 赛事
+        fixed_str(&mut buf, &metadata.event, 64);
+        fixed_str(&mut buf, &metadata.session, 64);
+        fixed_str(&mut buf, &metadata.comment, 1024);
+        buf.write_u16::<LittleEndian>(venue_ptr as u16).unwrap();
+
+        // 场地
+        fixed_str(&mut buf, &metadata.venue, 64);
+        zeros(&mut buf, 1034);
+        buf.write_u16::<LittleEndian>(vehicle_ptr as u16).unwrap();
+
+        // 车辆
+        fixed_str(&mut buf, &vehicle, 64);
+        zeros(&mut buf, 128);
+        buf.write_u32::<LittleEndian>(0).unwrap();
+        fixed_str(&mut buf, "GT7", 32);
+        zeros(&mut buf, 32);
+        debug_assert_eq!(buf.len() as u32, meta_ptr);
+
+        // 通道元数据 (双向链表)
+        for (index, channel) in CHANNELS.iter().enumerate() {
+            let index = index as u32;
+            let this_ptr = meta_ptr + index * CHANNEL_SIZE;
+            let prev_ptr = if index == 0 { 0 } else { this_ptr - CHANNEL_SIZE };
+            let next_ptr = if index + 1 == CHANNELS.len() as u32 { 0 } else { this_ptr + CHANNEL_SIZE };
+            buf.write_u32::<LittleEndian>(prev_ptr).unwrap();
+            buf.write_u32::<LittleEndian>(next_ptr).unwrap();
+            buf.write_u32::<LittleEndian>(data_ptr + index * channel_bytes).unwrap();
+            buf.write_u32::<LittleEndian>(self.samples.len() as u32).unwrap();
+            buf.write_u16::<LittleEndian>(0x2ee1 + index as u16).unwrap();
+            // 数据类型: float32
+            buf.write_u16::<LittleEndian>(0x07).unwrap();
+            buf.write_u16::<LittleEndian>(4).unwrap();
+            buf.write_u16::<LittleEndian>(MOTEC_SAMPLE_RATE).unwrap();
+            // shift, mul, scale, 小数位
+            for value in [0i16, 1, 1, 0] {
+                buf.write_i16::<LittleEndian>(value).unwrap();
+            }
+            fixed_str(&mut buf, channel.name, 32);
+            fixed_str(&mut buf, channel.short_name, 8);
+            fixed_str(&mut buf, channel.unit, 12);
+            zeros(&mut buf, 40);
+        }
+        debug_assert_eq!(buf.len() as u32, data_ptr);
+
+        // 通道数据
+        for channel in CHANNELS {
+            for packet in &self.samples {
+                buf.write_f32::<LittleEndian>((channel.extract)(packet)).unwrap();
+            }
+        }
+
+        writer.write_all(&buf)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 写出 `.ldx` 附加文件 (圈速信标和概要)
+    pub fn write_ldx(&self, mut writer: impl Write) -> Result<()> {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\"?>\n");
+        xml.push_str("<LDXFile Locale=\"English_United Kingdom.1252\" DefaultLocale=\"C\" Version=\"1.6\">\n");
+        xml.push_str(" <Layers>\n  <Layer>\n   <MarkerBlock>\n");
+        xml.push_str("    <MarkerGroup Name=\"Beacons\" Index=\"3\">\n");
+        for (index, beacon) in self.beacons.iter().enumerate() {
+            let _ = writeln!(
+                xml,
+                "     <Marker Version=\"100\" ClassName=\"BCN\" Name=\"Manual.{}\" Flags=\"77\" Time=\"{}.000000\"/>",
+                index + 1,
+                beacon.time.as_micros()
+            );
+        }
+        xml.push_str("    </MarkerGroup>\n   </MarkerBlock>\n   <RangeBlock/>\n  </Layer>\n");
+        xml.push_str("  <Details>\n");
+        let _ = writeln!(xml, "   <String Id=\"Total Laps\" Value=\"{}\"/>", self.beacons.len() + 1);
+        if let Some((lap, time)) = self.fastest_lap() {
+            let _ = writeln!(xml, "   <String Id=\"Fastest Time\" Value=\"{}\"/>", format_lap_time(time));
+            let _ = writeln!(xml, "   <String Id=\"Fastest Lap\" Value=\"{}\"/>", lap);
+        }
+        xml.push_str("  </Details>\n </Layers>\n</LDXFile>\n");
+
+        writer.write_all(xml.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 把录制会话 (`.gt7r`) 或JSON Lines数据包文件导出为 `.ld` 和同名的 `.ldx`
+///
+/// 录制文件的开始时间会覆盖 `metadata.started_at`
+///
+/// # 返回
+///
+/// 写出的 `.ld` 和 `.ldx` 文件路径
+pub fn export_session(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    mut metadata: MotecMetadata,
+) -> Result<(PathBuf, PathBuf)> {
+    let input = input.as_ref();
+    let packets = if input.extension().is_some_and(|extension| extension == RECORDING_EXTENSION) {
+        let (replay, _) = ReplaySource::open(input)?;
+        if let Some(started_at) = Local.timestamp_millis_opt(replay.header().started_at_ms).single() {
+            metadata.started_at = started_at.naive_local();
+        }
+        replay.frames().iter().map(|frame| frame.packet.clone()).collect()
+    } else {
+        export::read_packets(input)?
+    };
+
+    let log = MotecLog::new(&packets);
+    if log.is_empty() {
+        return Err(GT7Error::config_error("input", input.display().to_string(), "没有车辆在赛道上的数据"));
+    }
+
+    let ld_path = output.as_ref().with_extension("ld");
+    let ldx_path = output.as_ref().with_extension("ldx");
+    log.write_ld(BufWriter::new(create(&ld_path)?), &metadata)?;
+    log.write_ldx(BufWriter::new(create(&ldx_path)?))?;
+    log::info!(
+        "导出MoTeC日志 {}: {} 个采样，{} 个圈速信标",
+        ld_path.display(),
+        log.len(),
+        log.beacons().len()
+    );
+    Ok((ld_path, ldx_path))
+}
+
+fn create(path: &Path) -> Result<File> {
+    File::create(path).map_err(|e| GT7Error::file_error(format!("创建 {}: {}", path.display(), e)))
+}
+
+fn sample_time(samples: usize) -> Duration {
+    Duration::from_micros(samples as u64 * 1_000_000 / u64::from(MOTEC_SAMPLE_RATE))
+}
+
+fn format_lap_time(time: Duration) -> String {
+    let ms = time.as_millis();
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+fn zeros(buf: &mut Vec<u8>, len: usize) {
+    buf.resize(buf.len() + len, 0);
+}
+
+/// 定长字符串字段 (截断并以0填充)
+fn fixed_str(buf: &mut Vec<u8>, value: &str, len: usize) {
+    let bytes = value.as_bytes();
+    let bytes = &bytes[..bytes.len().min(len)];
+    buf.extend_from_slice(bytes);
+    zeros(buf, len - bytes.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+
+    fn u32_at(data: &[u8], offset: u32) -> u32 {
+        let offset = offset as usize;
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn str_at(data: &[u8], offset: u32, len: usize) -> &str {
+        let field = &data[offset as usize..offset as usize + len];
+        let end = field.iter().position(|&b| b == 0).unwrap_or(len);
+        std::str::from_utf8(&field[..end]).unwrap()
+    }
+
+    /// 第1圈120帧，第2圈从120开始，中间丢2帧，然后暂停
+    fn packets() -> Vec<GT7TelemetryPacket> {
+        let mut packets: Vec<_> = (0..240u32)
+            .filter(|id| !(150..152).contains(id))
+            .map(|id| {
+                let lap = if id < 120 { 1 } else { 2 };
+                let last = (id >= 120).then_some(83_456);
+                PacketBuilder::new()
+                    .packet_id(id)
+                    .lap(lap, 3)
+                    .lap_times(last, last)
+                    .speed_kmh(id as f32)
+                    .gear(3)
+                    .build()
+            })
+            .collect();
+        let mut paused = PacketBuilder::new().packet_id(240).lap(2, 3).build();
+        paused.game_state.is_paused = true;
+        packets.push(paused);
+        packets
+    }
+
+    #[test]
+    fn test_log_resamples_and_beacons() {
+        let packets = packets();
+        let log = MotecLog::new(&packets);
+        // 丢失的2帧被补齐，暂停帧被丢弃
+        assert_eq!(log.len(), 240);
+        assert_eq!(log.beacons().len(), 1);
+        assert_eq!(log.beacons()[0].lap, 2);
+        assert_eq!(log.beacons()[0].time, Duration::from_secs(2));
+        assert_eq!(log.fastest_lap(), Some((1, Duration::from_millis(83_456))));
+        assert_eq!(log.samples[151].packet_id, 149);
+    }
+
+    #[test]
+    fn test_ld_layout() {
+        let packets = packets();
+        let log = MotecLog::new(&packets);
+        let metadata = MotecMetadata {
+            driver: "Tester".into(),
+            venue: "Suzuka".into(),
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        log.write_ld(&mut data, &metadata).unwrap();
+
+        assert_eq!(u32_at(&data, 0), 0x40);
+        let meta_ptr = u32_at(&data, 8);
+        let data_ptr = u32_at(&data, 12);
+        assert_eq!(u32_at(&data, 36), HEADER_SIZE);
+        assert_eq!(u32_at(&data, 86), CHANNELS.len() as u32);
+        assert_eq!(str_at(&data, 158, 64), "Tester");
+        assert_eq!(str_at(&data, 222, 64), "GT7 car 0");
+        assert_eq!(str_at(&data, 350, 64), "Suzuka");
+        assert_eq!(data.len() as u32, data_ptr + CHANNELS.len() as u32 * 240 * 4);
+
+        // 遍历通道链表
+        let mut names = Vec::new();
+        let mut ptr = meta_ptr;
+        while ptr != 0 {
+            names.push(str_at(&data, ptr + 32, 32).to_string());
+            assert_eq!(u32_at(&data, ptr + 12), 240);
+            if names.last().unwrap() == "Ground Speed" {
+                let samples = u32_at(&data, ptr + 8);
+                let value = f32::from_bits(u32_at(&data, samples + 4 * 100));
+                assert!((value - 100.0).abs() < 0.01);
+            }
+            ptr = u32_at(&data, ptr + 4);
+        }
+        assert_eq!(names.len(), CHANNELS.len());
+        assert!(names.iter().any(|name| name == "Lap Number"));
+    }
+
+    #[test]
+    fn test_ldx_beacons() {
+        let packets = packets();
+        let mut xml = Vec::new();
+        MotecLog::new(&packets).write_ldx(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("Time=\"2000000.000000\""));
+        assert!(xml.contains("<String Id=\"Fastest Time\" Value=\"1:23.456\"/>"));
+        assert!(xml.contains("<String Id=\"Total Laps\" Value=\"2\"/>"));
+    }
+}
@@ -1,32 +1,137 @@
 //! GT7遥测数据包解析
-//! 
+//!
 //! 参考gt7telemetry Python库实现二进制数据包解析
 
 use crate::error::{Result, GT7Error};
 use crate::types::*;
 use byteorder::{LittleEndian, ReadBytesExt};
 use serde::{Deserialize, Serialize};
-use std::io::{Cursor, Read};
+use std::io::Cursor;
 use std::time::Duration;
 
-/// GT7遥测数据包版本
-pub const GT7_PACKET_VERSION: u16 = 1;
+/// GT7明文数据包字段偏移 (参考gt7telemetry)
+pub mod offsets {
+    /// 魔术字节 (u32)
+    pub const MAGIC: u64 = 0x00;
+    /// 世界坐标位置 (3×f32)
+    pub const POSITION: u64 = 0x04;
+    /// 速度向量 (3×f32)
+    pub const VELOCITY: u64 = 0x10;
+    /// 车身旋转 pitch/yaw/roll (3×f32)
+    pub const ROTATION: u64 = 0x1C;
+    /// 相对正北的朝向 (f32)
+    pub const ORIENTATION: u64 = 0x28;
+    /// 角速度 (3×f32)
+    pub const ANGULAR_VELOCITY: u64 = 0x2C;
+    /// 车身离地高度 (f32)
+    pub const RIDE_HEIGHT: u64 = 0x38;
+    /// 发动机转速 (f32)
+    pub const RPM: u64 = 0x3C;
+    /// 剩余燃油 (f32)
+    pub const FUEL_LEVEL: u64 = 0x44;
+    /// 油箱容量 (f32)
+    pub const FUEL_CAPACITY: u64 = 0x48;
+    /// 车速 (f32, m/s)
+    pub const SPEED: u64 = 0x4C;
+    /// 涡轮增压 (f32, 值-1为bar)
+    pub const BOOST: u64 = 0x50;
+    /// 机油压力 (f32)
+    pub const OIL_PRESSURE: u64 = 0x54;
+    /// 水温 (f32)
+    pub const WATER_TEMPERATURE: u64 = 0x58;
+    /// 机油温度 (f32)
+    pub const OIL_TEMPERATURE: u64 = 0x5C;
+    /// 胎面温度 FL/FR/RL/RR (4×f32)
+    pub const TYRE_TEMPERATURES: u64 = 0x60;
+    /// 数据包序号 (i32)
+    pub const PACKET_ID: u64 = 0x70;
+    /// 当前圈数 (i16)
+    pub const CURRENT_LAP: u64 = 0x74;
+    /// 总圈数 (i16)
+    pub const TOTAL_LAPS: u64 = 0x76;
+    /// 最快圈速 (i32, 毫秒, -1表示无)
+    pub const BEST_LAP_TIME: u64 = 0x78;
+    /// 上一圈时间 (i32, 毫秒, -1表示无)
+    pub const LAST_LAP_TIME: u64 = 0x7C;
+    /// 游戏内时间 (i32, 毫秒)
+    pub const TIME_OF_DAY: u64 = 0x80;
+    /// 起跑位置 (i16)
+    pub const START_POSITION: u64 = 0x84;
+    /// 参赛车辆数 (i16)
+    pub const TOTAL_CARS: u64 = 0x86;
+    /// 换挡提示最低转速 (u16)
+    pub const MIN_ALERT_RPM: u64 = 0x88;
+    /// 换挡提示最高转速 (u16)
+    pub const MAX_ALERT_RPM: u64 = 0x8A;
+    /// 计算极速 (i16, km/h)
+    pub const MAX_SPEED: u64 = 0x8C;
+    /// 状态标志位 (u16)
+    pub const FLAGS: u64 = 0x8E;
+    /// 档位 (u8, 低4位当前档位，高4位建议档位)
+    pub const GEARS: u64 = 0x90;
+    /// 油门 (u8)
+    pub const THROTTLE: u64 = 0x91;
+    /// 刹车 (u8)
+    pub const BRAKE: u64 = 0x92;
+    /// 路面法向量 (3×f32)
+    pub const ROAD_PLANE: u64 = 0x94;
+    /// 路面平面距离 (f32)
+    pub const ROAD_PLANE_DISTANCE: u64 = 0xA0;
+    /// 车轮转速 FL/FR/RL/RR (4×f32, rad/s)
+    pub const WHEEL_SPEEDS: u64 = 0xA4;
+    /// 轮胎半径 FL/FR/RL/RR (4×f32)
+    pub const TYRE_RADII: u64 = 0xB4;
+    /// 悬挂高度 FL/FR/RL/RR (4×f32)
+    pub const SUSPENSION_HEIGHTS: u64 = 0xC4;
+    /// 离合器踏板 (f32)
+    pub const CLUTCH: u64 = 0xF4;
+    /// 离合器接合度 (f32)
+    pub const CLUTCH_ENGAGEMENT: u64 = 0xF8;
+    /// 离合器后转速 (f32)
+    pub const RPM_AFTER_CLUTCH: u64 = 0xFC;
+    /// 极速传动比 (f32)
+    pub const TOP_SPEED_RATIO: u64 = 0x100;
+    /// 1-8档传动比 (8×f32)
+    pub const GEAR_RATIOS: u64 = 0x104;
+    /// 车辆代码 (i32)
+    pub const CAR_CODE: u64 = 0x124;
+
+    /// 方向盘转角 (f32, 弧度, 心跳"B"及以上)
+    pub const WHEEL_ROTATION: u64 = 0x128;
+    /// 横向加速度 (f32, 心跳"B"及以上)
+    pub const SWAY: u64 = 0x130;
+    /// 垂直加速度 (f32, 心跳"B"及以上)
+    pub const HEAVE: u64 = 0x134;
+    /// 纵向加速度 (f32, 心跳"B"及以上)
+    pub const SURGE: u64 = 0x138;
+
+    /// 过滤后油门 (u8, 心跳"~")
+    pub const THROTTLE_FILTERED: u64 = 0x13C;
+    /// 过滤后刹车 (u8, 心跳"~")
+    pub const BRAKE_FILTERED: u64 = 0x13D;
+    /// 能量回收 (f32, 心跳"~")
+    pub const ENERGY_RECOVERY: u64 = 0x140;
+}
 
 /// GT7遥测数据包 (参考gt7telemetry的数据结构)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GT7TelemetryPacket {
-    /// 数据包版本
-    pub version: u16,
+    /// 数据包格式
+    pub variant: PacketVariant,
     /// 游戏状态
     pub game_state: GameState,
     /// 车辆信息
     pub car_info: CarInfo,
     /// 赛道信息
     pub track_info: TrackInfo,
-    /// 数据包时间戳
+    /// 游戏内时间 (毫秒)
     pub timestamp: u64,
     /// 数据包计数器
     pub packet_id: u32,
+    /// 附加运动数据 (心跳"B"及以上)
+    pub motion: Option<MotionData>,
+    /// 附加输入数据 (心跳"~")
+    pub inputs: Option<InputData>,
 }
 
 /// 游戏状态信息
@@ -34,25 +139,33 @@ pub struct GT7TelemetryPacket {
 pub struct GameState {
     /// 游戏状态类型
     pub state_type: GameStateType,
-    /// 比赛信息
+    /// 比赛信息 (车辆在赛道上时可用)
     pub race_info: Option<RaceInfo>,
     /// 是否在暂停状态
     pub is_paused: bool,
-    /// 是否在重播状态
-    pub is_replay: bool,
-    /// 菜单状态ID
-    pub menu_id: u32,
+    /// 状态标志位
+    pub flags: CarFlags,
 }
 
 /// 车辆信息
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CarInfo {
+    /// 车辆代码 (GT7内部车辆ID)
+    pub car_code: u32,
     /// 车辆位置和速度
     pub position: Position,
+    /// 车速 (m/s)
+    pub speed: f32,
+    /// 计算极速 (km/h)
+    pub max_speed: u16,
+    /// 车身离地高度 (米)
+    pub ride_height: f32,
     /// 轮胎信息
     pub tires: TireInfo,
     /// 发动机信息
     pub engine: EngineInfo,
+    /// 变速箱信息
+    pub transmission: Transmission,
     /// 车辆配置
     pub configuration: Option<CarConfiguration>,
 }
@@ -60,93 +173,102 @@ pub struct CarInfo {
 /// 赛道信息
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TrackInfo {
-    /// 赛道数据
-    pub track_data: TrackData,
-    /// 当前赛段信息
+    /// 车辆下方路面的法向量
+    pub road_plane: Vector3,
+    /// 路面平面到原点的距离
+    pub road_plane_distance: f32,
+    /// 当前赛段 (GT7数据包未提供，默认为0)
     pub current_sector: u8,
-    /// 赛道湿度 (0.0-1.0)
-    pub track_wetness: f32,
 }
 
 impl GT7TelemetryPacket {
     /// 从原始字节数据解析GT7遥测数据包
-    /// 
+    ///
     /// # 参数
-    /// 
-    /// * `data` - 原始字节数据 (应为296字节)
-    /// 
+    ///
+    /// * `data` - 从网络接收的加密字节数据 (296/316/344字节，由长度识别格式)
+    ///
     /// # 返回
-    /// 
+    ///
     /// 解析后的遥测数据包
     pub fn from_bytes(data: &[u8]) -> Result<Self> {
-        if data.len() != crate::GT7_PACKET_SIZE {
-            return Err(GT7Error::incomplete_data(crate::GT7_PACKET_SIZE, data.len()));
-        }
+        let variant = Self::detect_variant(data)?;
+        let plain = crate::crypto::decrypt_packet(data, variant)?;
+        Self::from_decrypted_bytes(&plain)
+    }
+
+    /// 从已解密的明文数据解析GT7遥测数据包
+    ///
+    /// # 参数
+    ///
+    /// * `data` - Salsa20解密后的明文字节数据
+    pub fn from_decrypted_bytes(data: &[u8]) -> Result<Self> {
+        let variant = Self::detect_variant(data)?;
 
         let mut cursor = Cursor::new(data);
-        
+
         // 解析数据包头部
+        cursor.set_position(offsets::MAGIC);
         let magic = cursor.read_u32::<LittleEndian>()
             .map_err(|_| GT7Error::packet_parse_error("魔术字节", 0, 4))?;
-        
-        // 验证魔术字节 (GT7特有的标识)
-        if magic != 0x47375053 { // "GT7S" in little endian
-            return Err(GT7Error::invalid_packet_format("magic"));
-        }
 
-        let version = cursor.read_u16::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("版本", 4, 2))?;
-        
-        if version != GT7_PACKET_VERSION {
-            return Err(GT7Error::packet_version_mismatch(GT7_PACKET_VERSION, version));
+        // 验证魔术字节 ("G7S0")
+        if magic != crate::crypto::GT7_PACKET_MAGIC {
+            return Err(GT7Error::invalid_packet_format("magic"));
         }
 
+        cursor.set_position(offsets::PACKET_ID);
         let packet_id = cursor.read_u32::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("数据包ID", 6, 4))?;
+            .map_err(|_| GT7Error::packet_parse_error("数据包ID", offsets::PACKET_ID as usize, 4))?;
 
-        // 解析游戏状态 (偏移: 10)
-        let game_state = Self::parse_game_state(&mut cursor)?;
+        cursor.set_position(offsets::TIME_OF_DAY);
+        let timestamp = cursor.read_i32::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("游戏内时间", offsets::TIME_OF_DAY as usize, 4))?
+            .max(0) as u64;
 
-        // 解析车辆信息 (偏移: 50) 
-        cursor.set_position(50);
+        let game_state = Self::parse_game_state(&mut cursor)?;
         let car_info = Self::parse_car_info(&mut cursor)?;
-
-        // 解析赛道信息 (偏移: 200)
-        cursor.set_position(200);
         let track_info = Self::parse_track_info(&mut cursor)?;
 
-        // 解析时间戳 (偏移: 280)
-        cursor.set_position(280);
-        let timestamp = cursor.read_u64::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("时间戳", 280, 8))?;
+        // 按格式解析附加数据段
+        let motion = match variant {
+            PacketVariant::A => None,
+            PacketVariant::B | PacketVariant::Tilde => Some(Self::parse_motion_data(&mut cursor)?),
+        };
+        let inputs = match variant {
+            PacketVariant::A | PacketVariant::B => None,
+            PacketVariant::Tilde => Some(Self::parse_input_data(&mut cursor)?),
+        };
 
         Ok(Self {
-            version,
+            variant,
             game_state,
             car_info,
             track_info,
             timestamp,
             packet_id,
+            motion,
+            inputs,
         })
     }
 
+    /// 根据数据长度识别数据包格式
+    fn detect_variant(data: &[u8]) -> Result<PacketVariant> {
+        PacketVariant::from_packet_size(data.len())
+            .ok_or_else(|| GT7Error::incomplete_data(crate::GT7_PACKET_SIZE, data.len()))
+    }
+
     /// 解析游戏状态信息
     fn parse_game_state(cursor: &mut Cursor<&[u8]>) -> Result<GameState> {
-        let state_type_raw = cursor.read_u8()
-            .map_err(|_| GT7Error::packet_parse_error("游戏状态类型", cursor.position() as usize - 1, 1))?;
-        let state_type = GameStateType::from(state_type_raw);
-
-        let is_paused = cursor.read_u8()
-            .map_err(|_| GT7Error::packet_parse_error("暂停状态", cursor.position() as usize - 1, 1))? != 0;
-
-        let is_replay = cursor.read_u8()
-            .map_err(|_| GT7Error::packet_parse_error("重播状态", cursor.position() as usize - 1, 1))? != 0;
-
-        let menu_id = cursor.read_u32::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("菜单ID", cursor.position() as usize - 4, 4))?;
-
-        // 解析比赛信息 (仅在比赛状态下)
-        let race_info = if state_type == GameStateType::InRace {
+        cursor.set_position(offsets::FLAGS);
+        let flags = CarFlags::from(
+            cursor.read_u16::<LittleEndian>()
+                .map_err(|_| GT7Error::packet_parse_error("状态标志位", offsets::FLAGS as usize, 2))?,
+        );
+        let state_type = GameStateType::from_flags(&flags);
+
+        // 解析比赛信息 (仅在车辆位于赛道上时)
+        let race_info = if flags.car_on_track {
             Some(Self::parse_race_info(cursor)?)
         } else {
             None
@@ -155,39 +277,38 @@ impl GT7TelemetryPacket {
         Ok(GameState {
             state_type,
             race_info,
-            is_paused,
-            is_replay,
-            menu_id,
+            is_paused: flags.paused,
+            flags,
         })
     }
 
     /// 解析比赛信息
     fn parse_race_info(cursor: &mut Cursor<&[u8]>) -> Result<RaceInfo> {
-        let current_lap = cursor.read_u16::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("当前圈数", cursor.position() as usize - 2, 2))?;
-
-        let total_laps = cursor.read_u16::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("总圈数", cursor.position() as usize - 2, 2))?;
+        cursor.set_position(offsets::CURRENT_LAP);
+        let current_lap = cursor.read_i16::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("当前圈数", offsets::CURRENT_LAP as usize, 2))?
+            .max(0) as u16;
 
-        let position = cursor.read_u8()
-            .map_err(|_| GT7Error::packet_parse_error("当前位置", cursor.position() as usize - 1, 1))?;
+        let total_laps = cursor.read_i16::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("总圈数", offsets::TOTAL_LAPS as usize, 2))?
+            .max(0) as u16;
 
-        let total_participants = cursor.read_u8()
-            .map_err(|_| GT7Error::packet_parse_error("总参赛者", cursor.position() as usize - 1, 1))?;
+        let best_lap_time_raw = cursor.read_i32::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("最快圈速", offsets::BEST_LAP_TIME as usize, 4))?;
+        let best_lap_time = if best_lap_time_raw > 0 { Some(best_lap_time_raw as u32) } else { None };
 
-        let best_lap_time_raw = cursor.read_u32::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("最快圈速", cursor.position() as usize - 4, 4))?;
-        let best_lap_time = if best_lap_time_raw > 0 { Some(best_lap_time_raw) } else { None };
+        let last_lap_time_raw = cursor.read_i32::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("上圈时间", offsets::LAST_LAP_TIME as usize, 4))?;
+        let last_lap_time = if last_lap_time_raw > 0 { Some(last_lap_time_raw as u32) } else { None };
 
-        let last_lap_time_raw = cursor.read_u32::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("上圈时间", cursor.position() as usize - 4, 4))?;
-        let last_lap_time = if last_lap_time_raw > 0 { Some(last_lap_time_raw) } else { None };
+        cursor.set_position(offsets::START_POSITION);
+        let position = cursor.read_i16::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("起跑位置", offsets::START_POSITION as usize, 2))?
+            .clamp(0, u8::MAX as i16) as u8;
 
-        let current_lap_time = cursor.read_u32::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("当前圈时间", cursor.position() as usize - 4, 4))?;
-
-        let track_progress = cursor.read_f32::<LittleEndian>()
-            .map_err(|_| GT7Error::packet_parse_error("赛道进度", cursor.position() as usize - 4, 4))?;
+        let total_participants = cursor.read_i16::<LittleEndian>()
+            .map_err(|_| GT7Error::packet_parse_error("参赛车辆数", offsets::TOTAL_CARS as usize, 2))?
+            .clamp(0, u8::MAX as i16) as u8;
 
         Ok(RaceInfo {
             current_lap,
@@ -196,141 +317,307 @@ impl GT7TelemetryPacket {
             total_participants,
             best_lap_time,
             last_lap_time,
-            current_lap_time,
-            track_progress,
+            current_lap_time: 0,
+            track_progress: 0.0,
         })
     }
 
-    /// 解析车辆信息
-    fn parse_car_info(cursor: &mut Cursor<&[u8]>) -> Result<CarInfo> {
-        // 解析位置信息
-        let world_x = cursor.read_f32::<LittleEndian>()?;
-        let world_y = cursor.read_f32::<LittleEndian>()?;
-        let world_z = cursor.read_f32::<LittleEndian>()?;
-
-        let vel_x = cursor.read_f32::<LittleEndian>()?;
-        let vel_y = cursor.read_f32::<LittleEndian>()?;
-        let vel_z = cursor.read_f32::<LittleEndian>()?;
+    /// 读取连续的3个f32为向量
+    fn read_vector3(cursor: &mut Cursor<&[u8]>, offset: u64) -> Result<Vector3> {
+        cursor.set_position(offset);
+        Ok(Vector3::new(
+            cursor.read_f32::<LittleEndian>()?,
+            cursor.read_f32::<LittleEndian>()?,
+            cursor.read_f32::<LittleEndian>()?,
+        ))
+    }
 
-        let rot_x = cursor.read_f32::<LittleEndian>()?;
-        let rot_y = cursor.read_f32::<LittleEndian>()?;
-        let rot_z = cursor.read_f32::<LittleEndian>()?;
+    /// 读取按 FL/FR/RL/RR 排列的4个f32
+    fn read_wheels(cursor: &mut Cursor<&[u8]>, offset: u64) -> Result<[f32; 4]> {
+        cursor.set_position(offset);
+        let mut values = [0.0; 4];
+        for value in values.iter_mut() {
+            *value = cursor.read_f32::<LittleEndian>()?;
+        }
+        Ok(values)
+    }
 
-        let ang_vel_x = cursor.read_f32::<LittleEndian>()?;
-        let ang_vel_y = cursor.read_f32::<LittleEndian>()?;
-        let ang_vel_z = cursor.read_f32::<LittleEndian>()?;
+    /// 在指定偏移读取单个f32
+    fn read_f32_at(cursor: &mut Cursor<&[u8]>, offset: u64) -> Result<f32> {
+        cursor.set_position(offset);
+        Ok(cursor.read_f32::<LittleEndian>()?)
+    }
 
+    /// 解析车辆信息
+    fn parse_car_info(cursor: &mut Cursor<&[u8]>) -> Result<CarInfo> {
+        // 解析位置信息
         let position = Position {
-            world: Vector3::new(world_x, world_y, world_z),
-            velocity: Vector3::new(vel_x, vel_y, vel_z),
-            angular_velocity: Vector3::new(ang_vel_x, ang_vel_y, ang_vel_z),
-            rotation: Vector3::new(rot_x, rot_y, rot_z),
+            world: Self::read_vector3(cursor, offsets::POSITION)?,
+            velocity: Self::read_vector3(cursor, offsets::VELOCITY)?,
+            angular_velocity: Self::read_vector3(cursor, offsets::ANGULAR_VELOCITY)?,
+            rotation: Self::read_vector3(cursor, offsets::ROTATION)?,
+            orientation: Self::read_f32_at(cursor, offsets::ORIENTATION)?,
         };
 
+        let ride_height = Self::read_f32_at(cursor, offsets::RIDE_HEIGHT)?;
+        let speed = Self::read_f32_at(cursor, offsets::SPEED)?;
+
         // 解析轮胎信息
+        let temperatures = Self::read_wheels(cursor, offsets::TYRE_TEMPERATURES)?;
+        let wheel_speeds = Self::read_wheels(cursor, offsets::WHEEL_SPEEDS)?;
+        let radii = Self::read_wheels(cursor, offsets::TYRE_RADII)?;
+        let suspension = Self::read_wheels(cursor, offsets::SUSPENSION_HEIGHTS)?;
+
+        let tire = |i: usize| TireData {
+            temperature: temperatures[i],
+            wear: 0.0,
+            suspension_travel: suspension[i],
+            wheel_speed: wheel_speeds[i],
+            radius: radii[i],
+        };
         let tires = TireInfo {
-            front_left: TireData {
-                temperature: cursor.read_f32::<LittleEndian>()?,
-                wear: cursor.read_f32::<LittleEndian>()?,
-                suspension_travel: cursor.read_f32::<LittleEndian>()?,
-                wheel_speed: cursor.read_f32::<LittleEndian>()?,
-                radius: cursor.read_f32::<LittleEndian>()?,
-            },
-            front_right: TireData {
-                temperature: cursor.read_f32::<LittleEndian>()?,
-                wear: cursor.read_f32::<LittleEndian>()?,
-                suspension_travel: cursor.read_f32::<LittleEndian>()?,
-                wheel_speed: cursor.read_f32::<LittleEndian>()?,
-                radius: cursor.read_f32::<LittleEndian>()?,
-            },
-            rear_left: TireData {
-                temperature: cursor.read_f32::<LittleEndian>()?,
-                wear: cursor.read_f32::<LittleEndian>()?,
-                suspension_travel: cursor.read_f32::<LittleEndian>()?,
-                wheel_speed: cursor.read_f32::<LittleEndian>()?,
-                radius: cursor.read_f32::<LittleEndian>()?,
-            },
-            rear_right: TireData {
-                temperature: cursor.read_f32::<LittleEndian>()?,
-                wear: cursor.read_f32::<LittleEndian>()?,
-                suspension_travel: cursor.read_f32::<LittleEndian>()?,
-                wheel_speed: cursor.read_f32::<LittleEndian>()?,
-                radius: cursor.read_f32::<LittleEndian>()?,
-            },
+            front_left: tire(0),
+            front_right: tire(1),
+            rear_left: tire(2),
+            rear_right: tire(3),
         };
 
         // 解析发动机信息
-        let fuel_remaining = cursor.read_f32::<LittleEndian>()?;
-        let fuel_capacity = cursor.read_f32::<LittleEndian>()?;
+        let fuel_remaining = Self::read_f32_at(cursor, offsets::FUEL_LEVEL)?;
+        let fuel_capacity = Self::read_f32_at(cursor, offsets::FUEL_CAPACITY)?;
         let fuel_level = if fuel_capacity > 0.0 { fuel_remaining / fuel_capacity } else { 0.0 };
-        
+
+        cursor.set_position(offsets::MIN_ALERT_RPM);
+        let min_alert_rpm = cursor.read_u16::<LittleEndian>()? as f32;
+        let max_rpm = cursor.read_u16::<LittleEndian>()? as f32;
+        let max_speed = cursor.read_i16::<LittleEndian>()?.max(0) as u16;
+
+        cursor.set_position(offsets::GEARS);
+        let gears = cursor.read_u8()?;
+        let throttle = cursor.read_u8()? as f32 / 255.0;
+        let brake = cursor.read_u8()? as f32 / 255.0;
+
         let engine = EngineInfo {
-            rpm: cursor.read_f32::<LittleEndian>()?,
-            max_rpm: cursor.read_f32::<LittleEndian>()?,
-            throttle: cursor.read_f32::<LittleEndian>()?,
-            brake: cursor.read_f32::<LittleEndian>()?,
-            clutch: cursor.read_f32::<LittleEndian>()?,
-            gear: cursor.read_i8()?,
-            suggested_gear: cursor.read_i8()?,
+            rpm: Self::read_f32_at(cursor, offsets::RPM)?,
+            max_rpm,
+            min_alert_rpm,
+            throttle,
+            brake,
+            clutch: Self::read_f32_at(cursor, offsets::CLUTCH)?,
+            clutch_engagement: Self::read_f32_at(cursor, offsets::CLUTCH_ENGAGEMENT)?,
+            rpm_after_clutch: Self::read_f32_at(cursor, offsets::RPM_AFTER_CLUTCH)?,
+            gear: (gears & 0x0F) as i8,
+            suggested_gear: (gears >> 4) as i8,
+            boost: Self::read_f32_at(cursor, offsets::BOOST)? - 1.0,
+            oil_pressure: Self::read_f32_at(cursor, offsets::OIL_PRESSURE)?,
+            oil_temperature: Self::read_f32_at(cursor, offsets::OIL_TEMPERATURE)?,
+            water_temperature: Self::read_f32_at(cursor, offsets::WATER_TEMPERATURE)?,
             fuel_remaining,
-            fuel_consumption: cursor.read_f32::<LittleEndian>()?,
+            fuel_consumption: 0.0,
             fuel_capacity,
             fuel_level,
         };
 
+        // 解析变速箱信息
+        let top_speed_ratio = Self::read_f32_at(cursor, offsets::TOP_SPEED_RATIO)?;
+        let mut gear_ratios = [0.0; 8];
+        cursor.set_position(offsets::GEAR_RATIOS);
+        for ratio in gear_ratios.iter_mut() {
+            *ratio = cursor.read_f32::<LittleEndian>()?;
+        }
+
+        cursor.set_position(offsets::CAR_CODE);
+        let car_code = cursor.read_u32::<LittleEndian>()?;
+
         Ok(CarInfo {
+            car_code,
             position,
+            speed,
+            max_speed,
+            ride_height,
             tires,
             engine,
+            transmission: Transmission {
+                top_speed_ratio,
+                gear_ratios,
+            },
             configuration: None, // 车辆配置在别的地方解析
         })
     }
 
     /// 解析赛道信息
     fn parse_track_info(cursor: &mut Cursor<&[u8]>) -> Result<TrackInfo> {
-        let track_id = cursor.read_u32::<LittleEndian>()?;
-        let track_length = cursor.read_f32::<LittleEndian>()?;
-        let altitude = cursor.read_f32::<LittleEndian>()?;
-        
-        let weather_raw = cursor.read_u8()?;
-        let weather = WeatherCondition::from(weather_raw);
-        
-        let road_temperature = cursor.read_f32::<LittleEndian>()?;
-        let air_temperature = cursor.read_f32::<LittleEndian>()?;
-        let current_sector = cursor.read_u8()?;
-        let track_wetness = cursor.read_f32::<LittleEndian>()?;
-
-        // 读取赛道名称 (假设为32字节的UTF-8字符串)
-        let mut track_name_bytes = [0u8; 32];
-        cursor.read_exact(&mut track_name_bytes)?;
-        let track_name = String::from_utf8_lossy(&track_name_bytes)
-            .trim_end_matches('\0')
-            .to_string();
-
-        let track_data = TrackData {
-            track_id,
-            track_name,
-            track_length,
-            altitude,
-            weather,
-            road_temperature,
-            air_temperature,
-        };
-
         Ok(TrackInfo {
-            track_data,
-            current_sector,
-            track_wetness,
+            road_plane: Self::read_vector3(cursor, offsets::ROAD_PLANE)?,
+            road_plane_distance: Self::read_f32_at(cursor, offsets::ROAD_PLANE_DISTANCE)?,
+            current_sector: 0,
         })
     }
 
-    /// 验证数据包完整性
-    pub fn validate(&self) -> Result<()> {
-        // 验证版本
-        if self.version != GT7_PACKET_VERSION {
-            return Err(GT7Error::packet_version_mismatch(GT7_PACKET_VERSION, self.version));
+    /// 解析附加运动数据 (心跳"B"及以上)
+    fn parse_motion_data(cursor: &mut Cursor<&[u8]>) -> Result<MotionData> {
+        Ok(MotionData {
+            wheel_rotation: Self::read_f32_at(cursor, offsets::WHEEL_ROTATION)?,
+            sway: Self::read_f32_at(cursor, offsets::SWAY)?,
+            heave: Self::read_f32_at(cursor, offsets::HEAVE)?,
+            surge: Self::read_f32_at(cursor, offsets::SURGE)?,
+        })
+    }
+
+    /// 解析附加输入数据 (心跳"~")
+    fn parse_input_data(cursor: &mut Cursor<&[u8]>) -> Result<InputData> {
+        cursor.set_position(offsets::THROTTLE_FILTERED);
+        let throttle_filtered = cursor.read_u8()? as f32 / 255.0;
+        let brake_filtered = cursor.read_u8()? as f32 / 255.0;
+
+        Ok(InputData {
+            throttle_filtered,
+            brake_filtered,
+            energy_recovery: Self::read_f32_at(cursor, offsets::ENERGY_RECOVERY)?,
+        })
+    }
+
+    /// 编码为明文字节数据 (`from_decrypted_bytes` 的逆操作)
+    ///
+    /// GT7数据包中不存在的字段 (如轮胎磨损、赛段) 不会被编码
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![0u8; self.variant.packet_size()];
+        let car = &self.car_info;
+        let engine = &car.engine;
+        let tires = [
+            &car.tires.front_left,
+            &car.tires.front_right,
+            &car.tires.rear_left,
+            &car.tires.rear_right,
+        ];
+
+        Self::write_at(&mut data, offsets::MAGIC, &crate::crypto::GT7_PACKET_MAGIC.to_le_bytes());
+
+        // 车辆位置信息
+        Self::write_vector3(&mut data, offsets::POSITION, &car.position.world);
+        Self::write_vector3(&mut data, offsets::VELOCITY, &car.position.velocity);
+        Self::write_vector3(&mut data, offsets::ROTATION, &car.position.rotation);
+        Self::write_f32_at(&mut data, offsets::ORIENTATION, car.position.orientation);
+        Self::write_vector3(&mut data, offsets::ANGULAR_VELOCITY, &car.position.angular_velocity);
+        Self::write_f32_at(&mut data, offsets::RIDE_HEIGHT, car.ride_height);
+        Self::write_f32_at(&mut data, offsets::SPEED, car.speed);
+
+        // 发动机信息
+        Self::write_f32_at(&mut data, offsets::RPM, engine.rpm);
+        Self::write_f32_at(&mut data, offsets::FUEL_LEVEL, engine.fuel_remaining);
+        Self::write_f32_at(&mut data, offsets::FUEL_CAPACITY, engine.fuel_capacity);
+        Self::write_f32_at(&mut data, offsets::BOOST, engine.boost + 1.0);
+        Self::write_f32_at(&mut data, offsets::OIL_PRESSURE, engine.oil_pressure);
+        Self::write_f32_at(&mut data, offsets::WATER_TEMPERATURE, engine.water_temperature);
+        Self::write_f32_at(&mut data, offsets::OIL_TEMPERATURE, engine.oil_temperature);
+        Self::write_at(&mut data, offsets::MIN_ALERT_RPM, &(engine.min_alert_rpm as u16).to_le_bytes());
+        Self::write_at(&mut data, offsets::MAX_ALERT_RPM, &(engine.max_rpm as u16).to_le_bytes());
+        Self::write_at(&mut data, offsets::MAX_SPEED, &(car.max_speed.min(i16::MAX as u16) as i16).to_le_bytes());
+        let gears = (engine.gear as u8 & 0x0F) | ((engine.suggested_gear as u8 & 0x0F) << 4);
+        Self::write_at(
+            &mut data,
+            offsets::GEARS,
+            &[gears, Self::encode_pedal(engine.throttle), Self::encode_pedal(engine.brake)],
+        );
+        Self::write_f32_at(&mut data, offsets::CLUTCH, engine.clutch);
+        Self::write_f32_at(&mut data, offsets::CLUTCH_ENGAGEMENT, engine.clutch_engagement);
+        Self::write_f32_at(&mut data, offsets::RPM_AFTER_CLUTCH, engine.rpm_after_clutch);
+
+        // 轮胎信息
+        Self::write_wheels(&mut data, offsets::TYRE_TEMPERATURES, tires.map(|t| t.temperature));
+        Self::write_wheels(&mut data, offsets::WHEEL_SPEEDS, tires.map(|t| t.wheel_speed));
+        Self::write_wheels(&mut data, offsets::TYRE_RADII, tires.map(|t| t.radius));
+        Self::write_wheels(&mut data, offsets::SUSPENSION_HEIGHTS, tires.map(|t| t.suspension_travel));
+
+        // 变速箱信息
+        Self::write_f32_at(&mut data, offsets::TOP_SPEED_RATIO, car.transmission.top_speed_ratio);
+        for (i, ratio) in car.transmission.gear_ratios.iter().enumerate() {
+            Self::write_f32_at(&mut data, offsets::GEAR_RATIOS + 4 * i as u64, *ratio);
+        }
+        Self::write_at(&mut data, offsets::CAR_CODE, &car.car_code.to_le_bytes());
+
+        // 游戏状态和比赛信息
+        Self::write_at(&mut data, offsets::PACKET_ID, &self.packet_id.to_le_bytes());
+        Self::write_at(&mut data, offsets::TIME_OF_DAY, &(self.timestamp as u32).to_le_bytes());
+        Self::write_at(&mut data, offsets::FLAGS, &self.game_state.flags.bits().to_le_bytes());
+        if let Some(race) = &self.game_state.race_info {
+            let lap_time = |ms: Option<u32>| ms.map(|t| t as i32).unwrap_or(-1);
+            Self::write_at(&mut data, offsets::CURRENT_LAP, &(race.current_lap as i16).to_le_bytes());
+            Self::write_at(&mut data, offsets::TOTAL_LAPS, &(race.total_laps as i16).to_le_bytes());
+            Self::write_at(&mut data, offsets::BEST_LAP_TIME, &lap_time(race.best_lap_time).to_le_bytes());
+            Self::write_at(&mut data, offsets::LAST_LAP_TIME, &lap_time(race.last_lap_time).to_le_bytes());
+            Self::write_at(&mut data, offsets::START_POSITION, &(race.position as i16).to_le_bytes());
+            Self::write_at(&mut data, offsets::TOTAL_CARS, &(race.total_participants as i16).to_le_bytes());
+        }
+
+        // 赛道信息
+        Self::write_vector3(&mut data, offsets::ROAD_PLANE, &self.track_info.road_plane);
+        Self::write_f32_at(&mut data, offsets::ROAD_PLANE_DISTANCE, self.track_info.road_plane_distance);
+
+        // 附加数据段
+        if let (Some(motion), true) = (&self.motion, self.variant != PacketVariant::A) {
+            Self::write_f32_at(&mut data, offsets::WHEEL_ROTATION, motion.wheel_rotation);
+            Self::write_f32_at(&mut data, offsets::SWAY, motion.sway);
+            Self::write_f32_at(&mut data, offsets::HEAVE, motion.heave);
+            Self::write_f32_at(&mut data, offsets::SURGE, motion.surge);
         }
+        if let (Some(inputs), PacketVariant::Tilde) = (&self.inputs, self.variant) {
+            Self::write_at(
+                &mut data,
+                offsets::THROTTLE_FILTERED,
+                &[Self::encode_pedal(inputs.throttle_filtered), Self::encode_pedal(inputs.brake_filtered)],
+            );
+            Self::write_f32_at(&mut data, offsets::ENERGY_RECOVERY, inputs.energy_recovery);
+        }
+
+        data
+    }
+
+    /// 编码并加密为网络字节数据 (`from_bytes` 的逆操作)
+    ///
+    /// 使用 `packet_id` 作为IV种子
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_seed(self.packet_id)
+    }
+
+    /// 使用指定IV种子编码并加密为网络字节数据
+    pub fn to_bytes_with_seed(&self, seed: u32) -> Vec<u8> {
+        crate::crypto::encrypt_packet(&self.encode(), self.variant, seed)
+            .expect("编码后的数据包长度总是包含IV种子")
+    }
 
+    /// 将0.0-1.0的踏板值编码为u8
+    fn encode_pedal(value: f32) -> u8 {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    /// 在指定偏移写入字节
+    fn write_at(data: &mut [u8], offset: u64, bytes: &[u8]) {
+        let offset = offset as usize;
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// 在指定偏移写入单个f32
+    fn write_f32_at(data: &mut [u8], offset: u64, value: f32) {
+        Self::write_at(data, offset, &value.to_le_bytes());
+    }
+
+    /// 写入向量为连续的3个f32
+    fn write_vector3(data: &mut [u8], offset: u64, vector: &Vector3) {
+        Self::write_f32_at(data, offset, vector.x);
+        Self::write_f32_at(data, offset + 4, vector.y);
+        Self::write_f32_at(data, offset + 8, vector.z);
+    }
+
+    /// 写入按 FL/FR/RL/RR 排列的4个f32
+    fn write_wheels(data: &mut [u8], offset: u64, values: [f32; 4]) {
+        for (i, value) in values.iter().enumerate() {
+            Self::write_f32_at(data, offset + 4 * i as u64, *value);
+        }
+    }
+
+    /// 验证数据包完整性
+    pub fn validate(&self) -> Result<()> {
         // 验证基本数据范围
         if self.car_info.engine.throttle < 0.0 || self.car_info.engine.throttle > 1.0 {
             return Err(GT7Error::invalid_packet_format("油门值超出范围"));
@@ -340,16 +627,12 @@ impl GT7TelemetryPacket {
             return Err(GT7Error::invalid_packet_format("刹车值超出范围"));
         }
 
-        if self.track_info.track_wetness < 0.0 || self.track_info.track_wetness > 1.0 {
-            return Err(GT7Error::invalid_packet_format("赛道湿度超出范围"));
-        }
-
         Ok(())
     }
 
     /// 计算车辆当前速度 (km/h)
     pub fn get_speed_kmh(&self) -> f32 {
-        self.car_info.position.velocity.magnitude() * 3.6
+        self.car_info.speed * 3.6
     }
 
     /// 检查是否在比赛中
@@ -370,11 +653,140 @@ impl GT7TelemetryPacket {
             _ => "N".to_string(),
         }
     }
-    
+
     /// 获取最快圈速时间
     pub fn get_best_lap_time(&self) -> Option<Duration> {
         self.game_state.race_info.as_ref()
             .and_then(|r| r.best_lap_time)
             .map(|ms| Duration::from_millis(ms as u64))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造一个带魔术字节的明文数据包
+    fn plain_packet() -> Vec<u8> {
+        plain_packet_of(PacketVariant::A)
+    }
+
+    fn plain_packet_of(variant: PacketVariant) -> Vec<u8> {
+        let mut data = vec![0u8; variant.packet_size()];
+        data[0..4].copy_from_slice(&crate::crypto::GT7_PACKET_MAGIC.to_le_bytes());
+        data
+    }
+
+    fn put(data: &mut [u8], offset: u64, bytes: &[u8]) {
+        let offset = offset as usize;
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    #[test]
+    fn test_parse_real_field_map() {
+        let mut data = plain_packet();
+        put(&mut data, offsets::POSITION, &12.5f32.to_le_bytes());
+        put(&mut data, offsets::RIDE_HEIGHT, &0.08f32.to_le_bytes());
+        put(&mut data, offsets::RPM, &6500.0f32.to_le_bytes());
+        put(&mut data, offsets::FUEL_LEVEL, &30.0f32.to_le_bytes());
+        put(&mut data, offsets::FUEL_CAPACITY, &60.0f32.to_le_bytes());
+        put(&mut data, offsets::SPEED, &50.0f32.to_le_bytes());
+        put(&mut data, offsets::BOOST, &1.5f32.to_le_bytes());
+        put(&mut data, offsets::WATER_TEMPERATURE, &85.0f32.to_le_bytes());
+        put(&mut data, offsets::OIL_TEMPERATURE, &110.0f32.to_le_bytes());
+        put(&mut data, offsets::TYRE_TEMPERATURES + 4, &72.0f32.to_le_bytes());
+        put(&mut data, offsets::PACKET_ID, &42i32.to_le_bytes());
+        put(&mut data, offsets::CURRENT_LAP, &3i16.to_le_bytes());
+        put(&mut data, offsets::TOTAL_LAPS, &5i16.to_le_bytes());
+        put(&mut data, offsets::BEST_LAP_TIME, &(-1i32).to_le_bytes());
+        put(&mut data, offsets::LAST_LAP_TIME, &95_123i32.to_le_bytes());
+        put(&mut data, offsets::MAX_ALERT_RPM, &7800u16.to_le_bytes());
+        put(&mut data, offsets::MAX_SPEED, &280i16.to_le_bytes());
+        put(&mut data, offsets::FLAGS, &CarFlags::CAR_ON_TRACK.to_le_bytes());
+        put(&mut data, offsets::GEARS, &[0x43, 255, 0]);
+        put(&mut data, offsets::WHEEL_SPEEDS + 12, &(-150.0f32).to_le_bytes());
+        put(&mut data, offsets::GEAR_RATIOS + 4, &2.1f32.to_le_bytes());
+        put(&mut data, offsets::CAR_CODE, &3245i32.to_le_bytes());
+
+        let packet = GT7TelemetryPacket::from_decrypted_bytes(&data).unwrap();
+        let car = &packet.car_info;
+        assert_eq!(packet.packet_id, 42);
+        assert_eq!(car.position.world.x, 12.5);
+        assert_eq!(car.ride_height, 0.08);
+        assert_eq!(car.engine.rpm, 6500.0);
+        assert_eq!(car.engine.fuel_level, 0.5);
+        assert_eq!(car.engine.boost, 0.5);
+        assert_eq!(car.engine.water_temperature, 85.0);
+        assert_eq!(car.engine.oil_temperature, 110.0);
+        assert_eq!(car.engine.max_rpm, 7800.0);
+        assert_eq!(car.engine.gear, 3);
+        assert_eq!(car.engine.suggested_gear, 4);
+        assert_eq!(car.engine.throttle, 1.0);
+        assert_eq!(car.tires.front_right.temperature, 72.0);
+        assert_eq!(car.tires.rear_right.wheel_speed, -150.0);
+        assert_eq!(car.transmission.gear_ratios[1], 2.1);
+        assert_eq!(car.max_speed, 280);
+        assert_eq!(car.car_code, 3245);
+        assert_eq!(packet.get_speed_kmh(), 180.0);
+
+        let race = packet.game_state.race_info.as_ref().unwrap();
+        assert_eq!(race.current_lap, 3);
+        assert_eq!(race.total_laps, 5);
+        assert_eq!(race.best_lap_time, None);
+        assert_eq!(race.last_lap_time, Some(95_123));
+        assert!(packet.is_in_race());
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let mut data = plain_packet();
+        let bits = CarFlags::CAR_ON_TRACK | CarFlags::PAUSED | CarFlags::HAS_TURBO | CarFlags::TCS_ACTIVE;
+        put(&mut data, offsets::FLAGS, &bits.to_le_bytes());
+
+        let packet = GT7TelemetryPacket::from_decrypted_bytes(&data).unwrap();
+        let flags = packet.game_state.flags;
+        assert!(flags.car_on_track && flags.paused && flags.has_turbo && flags.tcs_active);
+        assert!(!flags.asm_active && !flags.handbrake && !flags.in_gear);
+        assert_eq!(flags.bits(), bits);
+        assert_eq!(packet.game_state.state_type, GameStateType::Paused);
+        assert!(packet.game_state.is_paused);
+        assert!(packet.game_state.race_info.is_some());
+    }
+
+    #[test]
+    fn test_state_from_flags() {
+        let state = |bits: u16| GameStateType::from_flags(&CarFlags::from(bits));
+        assert_eq!(state(0), GameStateType::InMenu);
+        assert_eq!(state(CarFlags::CAR_ON_TRACK | CarFlags::IN_GEAR), GameStateType::InRace);
+        assert_eq!(state(CarFlags::CAR_ON_TRACK | CarFlags::LOADING_OR_PROCESSING), GameStateType::Loading);
+    }
+
+    #[test]
+    fn test_parse_off_track_has_no_race_info() {
+        let data = plain_packet();
+        let packet = GT7TelemetryPacket::from_decrypted_bytes(&data).unwrap();
+        assert!(packet.is_in_menu());
+        assert!(packet.game_state.race_info.is_none());
+    }
+
+    #[test]
+    fn test_parse_variant_b_motion() {
+        let mut data = plain_packet_of(PacketVariant::B);
+        put(&mut data, offsets::WHEEL_ROTATION, &(-0.5f32).to_le_bytes());
+        put(&mut data, offsets::SURGE, &9.8f32.to_le_bytes());
+
+        let packet = GT7TelemetryPacket::from_decrypted_bytes(&data).unwrap();
+        assert_eq!(packet.variant, PacketVariant::B);
+        assert!(packet.inputs.is_none());
+
+        let motion = packet.motion.unwrap();
+        assert_eq!(motion.wheel_rotation, -0.5);
+        assert_eq!(motion.surge, 9.8);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_size() {
+        let data = vec![0u8; 300];
+        assert!(GT7TelemetryPacket::from_decrypted_bytes(&data).unwrap_err().is_packet_error());
+    }
+}
@@ -0,0 +1,394 @@
+//! 遥测会话录制
+//!
+//! 把收到的每个原始 (加密) 数据报连同单调接收时间和来源地址追加写入文件，用于复现实际运行中的问题。
+//!
+//! 文件格式 (`.gt7r`，小端序):
+//!
+//! ```text
+//! 文件头 (16字节):
+//!   magic "GT7R" | 格式版本 u16 | 请求的数据包格式 u8 ('A'/'B'/'~') | 保留 u8 | 会话开始时间 i64 (Unix毫秒)
+//! 记录 (重复):
+//!   数据长度 u16 | 接收时间 u64 (相对会话开始的微秒) | 地址族 u8 (4/6) | IP (4/16字节) | 端口 u16
+//!   | 数据 | CRC32 u32 (覆盖本条记录之前的所有字段)
+//! ```
+//!
+//! 每条记录一次性追加写入，进程崩溃时最多留下一条不完整的记录，读取时会在该处停止
+
+use crate::error::{Result, GT7Error};
+use crate::types::PacketVariant;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 录制文件魔数
+pub const RECORDING_MAGIC: &[u8; 4] = b"GT7R";
+
+/// 录制文件格式版本
+pub const RECORDING_VERSION: u16 = 1;
+
+/// 录制文件扩展名
+pub const RECORDING_EXTENSION: &str = "gt7r";
+
+/// 未配置 `log_file_path` 时的录制目录
+pub const DEFAULT_RECORDING_DIR: &str = "gt7-recordings";
+
+/// 文件头长度
+const HEADER_LEN: usize = 16;
+
+/// 单条记录的典型大小 (最大数据包加记录字段)
+const GT7_RECORD_CAPACITY: usize = crate::GT7_MAX_PACKET_SIZE + 36;
+
+/// 距上次同步超过该时间时把数据刷到磁盘
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 录制文件头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordingHeader {
+    /// 格式版本
+    pub version: u16,
+    /// 录制时请求的数据包格式
+    pub variant: PacketVariant,
+    /// 会话开始时间 (Unix毫秒)
+    pub started_at_ms: i64,
+}
+
+/// 录制的一个数据报
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedDatagram {
+    /// 相对会话开始的接收时间
+    pub timestamp: Duration,
+    /// 来源地址
+    pub source: SocketAddr,
+    /// 原始 (加密) 数据
+    pub data: Vec<u8>,
+}
+
+/// 会话录制器
+///
+/// 每次创建写入一个新文件，可在多个接收任务间共享
+#[derive(Debug)]
+pub struct SessionRecorder {
+    path: PathBuf,
+    started: Instant,
+    state: Mutex<WriterState>,
+}
+
+#[derive(Debug)]
+struct WriterState {
+    file: File,
+    last_sync: Instant,
+    records: u64,
+}
+
+impl SessionRecorder {
+    /// 在目录中创建新的会话文件 (`session-YYYYMMDD-HHMMSS.gt7r`，重名时追加序号)
+    pub fn create_in(dir: impl AsRef<Path>, variant: PacketVariant) -> Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)
+            .map_err(|e| GT7Error::file_error(format!("创建录制目录 {}: {}", dir.display(), e)))?;
+
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        for attempt in 0u32.. {
+            let name = match attempt {
+                0 => format!("session-{}.{}", stamp, RECORDING_EXTENSION),
+                n => format!("session-{}-{}.{}", stamp, n, RECORDING_EXTENSION),
+            };
+            let path = dir.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Self::with_file(path, file, variant),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(GT7Error::file_error(format!("创建录制文件 {}: {}", path.display(), e)))
+                }
+            }
+        }
+        unreachable!("会话文件序号用尽")
+    }
+
+    fn with_file(path: PathBuf, mut file: File, variant: PacketVariant) -> Result<Self> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(RECORDING_MAGIC);
+        header.write_u16::<LittleEndian>(RECORDING_VERSION).unwrap();
+        header.push(variant.heartbeat()[0]);
+        header.push(0);
+        header.write_i64::<LittleEndian>(chrono::Utc::now().timestamp_millis()).unwrap();
+        file.write_all(&header)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| GT7Error::file_error(format!("写入录制文件头 {}: {}", path.display(), e)))?;
+
+        log::info!("开始录制遥测会话: {}", path.display());
+        Ok(Self {
+            path,
+            started: Instant::now(),
+            state: Mutex::new(WriterState {
+                file,
+                last_sync: Instant::now(),
+                records: 0,
+            }),
+        })
+    }
+
+    /// 录制文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 已录制的数据报数
+    pub fn records(&self) -> u64 {
+        self.state.lock().unwrap().records
+    }
+
+    /// 追加一个数据报
+    pub fn record(&self, source: SocketAddr, data: &[u8]) -> Result<()> {
+        let timestamp = self.started.elapsed();
+        let record = encode_record(timestamp, source, data)?;
+
+        let mut state = self.state.lock().unwrap();
+        state.file.write_all(&record)
+            .map_err(|e| GT7Error::file_error(format!("写入录制文件 {}: {}", self.path.display(), e)))?;
+        state.records += 1;
+        if state.last_sync.elapsed() >= SYNC_INTERVAL {
+            // 同步失败不影响后续写入
+            if let Err(e) = state.file.sync_data() {
+                log::warn!("同步录制文件 {} 失败: {}", self.path.display(), e);
+            }
+            state.last_sync = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// 结束录制，把数据刷到磁盘
+    pub fn finish(&self) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        state.file.sync_all()
+            .map_err(|e| GT7Error::file_error(format!("同步录制文件 {}: {}", self.path.display(), e)))?;
+        log::info!("结束录制: {} ({} 个数据报)", self.path.display(), state.records);
+        Ok(())
+    }
+}
+
+fn encode_record(timestamp: Duration, source: SocketAddr, data: &[u8]) -> Result<Vec<u8>> {
+    let length = u16::try_from(data.len())
+        .map_err(|_| GT7Error::invalid_packet_format("record_length"))?;
+
+    let mut record = Vec::with_capacity(data.len() + 36);
+    record.write_u16::<LittleEndian>(length).unwrap();
+    record.write_u64::<LittleEndian>(timestamp.as_micros() as u64).unwrap();
+    match source.ip() {
+        IpAddr::V4(ip) => {
+            record.push(4);
+            record.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            record.push(6);
+            record.extend_from_slice(&ip.octets());
+        }
+    }
+    record.write_u16::<LittleEndian>(source.port()).unwrap();
+    record.extend_from_slice(data);
+    let checksum = crc32fast::hash(&record);
+    record.write_u32::<LittleEndian>(checksum).unwrap();
+    Ok(record)
+}
+
+/// 录制文件读取器
+///
+/// 迭代返回录制的数据报；遇到不完整或校验失败的记录时停止，并通过 [`is_truncated`](Self::is_truncated) 报告
+pub struct RecordingReader<R = BufReader<File>> {
+    reader: R,
+    header: RecordingHeader,
+    truncated: bool,
+}
+
+impl RecordingReader {
+    /// 打开录制文件
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| GT7Error::file_error(format!("打开录制文件 {}: {}", path.display(), e)))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> RecordingReader<R> {
+    /// 从任意数据源读取
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; HEADER_LEN];
+        reader.read_exact(&mut header)
+            .map_err(|_| GT7Error::incomplete_data(HEADER_LEN, 0))?;
+        if &header[0..4] != RECORDING_MAGIC {
+            return Err(GT7Error::invalid_packet_format("recording_magic"));
+        }
+
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != RECORDING_VERSION {
+            return Err(GT7Error::packet_version_mismatch(RECORDING_VERSION, version));
+        }
+        let variant = PacketVariant::ALL
+            .into_iter()
+            .find(|variant| variant.heartbeat()[0] == header[6])
+            .ok_or_else(|| GT7Error::invalid_packet_format("recording_variant"))?;
+        let started_at_ms = i64::from_le_bytes(header[8..16].try_into().unwrap());
+
+        Ok(Self {
+            reader,
+            header: RecordingHeader { version, variant, started_at_ms },
+            truncated: false,
+        })
+    }
+
+    /// 文件头
+    pub fn header(&self) -> RecordingHeader {
+        self.header
+    }
+
+    /// 是否因记录不完整或损坏而提前结束
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// 读取下一条记录；文件正常结束时返回 `Ok(None)`
+    fn read_record(&mut self) -> std::io::Result<Option<RecordedDatagram>> {
+        let mut record = Vec::with_capacity(GT7_RECORD_CAPACITY);
+        let mut fixed = [0u8; 11];
+        match self.reader.read_exact(&mut fixed[..1]) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            other => other?,
+        }
+        self.reader.read_exact(&mut fixed[1..])?;
+        record.extend_from_slice(&fixed);
+
+        let mut cursor = &fixed[..];
+        let length = cursor.read_u16::<LittleEndian>()? as usize;
+        let timestamp = Duration::from_micros(cursor.read_u64::<LittleEndian>()?);
+        let ip = match cursor.read_u8()? {
+            4 => {
+                let mut octets = [0u8; 4];
+                self.reader.read_exact(&mut octets)?;
+                record.extend_from_slice(&octets);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            6 => {
+                let mut octets = [0u8; 16];
+                self.reader.read_exact(&mut octets)?;
+                record.extend_from_slice(&octets);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return Err(ErrorKind::InvalidData.into()),
+        };
+
+        let mut rest = vec![0u8; 2 + length + 4];
+        self.reader.read_exact(&mut rest)?;
+        record.extend_from_slice(&rest[..2 + length]);
+        let port = u16::from_le_bytes([rest[0], rest[1]]);
+        let checksum = u32::from_le_bytes(rest[2 + length..].try_into().unwrap());
+        if crc32fast::hash(&record) != checksum {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        Ok(Some(RecordedDatagram {
+            timestamp,
+            source: SocketAddr::new(ip, port),
+            data: rest[2..2 + length].to_vec(),
+        }))
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = RecordedDatagram;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.truncated {
+            return None;
+        }
+        match self.read_record() {
+            Ok(record) => record,
+            Err(e) => {
+                log::warn!("录制文件在不完整或损坏的记录处结束: {}", e);
+                self.truncated = true;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+    use std::io::Cursor;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gt7-recorder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_record_and_read_back() {
+        let dir = temp_dir("roundtrip");
+        let recorder = SessionRecorder::create_in(&dir, PacketVariant::B).unwrap();
+        let packet = PacketBuilder::new().variant(PacketVariant::B).packet_id(9).build_bytes();
+        let v4: SocketAddr = "192.168.1.30:33740".parse().unwrap();
+        let v6: SocketAddr = "[fe80::1]:33740".parse().unwrap();
+        recorder.record(v4, &packet).unwrap();
+        recorder.record(v6, b"junk").unwrap();
+        recorder.finish().unwrap();
+        assert_eq!(recorder.records(), 2);
+
+        let mut reader = RecordingReader::open(recorder.path()).unwrap();
+        assert_eq!(reader.header().variant, PacketVariant::B);
+        assert_eq!(reader.header().version, RECORDING_VERSION);
+        let records: Vec<_> = reader.by_ref().collect();
+        assert!(!reader.is_truncated());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].source, v4);
+        assert_eq!(records[0].data, packet);
+        assert_eq!(records[1].source, v6);
+        assert!(records[0].timestamp <= records[1].timestamp);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_sessions_rotate() {
+        let dir = temp_dir("rotate");
+        let first = SessionRecorder::create_in(&dir, PacketVariant::A).unwrap();
+        let second = SessionRecorder::create_in(&dir, PacketVariant::A).unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_torn_and_corrupt_tail() {
+        let dir = temp_dir("torn");
+        let recorder = SessionRecorder::create_in(&dir, PacketVariant::A).unwrap();
+        let source: SocketAddr = "10.0.0.2:33740".parse().unwrap();
+        for id in 0..3 {
+            recorder.record(source, &PacketBuilder::new().packet_id(id).build_bytes()).unwrap();
+        }
+        let bytes = fs::read(recorder.path()).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        // 模拟崩溃时写了一半的最后一条记录
+        let torn = &bytes[..bytes.len() - 10];
+        let mut reader = RecordingReader::new(Cursor::new(torn)).unwrap();
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.is_truncated());
+
+        // 数据损坏的记录不会被当作有效数据
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 20;
+        corrupt[last] ^= 0xFF;
+        let mut reader = RecordingReader::new(Cursor::new(corrupt)).unwrap();
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.is_truncated());
+
+        assert!(RecordingReader::new(Cursor::new(b"NOPE".to_vec())).is_err());
+    }
+}
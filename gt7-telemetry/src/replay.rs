@@ -0,0 +1,259 @@
+//! 录制会话回放
+//!
+//! 读取 [`recorder`](crate::recorder) 录制的会话，通过与 [`GT7TelemetryClient::new`](crate::GT7TelemetryClient::new)
+//! 相同的广播接收器输出数据包，支持实时、N倍速、单步以及按时间或圈数跳转，
+//! 无需主机即可确定性地测试自动驾驶和分析代码
+
+use crate::error::{Result, GT7Error};
+use crate::packet::GT7TelemetryPacket;
+use crate::recorder::{RecordingHeader, RecordingReader};
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+
+/// 回放的一帧
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayFrame {
+    /// 相对会话开始的接收时间
+    pub timestamp: Duration,
+    /// 来源IP (与客户端广播中的IP一致)
+    pub ip: String,
+    /// 解析后的数据包
+    pub packet: GT7TelemetryPacket,
+}
+
+/// 录制会话回放源
+#[derive(Debug)]
+pub struct ReplaySource {
+    header: RecordingHeader,
+    frames: Vec<ReplayFrame>,
+    /// 下一帧的位置
+    position: usize,
+    /// 无法解析而跳过的数据报数
+    skipped: usize,
+    packet_sender: broadcast::Sender<(String, GT7TelemetryPacket)>,
+}
+
+impl ReplaySource {
+    /// 打开录制文件
+    ///
+    /// # 返回
+    ///
+    /// 回放源和数据包接收器 (与 `GT7TelemetryClient::new` 返回的接收器类型相同)
+    pub fn open(path: impl AsRef<Path>) -> Result<(Self, broadcast::Receiver<(String, GT7TelemetryPacket)>)> {
+        Self::from_reader(RecordingReader::open(path)?)
+    }
+
+    /// 从录制读取器创建
+    pub fn from_reader<R: Read>(
+        mut reader: RecordingReader<R>,
+    ) -> Result<(Self, broadcast::Receiver<(String, GT7TelemetryPacket)>)> {
+        let mut frames = Vec::new();
+        let mut skipped = 0;
+        for datagram in reader.by_ref() {
+            match GT7TelemetryPacket::from_bytes(&datagram.data) {
+                Ok(packet) => frames.push(ReplayFrame {
+                    timestamp: datagram.timestamp,
+                    ip: datagram.source.ip().to_canonical().to_string(),
+                    packet,
+                }),
+                Err(_) => skipped += 1,
+            }
+        }
+        if reader.is_truncated() {
+            log::warn!("录制文件不完整，回放到最后一条完整记录");
+        }
+        log::info!("加载录制会话: {} 帧，跳过 {} 个无法解析的数据报", frames.len(), skipped);
+
+        let (packet_sender, packet_receiver) = broadcast::channel(1000);
+        let source = Self {
+            header: reader.header(),
+            frames,
+            position: 0,
+            skipped,
+            packet_sender,
+        };
+        Ok((source, packet_receiver))
+    }
+
+    /// 再订阅一个数据包接收器
+    pub fn subscribe(&self) -> broadcast::Receiver<(String, GT7TelemetryPacket)> {
+        self.packet_sender.subscribe()
+    }
+
+    /// 录制文件头
+    pub fn header(&self) -> RecordingHeader {
+        self.header
+    }
+
+    /// 所有帧
+    pub fn frames(&self) -> &[ReplayFrame] {
+        &self.frames
+    }
+
+    /// 帧数
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// 是否没有可回放的帧
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// 无法解析而跳过的数据报数
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// 下一帧的位置
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// 是否已回放到结尾
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.frames.len()
+    }
+
+    /// 会话时长 (最后一帧的时间)
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |frame| frame.timestamp)
+    }
+
+    /// 单步: 输出下一帧并返回它，已到结尾时返回 `None`
+    pub fn step(&mut self) -> Option<&ReplayFrame> {
+        let frame = self.frames.get(self.position)?;
+        // 没有接收器时丢弃即可
+        let _ = self.packet_sender.send((frame.ip.clone(), frame.packet.clone()));
+        self.position += 1;
+        Some(frame)
+    }
+
+    /// 跳转到指定时间之后的第一帧
+    pub fn seek_to_timestamp(&mut self, timestamp: Duration) {
+        self.position = self.frames.partition_point(|frame| frame.timestamp < timestamp);
+    }
+
+    /// 跳转到指定圈的第一帧
+    pub fn seek_to_lap(&mut self, lap: u16) -> Result<()> {
+        let position = self.frames
+            .iter()
+            .position(|frame| frame.packet.game_state.race_info.as_ref().is_some_and(|race| race.current_lap == lap))
+            .ok_or_else(|| GT7Error::config_error("lap", lap.to_string(), "录制中没有该圈的数据"))?;
+        self.position = position;
+        Ok(())
+    }
+
+    /// 按录制时的节奏从当前位置回放到结尾
+    ///
+    /// `speed` 为回放倍速 (1.0为实时，10.0为10倍速)。丢弃返回的future即可暂停，位置保留在已输出的帧之后
+    pub async fn play(&mut self, speed: f64) -> Result<()> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(GT7Error::config_error("speed", speed.to_string(), "回放倍速必须是正数"));
+        }
+
+        let Some(first) = self.frames.get(self.position) else {
+            return Ok(());
+        };
+        let origin = first.timestamp;
+        let started = Instant::now();
+
+        while let Some(frame) = self.frames.get(self.position) {
+            let offset = (frame.timestamp - origin).div_f64(speed);
+            tokio::time::sleep_until(started + offset).await;
+            self.step();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PacketBuilder;
+    use crate::recorder::SessionRecorder;
+    use crate::types::PacketVariant;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+
+    /// 录制3圈，每圈10帧，帧间隔10ms
+    fn record_session(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("gt7-replay-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let recorder = SessionRecorder::create_in(&dir, PacketVariant::A).unwrap();
+        let source: SocketAddr = "192.168.1.30:33740".parse().unwrap();
+        for packet_id in 0..30u32 {
+            let bytes = PacketBuilder::new()
+                .packet_id(packet_id)
+                .lap(packet_id as u16 / 10 + 1, 3)
+                .build_bytes();
+            recorder.record(source, &bytes).unwrap();
+            if packet_id == 14 {
+                recorder.record(source, b"noise").unwrap();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        recorder.finish().unwrap();
+        (dir, recorder.path().to_path_buf())
+    }
+
+    #[test]
+    fn test_step_and_seek() {
+        let (dir, path) = record_session("step");
+        let (mut replay, mut receiver) = ReplaySource::open(&path).unwrap();
+        assert_eq!(replay.len(), 30);
+        assert_eq!(replay.skipped(), 1);
+
+        assert_eq!(replay.step().unwrap().packet.packet_id, 0);
+        assert_eq!(replay.step().unwrap().packet.packet_id, 1);
+        let (ip, packet) = receiver.try_recv().unwrap();
+        assert_eq!(ip, "192.168.1.30");
+        assert_eq!(packet.packet_id, 0);
+        assert_eq!(receiver.try_recv().unwrap().1.packet_id, 1);
+
+        replay.seek_to_lap(3).unwrap();
+        assert_eq!(replay.step().unwrap().packet.packet_id, 20);
+        assert!(replay.seek_to_lap(9).is_err());
+
+        let timestamp = replay.frames()[12].timestamp;
+        replay.seek_to_timestamp(timestamp);
+        assert_eq!(replay.step().unwrap().packet.packet_id, 12);
+        replay.seek_to_timestamp(replay.duration() + Duration::from_secs(1));
+        assert!(replay.is_finished());
+        assert!(replay.step().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_play_speeds() {
+        let (dir, path) = record_session("play");
+        let (mut replay, mut receiver) = ReplaySource::open(&path).unwrap();
+        let duration = replay.duration() - replay.frames()[0].timestamp;
+
+        // 实时回放从第2圈开始
+        replay.seek_to_lap(2).unwrap();
+        let started = std::time::Instant::now();
+        replay.play(1.0).await.unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= duration.mul_f64(0.6), "{:?} < {:?}", elapsed, duration);
+        let mut delivered = Vec::new();
+        while let Ok((_, packet)) = receiver.try_recv() {
+            delivered.push(packet.packet_id);
+        }
+        assert_eq!(delivered, (10..30).collect::<Vec<_>>());
+
+        // 10倍速
+        replay.seek_to_timestamp(Duration::ZERO);
+        let started = std::time::Instant::now();
+        replay.play(10.0).await.unwrap();
+        assert!(started.elapsed() < duration.mul_f64(0.5));
+        assert!(replay.is_finished());
+
+        assert!(replay.play(0.0).await.is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
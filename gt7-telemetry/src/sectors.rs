@@ -0,0 +1,519 @@
+//! 赛段计时与实时圈速差
+//!
+//! [`SectorTimer`] 在 [`LapTracker`] 之上按赛段切分每一圈。数据包提供 `TrackInfo::current_sector`
+//! 时直接使用游戏的赛段；否则按圈内行驶距离 (车速积分) 把学习到的赛道长度等分。
+//! 同时维护个人最快圈和理论最快圈 (各赛段最好成绩之和)，并在每个数据包给出相对最快圈的实时差距。
+//!
+//! GT7数据包不包含赛道ID，换赛道时需要调用 [`SectorTimer::reset`] 或为每条赛道使用单独的计时器
+
+use crate::laps::{Lap, LapKind, LapSample, LapTracker};
+use crate::packet::GT7TelemetryPacket;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 赛段计时配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectorConfig {
+    /// 没有游戏赛段时按距离划分的赛段数
+    pub sector_count: usize,
+    /// 已知的赛道长度 (米)，为 `None` 时从有效圈学习
+    pub track_length: Option<f32>,
+}
+
+impl Default for SectorConfig {
+    fn default() -> Self {
+        Self {
+            sector_count: 3,
+            track_length: None,
+        }
+    }
+}
+
+/// 实时圈速差
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiveDelta {
+    /// 当前圈数
+    pub lap: u16,
+    /// 当前圈已经过的时间
+    pub elapsed: Duration,
+    /// 当前圈已行驶的距离 (米)
+    pub distance: f32,
+    /// 当前赛段 (从0开始)
+    pub sector: usize,
+    /// 与最快圈在同一位置的时间差 (秒，正数表示更慢)
+    pub delta_to_best: Option<f32>,
+    /// 按当前差距预测的圈速
+    pub predicted_lap_time: Option<Duration>,
+}
+
+/// 计时事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimingEvent {
+    /// 完成一个赛段
+    Sector {
+        /// 圈数
+        lap: u16,
+        /// 赛段 (从0开始)
+        sector: usize,
+        /// 赛段用时
+        time: Duration,
+        /// 是否快于此前该赛段的最好成绩
+        personal_best: bool,
+    },
+    /// 完成一圈 (包括出站圈、进站圈和无效圈)
+    Lap {
+        /// 圈记录
+        lap: Lap,
+        /// 各赛段用时 (无法划分赛段时为空)
+        sectors: Vec<Duration>,
+        /// 是否刷新了个人最快圈
+        personal_best: bool,
+    },
+}
+
+/// 最快圈的距离-时间曲线
+#[derive(Debug, Clone)]
+struct Reference {
+    time: Duration,
+    /// (距离, 时间)，距离单调不减
+    profile: Vec<(f32, Duration)>,
+}
+
+impl Reference {
+    /// 到达指定距离的时间 (线性插值)
+    fn time_at(&self, distance: f32) -> Option<Duration> {
+        time_at(&self.profile, distance)
+    }
+}
+
+/// 赛段计时器
+#[derive(Debug)]
+pub struct SectorTimer {
+    config: SectorConfig,
+    tracker: LapTracker,
+    /// 是否使用游戏提供的赛段
+    official_sectors: bool,
+    /// 游戏赛段数 (见过的最大赛段+1)
+    official_sector_count: usize,
+    track_length: Option<f32>,
+    /// 用于学习赛道长度的有效圈数
+    learned_laps: u32,
+    /// 当前圈的累计距离，与 `tracker.current_samples()` 一一对应
+    distances: Vec<f32>,
+    sector: usize,
+    sector_started: Duration,
+    best_lap: Option<Reference>,
+    best_sectors: Vec<Option<Duration>>,
+    delta_sender: watch::Sender<Option<LiveDelta>>,
+}
+
+impl SectorTimer {
+    /// 创建计时器
+    pub fn new(config: SectorConfig) -> Self {
+        let (delta_sender, _) = watch::channel(None);
+        Self {
+            track_length: config.track_length,
+            config,
+            tracker: LapTracker::new(),
+            official_sectors: false,
+            official_sector_count: 0,
+            learned_laps: 0,
+            distances: Vec::new(),
+            sector: 0,
+            sector_started: Duration::ZERO,
+            best_lap: None,
+            best_sectors: Vec::new(),
+            delta_sender,
+        }
+    }
+
+    /// 订阅实时圈速差 (每个数据包更新)
+    pub fn subscribe(&self) -> watch::Receiver<Option<LiveDelta>> {
+        self.delta_sender.subscribe()
+    }
+
+    /// 最近一次的实时圈速差
+    pub fn live_delta(&self) -> Option<LiveDelta> {
+        *self.delta_sender.borrow()
+    }
+
+    /// 赛道长度 (米)
+    pub fn track_length(&self) -> Option<f32> {
+        self.track_length
+    }
+
+    /// 赛段数
+    pub fn sector_count(&self) -> usize {
+        if self.official_sectors {
+            self.official_sector_count
+        } else {
+            self.config.sector_count.max(1)
+        }
+    }
+
+    /// 个人最快圈速
+    pub fn personal_best(&self) -> Option<Duration> {
+        self.best_lap.as_ref().map(|best| best.time)
+    }
+
+    /// 各赛段最好成绩
+    pub fn best_sectors(&self) -> &[Option<Duration>] {
+        &self.best_sectors
+    }
+
+    /// 理论最快圈速 (各赛段最好成绩之和)
+    pub fn theoretical_best(&self) -> Option<Duration> {
+        if self.best_sectors.is_empty() {
+            return None;
+        }
+        self.best_sectors.iter().copied().sum()
+    }
+
+    /// 清除学习到的赛道和最好成绩 (换赛道时使用)
+    pub fn reset(&mut self) {
+        let delta_sender = self.delta_sender.clone();
+        *self = Self::new(self.config.clone());
+        self.delta_sender = delta_sender;
+        self.delta_sender.send_replace(None);
+    }
+
+    /// 处理一个数据包，返回产生的计时事件
+    pub fn push(&mut self, packet: &GT7TelemetryPacket) -> Vec<TimingEvent> {
+        let sector = usize::from(packet.track_info.current_sector);
+        if sector > 0 {
+            self.official_sectors = true;
+            self.official_sector_count = self.official_sector_count.max(sector + 1);
+        }
+
+        let mut events = Vec::new();
+        if let Some(lap) = self.tracker.push(packet) {
+            self.complete_lap(lap, &mut events);
+        }
+        self.update_current(&mut events);
+        events
+    }
+
+    /// 数据流结束，返回进行中的圈
+    pub fn finish(&mut self) -> Vec<TimingEvent> {
+        let mut events = Vec::new();
+        if let Some(lap) = self.tracker.finish() {
+            self.complete_lap(lap, &mut events);
+        }
+        self.distances.clear();
+        self.delta_sender.send_replace(None);
+        events
+    }
+
+    fn complete_lap(&mut self, lap: Lap, events: &mut Vec<TimingEvent>) {
+        let profile = distance_profile(&lap.samples);
+        let lap_distance = profile.last().map_or(0.0, |&(distance, _)| distance);
+
+        if lap.is_valid() && self.config.track_length.is_none() {
+            // 用有效圈的平均距离作为赛道长度
+            self.learned_laps += 1;
+            let previous = self.track_length.unwrap_or(lap_distance);
+            self.track_length = Some(previous + (lap_distance - previous) / self.learned_laps as f32);
+        }
+
+        let sectors = if lap.kind == LapKind::Flying {
+            self.lap_sectors(&lap, &profile).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        // 最后一个赛段在通过计时线时完成
+        if let Some(&time) = sectors.last() {
+            let sector = sectors.len() - 1;
+            events.push(TimingEvent::Sector {
+                lap: lap.number,
+                sector,
+                time,
+                personal_best: self.is_best_sector(sector, time),
+            });
+        }
+
+        let mut personal_best = false;
+        if lap.is_valid() {
+            if self.best_sectors.len() < sectors.len() {
+                self.best_sectors.resize(sectors.len(), None);
+            }
+            for (best, &time) in self.best_sectors.iter_mut().zip(&sectors) {
+                if best.is_none_or(|best| time < best) {
+                    *best = Some(time);
+                }
+            }
+            if self.best_lap.as_ref().is_none_or(|best| lap.time() < best.time) {
+                personal_best = true;
+                log::info!("第 {} 圈刷新个人最快圈: {:?}", lap.number, lap.time());
+                self.best_lap = Some(Reference { time: lap.time(), profile });
+            }
+        }
+
+        events.push(TimingEvent::Lap { lap, sectors, personal_best });
+    }
+
+    /// 按整圈数据划分赛段，最后一个赛段补齐到圈速
+    fn lap_sectors(&self, lap: &Lap, profile: &[(f32, Duration)]) -> Option<Vec<Duration>> {
+        let mut crossings = Vec::new();
+        if self.official_sectors {
+            for pair in lap.samples.windows(2) {
+                if pair[1].packet.track_info.current_sector > pair[0].packet.track_info.current_sector {
+                    crossings.push(pair[1].elapsed);
+                }
+            }
+        } else {
+            let length = self.track_length?;
+            let lap_distance = profile.last()?.0;
+            for boundary in self.boundaries(length) {
+                if lap_distance < boundary {
+                    return None;
+                }
+                crossings.push(time_at(profile, boundary)?);
+            }
+        }
+
+        let mut sectors = Vec::with_capacity(crossings.len() + 1);
+        let mut started = Duration::ZERO;
+        for crossing in crossings {
+            sectors.push(crossing.saturating_sub(started));
+            started = crossing;
+        }
+        sectors.push(lap.time().saturating_sub(started));
+        Some(sectors)
+    }
+
+    /// 更新当前圈的距离、赛段和实时差距
+    fn update_current(&mut self, events: &mut Vec<TimingEvent>) {
+        let samples = self.tracker.current_samples();
+        let (Some(lap), Some(kind), Some(current)) =
+            (self.tracker.current_lap(), self.tracker.current_kind(), samples.last())
+        else {
+            self.distances.clear();
+            self.delta_sender.send_if_modified(|delta| delta.take().is_some());
+            return;
+        };
+
+        if samples.len() == 1 {
+            // 新的一圈
+            self.distances.clear();
+            self.sector = 0;
+            self.sector_started = Duration::ZERO;
+        }
+        if self.distances.len() + 1 != samples.len() {
+            // 倒带截断了采样，重新计算距离
+            self.distances = distance_profile(&samples[..samples.len() - 1])
+                .into_iter()
+                .map(|(distance, _)| distance)
+                .collect();
+        }
+        let previous = self.distances.last().copied();
+        let distance = match (previous, samples.len().checked_sub(2).map(|i| &samples[i])) {
+            (Some(previous_distance), Some(previous_sample)) => previous_distance + step_distance(previous_sample, current),
+            _ => 0.0,
+        };
+        self.distances.push(distance);
+
+        // 赛段
+        let sector = if self.official_sectors {
+            usize::from(current.packet.track_info.current_sector)
+        } else {
+            self.track_length.map_or(0, |length| {
+                self.boundaries(length).iter().filter(|&&boundary| distance >= boundary).count()
+            })
+        };
+        if kind == LapKind::Flying && sector > self.sector && sector < self.sector_count() {
+            for crossed in self.sector..sector {
+                let crossing = match (self.official_sectors, previous, self.track_length) {
+                    (false, Some(previous_distance), Some(length)) => {
+                        let boundary = self.boundaries(length)[crossed];
+                        interpolate_time(
+                            (previous_distance, samples[samples.len() - 2].elapsed),
+                            (distance, current.elapsed),
+                            boundary,
+                        )
+                    }
+                    _ => current.elapsed,
+                };
+                let time = crossing.saturating_sub(self.sector_started);
+                events.push(TimingEvent::Sector {
+                    lap,
+                    sector: crossed,
+                    time,
+                    personal_best: self.is_best_sector(crossed, time),
+                });
+                self.sector_started = crossing;
+            }
+        }
+        self.sector = sector;
+
+        // 实时差距只对计时圈有意义
+        let delta_to_best = match (&self.best_lap, kind) {
+            (Some(best), LapKind::Flying) => best
+                .time_at(distance)
+                .map(|reference| current.elapsed.as_secs_f32() - reference.as_secs_f32()),
+            _ => None,
+        };
+        let predicted_lap_time = match (delta_to_best, &self.best_lap) {
+            (Some(delta), Some(best)) => Some(Duration::from_secs_f32((best.time.as_secs_f32() + delta).max(0.0))),
+            _ => None,
+        };
+        self.delta_sender.send_replace(Some(LiveDelta {
+            lap,
+            elapsed: current.elapsed,
+            distance,
+            sector,
+            delta_to_best,
+            predicted_lap_time,
+        }));
+    }
+
+    /// 按距离划分时各赛段的起点 (不含0)
+    fn boundaries(&self, length: f32) -> Vec<f32> {
+        let count = self.config.sector_count.max(1);
+        (1..count).map(|i| length * i as f32 / count as f32).collect()
+    }
+
+    fn is_best_sector(&self, sector: usize, time: Duration) -> bool {
+        self.best_sectors.get(sector).copied().flatten().is_some_and(|best| time < best)
+    }
+}
+
+/// 两个采样间行驶的距离 (平均车速×时间)
+fn step_distance(previous: &LapSample, current: &LapSample) -> f32 {
+    let dt = current.elapsed.saturating_sub(previous.elapsed).as_secs_f32();
+    (previous.packet.car_info.speed + current.packet.car_info.speed) / 2.0 * dt
+}
+
+/// 圈内各采样的累计距离
+pub(crate) fn distance_profile(samples: &[LapSample]) -> Vec<(f32, Duration)> {
+    let mut distance = 0.0;
+    let mut profile = Vec::with_capacity(samples.len());
+    for (index, sample) in samples.iter().enumerate() {
+        if index > 0 {
+            distance += step_distance(&samples[index - 1], sample);
+        }
+        profile.push((distance, sample.elapsed));
+    }
+    profile
+}
+
+pub(crate) fn time_at(profile: &[(f32, Duration)], distance: f32) -> Option<Duration> {
+    let index = profile.partition_point(|&(d, _)| d < distance);
+    match (index.checked_sub(1).map(|i| profile[i]), profile.get(index)) {
+        (Some(before), Some(&after)) => Some(interpolate_time(before, after, distance)),
+        (None, Some(&(_, time))) => Some(time),
+        (Some((_, time)), None) => Some(time),
+        (None, None) => None,
+    }
+}
+
+fn interpolate_time(before: (f32, Duration), after: (f32, Duration), distance: f32) -> Duration {
+    let span = after.0 - before.0;
+    if span <= f32::EPSILON {
+        return after.1;
+    }
+    let ratio = ((distance - before.0) / span).clamp(0.0, 1.0);
+    before.1 + (after.1.saturating_sub(before.1)).mul_f32(ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::LapDriver;
+
+    /// 以恒定车速跑 `frames` 帧
+    fn drive(driver: &mut LapDriver, timer: &mut SectorTimer, lap: u16, frames: u32, speed_ms: f32) -> Vec<TimingEvent> {
+        driver
+            .lap(lap, 10, frames, |builder, _| builder.speed_kmh(speed_ms * 3.6))
+            .iter()
+            .flat_map(|packet| timer.push(packet))
+            .collect()
+    }
+
+    fn laps(events: &[TimingEvent]) -> Vec<(u16, Vec<Duration>, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                TimingEvent::Lap { lap, sectors, personal_best } => Some((lap.number, sectors.clone(), *personal_best)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_learns_track_and_splits_sectors() {
+        let mut timer = SectorTimer::new(SectorConfig::default());
+        let mut driver = LapDriver::default();
+        drive(&mut driver, &mut timer, 1, 60, 50.0);
+        // 第2圈: 6秒，50m/s，赛道约300米
+        let events = drive(&mut driver, &mut timer, 2, 360, 50.0);
+        assert!(laps(&events).iter().all(|(lap, _, _)| *lap == 1));
+        let events = drive(&mut driver, &mut timer, 3, 1, 50.0);
+        let lap2 = &laps(&events)[0];
+        assert_eq!(lap2.0, 2);
+        assert!(lap2.2);
+        assert_eq!(lap2.1.len(), 3);
+        for sector in &lap2.1 {
+            assert!((sector.as_secs_f32() - 2.0).abs() < 0.05, "{:?}", lap2.1);
+        }
+        let length = timer.track_length().unwrap();
+        assert!((length - 300.0).abs() < 1.0, "{}", length);
+        assert_eq!(timer.personal_best(), Some(Duration::from_secs(6)));
+
+        // 第3圈: 前半段更快
+        let mut events = drive(&mut driver, &mut timer, 3, 150, 60.0);
+        let delta = timer.live_delta().unwrap();
+        assert!(delta.delta_to_best.unwrap() < -0.3, "{:?}", delta);
+        assert!(delta.predicted_lap_time.unwrap() < Duration::from_secs(6));
+        events.extend(drive(&mut driver, &mut timer, 3, 310, 30.0));
+        events.extend(drive(&mut driver, &mut timer, 4, 1, 30.0));
+
+        let sectors: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                TimingEvent::Sector { lap: 3, sector, personal_best, .. } => Some((*sector, *personal_best)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sectors.len(), 3);
+        assert_eq!(sectors[0], (0, true));
+        let lap3 = &laps(&events)[0];
+        assert!(!lap3.2);
+        // 理论最快圈使用第3圈的第1赛段
+        assert!(timer.theoretical_best().unwrap() < timer.personal_best().unwrap());
+    }
+
+    #[test]
+    fn test_official_sectors() {
+        let mut timer = SectorTimer::new(SectorConfig::default());
+        let mut driver = LapDriver::default();
+        let mut events = Vec::new();
+        for (lap, frames) in [(1u16, 10u32), (2, 120), (3, 1)] {
+            for (frame, mut packet) in driver.lap(lap, 5, frames, |builder, _| builder.speed_kmh(100.0)).into_iter().enumerate() {
+                packet.track_info.current_sector = if frame < 30 { 0 } else if frame < 90 { 1 } else { 2 };
+                events.extend(timer.push(&packet));
+            }
+        }
+        assert_eq!(timer.sector_count(), 3);
+        let lap2 = laps(&events).into_iter().find(|(lap, _, _)| *lap == 2).unwrap();
+        assert_eq!(lap2.1, [Duration::from_millis(500), Duration::from_secs(1), Duration::from_millis(500)]);
+    }
+
+    #[test]
+    fn test_reset_clears_references() {
+        let mut timer = SectorTimer::new(SectorConfig { sector_count: 2, track_length: Some(100.0) });
+        let receiver = timer.subscribe();
+        let mut driver = LapDriver::default();
+        drive(&mut driver, &mut timer, 1, 10, 10.0);
+        drive(&mut driver, &mut timer, 2, 600, 10.0);
+        drive(&mut driver, &mut timer, 3, 10, 10.0);
+        assert!(timer.personal_best().is_some());
+        assert_eq!(timer.best_sectors().len(), 2);
+        assert!(receiver.borrow().is_some());
+
+        timer.reset();
+        assert!(timer.personal_best().is_none());
+        assert_eq!(timer.track_length(), Some(100.0));
+        assert!(receiver.borrow().is_none());
+    }
+}
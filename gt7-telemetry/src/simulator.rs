@@ -0,0 +1,473 @@
+//! GT7主机模拟器
+//!
+//! 模拟PS4/PS5上GT7的遥测行为: 监听心跳端口，收到心跳后以60Hz发送加密数据包，
+//! 心跳停止超过超时时间后停止发送，用于没有主机时的离线开发和集成测试
+
+use crate::builder::PacketBuilder;
+use crate::error::{Result, GT7Error};
+use crate::packet::GT7TelemetryPacket;
+use crate::types::*;
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+use std::io::{BufRead, BufReader};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::time::{interval, MissedTickBehavior};
+
+/// 模拟器配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatorConfig {
+    /// 监听心跳的地址
+    pub bind_address: SocketAddr,
+    /// 数据包发送到心跳来源IP的哪个端口 (None表示直接回复心跳的来源端口)
+    pub reply_port: Option<u16>,
+    /// 发送频率 (Hz)
+    pub packet_rate: u32,
+    /// 心跳超时时间，超时后停止向该客户端发送
+    pub heartbeat_timeout: Duration,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([0, 0, 0, 0], crate::GT7_HEARTBEAT_PORT)),
+            reply_port: Some(crate::GT7_TELEMETRY_PORT),
+            packet_rate: 60,
+            heartbeat_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// 模拟数据包来源
+pub trait PacketSource: Send {
+    /// 生成下一帧数据包 (`dt` 为距上一帧的时间)
+    fn next_packet(&mut self, dt: Duration) -> GT7TelemetryPacket;
+}
+
+/// 脚本化圈速数据源
+///
+/// 车辆沿椭圆赛道行驶，弯道减速、直道加速，并模拟档位、转速、燃油和胎温
+#[derive(Debug, Clone)]
+pub struct ScriptedLap {
+    /// 椭圆长半轴 (米)
+    semi_major: f32,
+    /// 椭圆短半轴 (米)
+    semi_minor: f32,
+    /// 弯道最低速度 (m/s)
+    min_speed: f32,
+    /// 直道最高速度 (m/s)
+    max_speed: f32,
+    /// 每圈燃油消耗 (升)
+    fuel_per_lap: f32,
+    /// 总圈数 (0表示不限)
+    total_laps: u16,
+    /// 当前赛道参数角
+    angle: f32,
+    heading: f32,
+    current_lap: u16,
+    lap_elapsed: Duration,
+    best_lap_time: Option<u32>,
+    last_lap_time: Option<u32>,
+    fuel: f32,
+    time_of_day: Duration,
+}
+
+impl Default for ScriptedLap {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl ScriptedLap {
+    /// 油箱容量 (升)
+    const FUEL_CAPACITY: f32 = 100.0;
+
+    /// 创建脚本化圈速数据源
+    ///
+    /// # 参数
+    ///
+    /// * `total_laps` - 比赛总圈数 (0表示计时赛)
+    pub fn new(total_laps: u16) -> Self {
+        Self {
+            semi_major: 400.0,
+            semi_minor: 200.0,
+            min_speed: 25.0,
+            max_speed: 70.0,
+            fuel_per_lap: 2.5,
+            total_laps,
+            angle: PI / 2.0,
+            heading: PI,
+            current_lap: 1,
+            lap_elapsed: Duration::ZERO,
+            best_lap_time: None,
+            last_lap_time: None,
+            fuel: Self::FUEL_CAPACITY,
+            time_of_day: Duration::from_secs(14 * 3600),
+        }
+    }
+
+    /// 给定参数角处的目标速度 (m/s)
+    fn speed_at(&self, angle: f32) -> f32 {
+        self.min_speed + (self.max_speed - self.min_speed) * angle.sin().powi(2)
+    }
+
+    /// 给定参数角处的世界坐标和切线方向
+    fn point_at(&self, angle: f32) -> (Vector3, Vector3) {
+        let (sin, cos) = angle.sin_cos();
+        let point = Vector3::new(self.semi_major * cos, 0.0, self.semi_minor * sin);
+        let tangent = Vector3::new(-self.semi_major * sin, 0.0, self.semi_minor * cos);
+        (point, tangent)
+    }
+}
+
+impl PacketSource for ScriptedLap {
+    fn next_packet(&mut self, dt: Duration) -> GT7TelemetryPacket {
+        let dt_secs = dt.as_secs_f32();
+        let speed = self.speed_at(self.angle);
+
+        // 沿椭圆推进
+        let (_, tangent) = self.point_at(self.angle);
+        let step = if tangent.magnitude() > 0.0 { speed * dt_secs / tangent.magnitude() } else { 0.0 };
+        self.angle += step;
+        self.lap_elapsed += dt;
+        self.time_of_day += dt;
+        self.fuel = (self.fuel - self.fuel_per_lap * step / TAU).max(0.0);
+
+        // 跨过起点线
+        if self.angle >= TAU + PI / 2.0 {
+            self.angle -= TAU;
+            let lap_ms = self.lap_elapsed.as_millis() as u32;
+            self.last_lap_time = Some(lap_ms);
+            self.best_lap_time = Some(self.best_lap_time.map_or(lap_ms, |best| best.min(lap_ms)));
+            self.lap_elapsed = Duration::ZERO;
+            self.current_lap = self.current_lap.saturating_add(1);
+        }
+
+        let (point, tangent) = self.point_at(self.angle);
+        let new_speed = self.speed_at(self.angle);
+        let direction = Vector3::new(tangent.x / tangent.magnitude(), 0.0, tangent.z / tangent.magnitude());
+        let heading = direction.z.atan2(direction.x);
+        let mut yaw_rate = heading - self.heading;
+        if yaw_rate > PI {
+            yaw_rate -= TAU;
+        } else if yaw_rate < -PI {
+            yaw_rate += TAU;
+        }
+        let yaw_rate = if dt_secs > 0.0 { yaw_rate / dt_secs } else { 0.0 };
+        self.heading = heading;
+
+        // 根据速度变化推算踏板
+        let accel = if dt_secs > 0.0 { (new_speed - speed) / dt_secs } else { 0.0 };
+        let (throttle, brake) = if accel >= 0.0 {
+            ((0.6 + accel / 10.0).min(1.0), 0.0)
+        } else {
+            (0.0, (-accel / 15.0).min(1.0))
+        };
+
+        let speed_kmh = new_speed * 3.6;
+        let gear = ((speed_kmh / 45.0) as i8 + 1).clamp(1, 6);
+        let rpm = (7500.0 * speed_kmh / (gear as f32 * 45.0)).clamp(1000.0, 7500.0);
+        let tyre_temp = 60.0 + 30.0 * new_speed / self.max_speed;
+
+        let mut packet = PacketBuilder::new()
+            .time_of_day(self.time_of_day.as_millis() as u32)
+            .speed_kmh(speed_kmh)
+            .position(point.x, point.y, point.z)
+            .velocity(direction.x * new_speed, 0.0, direction.z * new_speed)
+            .lap(self.current_lap, self.total_laps)
+            .lap_times(self.best_lap_time, self.last_lap_time)
+            .gear(gear)
+            .rpm(rpm)
+            .rpm_alert(6500, 7500)
+            .throttle(throttle)
+            .brake(brake)
+            .fuel(self.fuel, Self::FUEL_CAPACITY)
+            .tyre_temperatures([tyre_temp + 2.0, tyre_temp + 2.0, tyre_temp, tyre_temp])
+            .car_code(3245)
+            .motion(MotionData {
+                wheel_rotation: (yaw_rate * 0.5).clamp(-PI, PI),
+                sway: new_speed * yaw_rate / 9.81,
+                heave: 0.0,
+                surge: accel / 9.81,
+            })
+            .build();
+
+        packet.car_info.position.angular_velocity = Vector3::new(0.0, yaw_rate, 0.0);
+        packet.car_info.position.rotation = Vector3::new(0.0, heading / PI, 0.0);
+        packet
+    }
+}
+
+/// 录制数据包回放源
+///
+/// 循环回放JSON Lines格式 (每行一个 [`GT7TelemetryPacket`]) 的录制文件
+#[derive(Debug, Clone)]
+pub struct RecordedPackets {
+    packets: Vec<GT7TelemetryPacket>,
+    index: usize,
+}
+
+impl RecordedPackets {
+    /// 从数据包列表创建
+    pub fn new(packets: Vec<GT7TelemetryPacket>) -> Result<Self> {
+        if packets.is_empty() {
+            return Err(GT7Error::config_error("packets", "[]", "录制数据为空"));
+        }
+        Ok(Self { packets, index: 0 })
+    }
+
+    /// 从JSON Lines文件加载
+    pub fn from_json_lines(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .map_err(|_| GT7Error::file_error(format!("打开 {}", path.display())))?;
+
+        let mut packets = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            packets.push(serde_json::from_str(&line)?);
+        }
+
+        Self::new(packets)
+    }
+}
+
+impl PacketSource for RecordedPackets {
+    fn next_packet(&mut self, _dt: Duration) -> GT7TelemetryPacket {
+        let packet = self.packets[self.index].clone();
+        self.index = (self.index + 1) % self.packets.len();
+        packet
+    }
+}
+
+/// 已发送心跳的客户端
+#[derive(Debug, Clone, Copy)]
+struct Subscriber {
+    /// 数据包发送目标
+    target: SocketAddr,
+    /// 客户端请求的数据包格式
+    variant: PacketVariant,
+    /// 最后一次收到心跳的时间
+    last_heartbeat: Instant,
+}
+
+/// GT7主机模拟器
+pub struct ConsoleSimulator {
+    config: SimulatorConfig,
+    socket: UdpSocket,
+    source: Box<dyn PacketSource>,
+    subscribers: HashMap<SocketAddr, Subscriber>,
+    packet_id: u32,
+}
+
+impl ConsoleSimulator {
+    /// 绑定心跳端口并创建模拟器
+    pub async fn bind(config: SimulatorConfig, source: Box<dyn PacketSource>) -> Result<Self> {
+        if config.packet_rate == 0 {
+            return Err(GT7Error::config_error("packet_rate", "0", "发送频率必须大于0"));
+        }
+
+        let socket = UdpSocket::bind(config.bind_address)
+            .await
+            .map_err(|e| GT7Error::network_error(config.bind_address.to_string(), e.to_string()))?;
+
+        Ok(Self {
+            config,
+            socket,
+            source,
+            subscribers: HashMap::new(),
+            packet_id: 0,
+        })
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// 运行模拟器 (直到任务被取消)
+    ///
+    /// 接收错误只记录日志 (如Windows上客户端退出后的 `ConnectionReset`)，不会结束模拟器
+    pub async fn run(mut self) -> Result<()> {
+        let period = Duration::from_secs_f64(1.0 / self.config.packet_rate as f64);
+        let mut ticker = interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut buffer = [0u8; 64];
+
+        log::info!("GT7模拟器监听 {}", self.local_addr()?);
+
+        loop {
+            tokio::select! {
+                received = self.socket.recv_from(&mut buffer) => {
+                    match received {
+                        Ok((size, from)) => self.handle_heartbeat(&buffer[..size], from),
+                        Err(e) => log::warn!("接收心跳失败: {}", e),
+                    }
+                }
+                _ = ticker.tick() => {
+                    self.expire_subscribers();
+                    if !self.subscribers.is_empty() {
+                        self.broadcast(period).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// 处理收到的心跳
+    fn handle_heartbeat(&mut self, data: &[u8], from: SocketAddr) {
+        let Some(variant) = PacketVariant::ALL.into_iter().find(|v| v.heartbeat() == data) else {
+            log::warn!("忽略来自 {} 的无效心跳 ({} 字节)", from, data.len());
+            return;
+        };
+
+        let target = match self.config.reply_port {
+            Some(port) => SocketAddr::new(from.ip(), port),
+            None => from,
+        };
+
+        let subscriber = Subscriber {
+            target,
+            variant,
+            last_heartbeat: Instant::now(),
+        };
+        if self.subscribers.insert(from, subscriber).is_none() {
+            log::info!("收到来自 {} 的心跳，开始发送 {:?} 格式数据包到 {}", from, variant, target);
+        }
+    }
+
+    /// 移除心跳超时的客户端
+    fn expire_subscribers(&mut self) {
+        let timeout = self.config.heartbeat_timeout;
+        self.subscribers.retain(|from, subscriber| {
+            let alive = subscriber.last_heartbeat.elapsed() < timeout;
+            if !alive {
+                log::info!("{} 的心跳已停止，停止发送数据包", from);
+            }
+            alive
+        });
+    }
+
+    /// 生成一帧数据并发送给所有客户端
+    async fn broadcast(&mut self, dt: Duration) {
+        self.packet_id = self.packet_id.wrapping_add(1);
+        let mut packet = self.source.next_packet(dt);
+        packet.packet_id = self.packet_id;
+
+        for subscriber in self.subscribers.values() {
+            let bytes = Self::encode_for(&packet, subscriber.variant);
+            if let Err(e) = self.socket.send_to(&bytes, subscriber.target).await {
+                log::warn!("发送数据包到 {} 失败: {}", subscriber.target, e);
+            }
+        }
+    }
+
+    /// 按客户端请求的格式编码数据包
+    fn encode_for(packet: &GT7TelemetryPacket, variant: PacketVariant) -> Vec<u8> {
+        let mut packet = packet.clone();
+        packet.variant = variant;
+        if variant != PacketVariant::A && packet.motion.is_none() {
+            packet.motion = Some(MotionData { wheel_rotation: 0.0, sway: 0.0, heave: 0.0, surge: 0.0 });
+        }
+        if variant == PacketVariant::Tilde && packet.inputs.is_none() {
+            packet.inputs = Some(InputData {
+                throttle_filtered: packet.car_info.engine.throttle,
+                brake_filtered: packet.car_info.engine.brake,
+                energy_recovery: 0.0,
+            });
+        }
+        packet.to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn start_simulator(heartbeat_timeout: Duration) -> SocketAddr {
+        let config = SimulatorConfig {
+            bind_address: "127.0.0.1:0".parse().unwrap(),
+            reply_port: None,
+            packet_rate: 60,
+            heartbeat_timeout,
+        };
+        let simulator = ConsoleSimulator::bind(config, Box::new(ScriptedLap::new(3))).await.unwrap();
+        let address = simulator.local_addr().unwrap();
+        tokio::spawn(simulator.run());
+        address
+    }
+
+    async fn recv_packet(socket: &UdpSocket, wait: Duration) -> Option<GT7TelemetryPacket> {
+        let mut buffer = [0u8; crate::GT7_MAX_PACKET_SIZE];
+        let (size, _) = tokio::time::timeout(wait, socket.recv_from(&mut buffer)).await.ok()?.ok()?;
+        Some(GT7TelemetryPacket::from_bytes(&buffer[..size]).unwrap())
+    }
+
+    #[test]
+    fn test_scripted_lap_completes_laps() {
+        let mut lap = ScriptedLap::new(2);
+        let dt = Duration::from_millis(16);
+        let mut previous = lap.next_packet(dt);
+
+        // 椭圆周长约1.9km，平均速度约48m/s，两分钟内至少完成一圈
+        for _ in 0..(120 * 60) {
+            let packet = lap.next_packet(dt);
+            assert!(packet.validate().is_ok());
+            let speed_delta = (packet.car_info.speed - previous.car_info.speed).abs();
+            assert!(speed_delta < 1.0, "速度变化应平滑");
+            previous = packet;
+        }
+
+        let race = previous.game_state.race_info.unwrap();
+        assert!(race.current_lap >= 2);
+        assert!(race.last_lap_time.is_some());
+        assert!(previous.car_info.engine.fuel_remaining < ScriptedLap::FUEL_CAPACITY);
+    }
+
+    #[tokio::test]
+    async fn test_streams_after_heartbeat() {
+        let address = start_simulator(Duration::from_secs(5)).await;
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // 未发送心跳前没有数据
+        assert!(recv_packet(&client, Duration::from_millis(100)).await.is_none());
+
+        client.send_to(b"B", address).await.unwrap();
+        let first = recv_packet(&client, Duration::from_secs(1)).await.unwrap();
+        let second = recv_packet(&client, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(first.variant, PacketVariant::B);
+        assert!(first.motion.is_some());
+        assert!(second.packet_id > first.packet_id);
+        assert!(first.is_in_race());
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_heartbeats_cease() {
+        let address = start_simulator(Duration::from_millis(200)).await;
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        client.send_to(b"A", address).await.unwrap();
+        assert!(recv_packet(&client, Duration::from_secs(1)).await.is_some());
+
+        // 等待心跳超时后清空已发送的数据
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let mut buffer = [0u8; crate::GT7_MAX_PACKET_SIZE];
+        while client.try_recv_from(&mut buffer).is_ok() {}
+
+        assert!(recv_packet(&client, Duration::from_millis(200)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ignores_invalid_heartbeat() {
+        let address = start_simulator(Duration::from_secs(5)).await;
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        client.send_to(b"hello", address).await.unwrap();
+        assert!(recv_packet(&client, Duration::from_millis(200)).await.is_none());
+    }
+}
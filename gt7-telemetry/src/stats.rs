@@ -0,0 +1,238 @@
+//! 连接统计
+//!
+//! 基于数据包序号 (`packet_id`) 和到达时间统计每个连接的丢包、乱序、重复、
+//! 到达间隔抖动和心跳响应延迟，用于诊断不稳定的Wi-Fi主机
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// 平滑系数 (参考RFC 3550的抖动估计，每个样本占1/16)
+const SMOOTHING_GAIN: f64 = 1.0 / 16.0;
+
+/// 记录已到达序号的窗口大小
+const SEQUENCE_WINDOW: u32 = 64;
+
+/// 序号向后跳变超过该值时视为主机重新开始计数
+const SEQUENCE_RESET_THRESHOLD: u32 = 1000;
+
+/// 单个连接的统计快照
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    /// 心跳目标地址
+    pub address: SocketAddr,
+    /// 是否处于连接状态
+    pub is_connected: bool,
+    /// 接收到的有效数据包数
+    pub packets_received: u64,
+    /// 接收频率 (包/秒，基于平滑后的到达间隔)
+    pub packets_per_second: f64,
+    /// 按序号推断的丢包数
+    pub dropped_packets: u64,
+    /// 晚于更大序号到达的数据包数
+    pub out_of_order_packets: u64,
+    /// 重复到达的数据包数
+    pub duplicate_packets: u64,
+    /// 到达间隔抖动 (平滑后的间隔偏差)
+    pub jitter: Duration,
+    /// 最近一次从发送心跳到收到首个数据包的延迟
+    pub heartbeat_latency: Option<Duration>,
+    /// 解析或校验失败次数
+    pub decode_failures: u64,
+    /// 最后一个数据包的序号
+    pub last_packet_id: Option<u32>,
+    /// 距最后一个数据包的时间
+    pub since_last_packet: Option<Duration>,
+}
+
+impl ConnectionStats {
+    /// 丢包率 (0.0-1.0)
+    pub fn loss_ratio(&self) -> f64 {
+        let expected = self.packets_received.saturating_sub(self.duplicate_packets) + self.dropped_packets;
+        if expected == 0 {
+            0.0
+        } else {
+            self.dropped_packets as f64 / expected as f64
+        }
+    }
+}
+
+/// 序号与到达时间统计器
+///
+/// 由连接的接收任务和心跳任务更新，客户端读取时生成 [`ConnectionStats`]
+#[derive(Debug, Default)]
+pub(crate) struct StatsTracker {
+    /// 目前最大的序号
+    highest_id: Option<u32>,
+    /// 最大序号之前的到达记录，第i位表示序号 `highest_id - i` 已到达
+    seen: u64,
+    dropped: u64,
+    out_of_order: u64,
+    duplicates: u64,
+    last_packet_id: Option<u32>,
+    last_arrival: Option<Instant>,
+    /// 平滑后的到达间隔 (秒)
+    mean_interval: f64,
+    /// 平滑后的间隔偏差 (秒)
+    jitter: f64,
+    /// 等待首个数据包的心跳发送时间
+    pending_heartbeat: Option<Instant>,
+    heartbeat_latency: Option<Duration>,
+}
+
+impl StatsTracker {
+    /// 记录一个有效数据包
+    pub(crate) fn record_packet(&mut self, packet_id: u32, now: Instant) {
+        self.record_sequence(packet_id);
+        self.last_packet_id = Some(packet_id);
+
+        if let Some(last) = self.last_arrival.replace(now) {
+            let interval = now.duration_since(last).as_secs_f64();
+            if self.mean_interval == 0.0 {
+                self.mean_interval = interval;
+            } else {
+                let deviation = (interval - self.mean_interval).abs();
+                self.jitter += (deviation - self.jitter) * SMOOTHING_GAIN;
+                self.mean_interval += (interval - self.mean_interval) * SMOOTHING_GAIN;
+            }
+        }
+
+        if let Some(sent) = self.pending_heartbeat.take() {
+            self.heartbeat_latency = Some(now.saturating_duration_since(sent));
+        }
+    }
+
+    /// 记录在等待数据时发送的心跳，用于测量响应延迟
+    pub(crate) fn record_heartbeat(&mut self, now: Instant) {
+        self.pending_heartbeat.get_or_insert(now);
+    }
+
+    /// 连接中断后重新开始计算序号和间隔，中断期间不计为丢包
+    pub(crate) fn reset_stream(&mut self) {
+        self.highest_id = None;
+        self.seen = 0;
+        self.last_arrival = None;
+    }
+
+    fn record_sequence(&mut self, packet_id: u32) {
+        let Some(highest) = self.highest_id else {
+            self.highest_id = Some(packet_id);
+            self.seen = 1;
+            return;
+        };
+
+        let ahead = packet_id.wrapping_sub(highest);
+        let behind = highest.wrapping_sub(packet_id);
+        if ahead == 0 {
+            self.duplicates += 1;
+        } else if ahead < u32::MAX / 2 {
+            // 新的最大序号，中间缺失的先计为丢包
+            self.dropped += u64::from(ahead - 1);
+            self.seen = if ahead < SEQUENCE_WINDOW { (self.seen << ahead) | 1 } else { 1 };
+            self.highest_id = Some(packet_id);
+        } else if behind < SEQUENCE_WINDOW {
+            let bit = 1u64 << behind;
+            if self.seen & bit != 0 {
+                self.duplicates += 1;
+            } else {
+                // 迟到的数据包此前已计为丢包
+                self.seen |= bit;
+                self.out_of_order += 1;
+                self.dropped = self.dropped.saturating_sub(1);
+            }
+        } else if behind > SEQUENCE_RESET_THRESHOLD {
+            self.highest_id = Some(packet_id);
+            self.seen = 1;
+        } else {
+            // 超出记录窗口，无法判断是否重复
+            self.out_of_order += 1;
+        }
+    }
+
+    /// 生成统计快照 (连接相关字段由调用方填写)
+    pub(crate) fn snapshot(
+        &self,
+        address: SocketAddr,
+        is_connected: bool,
+        packets_received: u64,
+        decode_failures: u64,
+    ) -> ConnectionStats {
+        ConnectionStats {
+            address,
+            is_connected,
+            packets_received,
+            packets_per_second: if self.mean_interval > 0.0 { 1.0 / self.mean_interval } else { 0.0 },
+            dropped_packets: self.dropped,
+            out_of_order_packets: self.out_of_order,
+            duplicate_packets: self.duplicates,
+            jitter: Duration::from_secs_f64(self.jitter),
+            heartbeat_latency: self.heartbeat_latency,
+            decode_failures,
+            last_packet_id: self.last_packet_id,
+            since_last_packet: self.last_arrival.map(|last| last.elapsed()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(ids: &[u32]) -> ConnectionStats {
+        let mut tracker = StatsTracker::default();
+        let start = Instant::now();
+        for (i, &id) in ids.iter().enumerate() {
+            tracker.record_packet(id, start + Duration::from_millis(i as u64 * 16));
+        }
+        tracker.snapshot("127.0.0.1:33739".parse().unwrap(), true, ids.len() as u64, 0)
+    }
+
+    #[test]
+    fn test_in_order_sequence() {
+        let stats = track(&[10, 11, 12, 13]);
+        assert_eq!(stats.dropped_packets, 0);
+        assert_eq!(stats.out_of_order_packets, 0);
+        assert_eq!(stats.duplicate_packets, 0);
+        assert_eq!(stats.last_packet_id, Some(13));
+        assert!((stats.packets_per_second - 62.5).abs() < 1e-6);
+        assert_eq!(stats.jitter, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_loss_reorder_and_duplicates() {
+        // 缺3、5，3迟到，6重复
+        let stats = track(&[1, 2, 4, 6, 3, 6, 7]);
+        assert_eq!(stats.dropped_packets, 1);
+        assert_eq!(stats.out_of_order_packets, 1);
+        assert_eq!(stats.duplicate_packets, 1);
+        assert!((stats.loss_ratio() - 1.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sequence_wraps_and_resets() {
+        let stats = track(&[u32::MAX - 1, u32::MAX, 0, 1]);
+        assert_eq!(stats.dropped_packets, 0);
+
+        // 主机重新计数不算乱序
+        let stats = track(&[50_000, 50_001, 3, 4]);
+        assert_eq!(stats.dropped_packets, 0);
+        assert_eq!(stats.out_of_order_packets, 0);
+    }
+
+    #[test]
+    fn test_jitter_and_heartbeat_latency() {
+        let mut tracker = StatsTracker::default();
+        let start = Instant::now();
+        tracker.record_heartbeat(start);
+        tracker.record_heartbeat(start + Duration::from_millis(100));
+        let mut at = start + Duration::from_millis(30);
+        for i in 0..32 {
+            tracker.record_packet(i, at);
+            at += Duration::from_millis(if i % 2 == 0 { 10 } else { 20 });
+        }
+        let stats = tracker.snapshot("127.0.0.1:33739".parse().unwrap(), true, 32, 0);
+        assert_eq!(stats.heartbeat_latency, Some(Duration::from_millis(30)));
+        assert!(stats.jitter > Duration::from_millis(2));
+        assert!((stats.packets_per_second - 1000.0 / 15.0).abs() < 10.0);
+    }
+}
@@ -0,0 +1,600 @@
+//! 赛道地图重建
+//!
+//! 从一圈或多圈的世界坐标 (`Position::world`，y轴向上) 重建赛道中心线、
+//! 行驶线宽度包络和海拔曲线，并把实时位置投影为沿赛道的距离和横向偏移。
+//!
+//! 中心线是各圈行驶线在同一位置的平均，宽度包络是观测到的行驶线相对中心线的最大偏移，
+//! 因此圈数越多、走线越多样，包络越接近真实赛道宽度
+
+use crate::error::{Result, GT7Error};
+use crate::laps::Lap;
+use crate::types::Vector3;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 默认中心线采样间距 (米)
+pub const DEFAULT_SPACING: f32 = 2.0;
+
+/// 首尾距离小于该值时视为闭合赛道 (米)
+const CLOSED_LOOP_GAP: f32 = 50.0;
+
+/// 就近投影时前后搜索的距离 (米)
+const NEAR_SEARCH_WINDOW: f32 = 100.0;
+
+/// 中心线上的一个点
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackPoint {
+    /// 距起点的距离 (米)
+    pub distance: f32,
+    /// 世界坐标 (y为海拔)
+    pub position: Vector3,
+    /// 左侧包络宽度 (米)
+    pub left_width: f32,
+    /// 右侧包络宽度 (米)
+    pub right_width: f32,
+}
+
+/// 实时位置在赛道上的投影
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackProjection {
+    /// 沿赛道的距离 (米)
+    pub distance: f32,
+    /// 相对中心线的横向偏移 (米，在x-z平面上把行驶方向从x轴转向z轴90°的一侧为正)
+    pub lateral_offset: f32,
+    /// 投影点的赛道海拔
+    pub elevation: f32,
+    /// 是否在宽度包络内
+    pub within_envelope: bool,
+}
+
+/// 赛道地图
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackMap {
+    /// 赛道/布局标识 (由调用方指定，GT7数据包不包含赛道ID)
+    pub track_id: String,
+    /// 赛道长度 (米)
+    pub length: f32,
+    /// 是否是闭合赛道
+    pub closed: bool,
+    /// 中心线
+    pub points: Vec<TrackPoint>,
+    /// 参与重建的圈数
+    pub laps: u32,
+}
+
+impl TrackMap {
+    /// 把位置投影到赛道上 (搜索整条中心线)
+    pub fn project(&self, position: Vector3) -> Option<TrackProjection> {
+        self.locate(position, 0..self.segment_count()).map(|located| located.projection)
+    }
+
+    /// 在 `near_distance` 附近投影，适合逐帧跟踪；附近找不到时搜索整条中心线
+    pub fn project_near(&self, position: Vector3, near_distance: f32) -> Option<TrackProjection> {
+        self.locate_near(position, near_distance).map(|located| located.projection)
+    }
+
+    /// 指定距离处的海拔 (线性插值)
+    pub fn elevation_at(&self, distance: f32) -> Option<f32> {
+        let (a, b, t) = self.interpolation(distance)?;
+        Some(a.position.y + (b.position.y - a.position.y) * t)
+    }
+
+    /// 指定距离处的中心线位置 (线性插值)
+    pub fn position_at(&self, distance: f32) -> Option<Vector3> {
+        let (a, b, t) = self.interpolation(distance)?;
+        Some(lerp(a.position, b.position, t))
+    }
+
+    /// 海拔曲线: (距离, 海拔)
+    pub fn elevation_profile(&self) -> Vec<(f32, f32)> {
+        self.points.iter().map(|point| (point.distance, point.position.y)).collect()
+    }
+
+    fn segment_count(&self) -> usize {
+        match (self.points.len(), self.closed) {
+            (0 | 1, _) => 0,
+            (n, true) => n,
+            (n, false) => n - 1,
+        }
+    }
+
+    /// 点 `index` 处的单位法线 (x, z)，指向横向偏移的正方向
+    fn normal(&self, index: usize) -> (f32, f32) {
+        let len = self.points.len();
+        let previous = if index > 0 || self.closed { (index + len - 1) % len } else { index };
+        let next = if index + 1 < len || self.closed { (index + 1) % len } else { index };
+        let a = self.points[previous].position;
+        let b = self.points[next].position;
+        let (dx, dz) = (b.x - a.x, b.z - a.z);
+        let length = (dx * dx + dz * dz).sqrt();
+        if length <= f32::EPSILON {
+            return (0.0, 0.0);
+        }
+        (-dz / length, dx / length)
+    }
+
+    fn average_spacing(&self) -> f32 {
+        (self.length / self.segment_count().max(1) as f32).max(0.1)
+    }
+
+    /// 线段 `index` 的两端及起点距离
+    fn segment(&self, index: usize) -> (&TrackPoint, &TrackPoint, f32, f32) {
+        let a = &self.points[index];
+        let b = &self.points[(index + 1) % self.points.len()];
+        let end = if index + 1 == self.points.len() { self.length } else { b.distance };
+        (a, b, a.distance, end)
+    }
+
+    /// 包含指定距离的线段
+    fn segment_at(&self, distance: f32) -> usize {
+        let distance = self.normalize(distance);
+        self.points.partition_point(|point| point.distance <= distance).saturating_sub(1).min(self.segment_count() - 1)
+    }
+
+    fn normalize(&self, distance: f32) -> f32 {
+        if self.closed && self.length > 0.0 {
+            distance.rem_euclid(self.length)
+        } else {
+            distance.clamp(0.0, self.length)
+        }
+    }
+
+    fn interpolation(&self, distance: f32) -> Option<(&TrackPoint, &TrackPoint, f32)> {
+        if self.segment_count() == 0 {
+            return self.points.first().map(|point| (point, point, 0.0));
+        }
+        let distance = self.normalize(distance);
+        let (a, b, start, end) = self.segment(self.segment_at(distance));
+        let t = if end > start { ((distance - start) / (end - start)).clamp(0.0, 1.0) } else { 0.0 };
+        Some((a, b, t))
+    }
+
+    fn locate_near(&self, position: Vector3, near_distance: f32) -> Option<Located> {
+        let count = self.segment_count();
+        if count == 0 {
+            return None;
+        }
+        let window = (NEAR_SEARCH_WINDOW / self.average_spacing()).ceil() as usize;
+        if window * 2 + 1 >= count {
+            return self.locate(position, 0..count);
+        }
+        let center = self.segment_at(near_distance);
+        let segments: Vec<usize> = if self.closed {
+            (0..=window * 2).map(|i| (center + count - window + i) % count).collect()
+        } else {
+            (center.saturating_sub(window)..(center + window + 1).min(count)).collect()
+        };
+        let located = self.locate(position, segments.iter().copied())?;
+        // 最近点在搜索窗口边缘时说明位置已经离开窗口
+        let at_edge = (located.segment == segments[0] && segments[0] != 0 && located.t == 0.0)
+            || (located.segment == segments[segments.len() - 1] && located.segment + 1 != count && located.t == 1.0);
+        if at_edge {
+            self.locate(position, 0..count)
+        } else {
+            Some(located)
+        }
+    }
+
+    fn locate(&self, position: Vector3, segments: impl IntoIterator<Item = usize>) -> Option<Located> {
+        let mut best: Option<(f32, Located)> = None;
+        for index in segments {
+            let (a, b, start, end) = self.segment(index);
+            let (t, lateral) = project_onto(a.position, b.position, position);
+            let on_line = lerp(a.position, b.position, t);
+            let dx = position.x - on_line.x;
+            let dz = position.z - on_line.z;
+            let distance_sq = dx * dx + dz * dz;
+            if best.as_ref().is_some_and(|(best_sq, _)| *best_sq <= distance_sq) {
+                continue;
+            }
+            let left_width = a.left_width + (b.left_width - a.left_width) * t;
+            let right_width = a.right_width + (b.right_width - a.right_width) * t;
+            best = Some((
+                distance_sq,
+                Located {
+                    segment: index,
+                    t,
+                    projection: TrackProjection {
+                        distance: self.normalize(start + (end - start) * t),
+                        lateral_offset: lateral,
+                        elevation: on_line.y,
+                        within_envelope: lateral <= left_width && -lateral <= right_width,
+                    },
+                },
+            ));
+        }
+        best.map(|(_, located)| located)
+    }
+
+    /// 离位置最近的中心线点
+    fn nearest_point(&self, located: &Located) -> usize {
+        (located.segment + usize::from(located.t >= 0.5)) % self.points.len()
+    }
+}
+
+/// 投影所在的线段
+#[derive(Debug, Clone, Copy)]
+struct Located {
+    segment: usize,
+    t: f32,
+    projection: TrackProjection,
+}
+
+/// 赛道地图构建器
+#[derive(Debug, Clone)]
+pub struct TrackMapBuilder {
+    track_id: String,
+    spacing: f32,
+    laps: Vec<Vec<Vector3>>,
+}
+
+impl TrackMapBuilder {
+    /// 创建构建器
+    ///
+    /// # 参数
+    ///
+    /// * `track_id` - 赛道/布局标识
+    /// * `spacing` - 中心线采样间距 (米)
+    pub fn new(track_id: impl Into<String>, spacing: f32) -> Self {
+        Self {
+            track_id: track_id.into(),
+            spacing: spacing.max(0.1),
+            laps: Vec::new(),
+        }
+    }
+
+    /// 加入一圈 (建议只使用有效的计时圈)
+    pub fn add_lap(&mut self, lap: &Lap) -> &mut Self {
+        self.add_positions(lap.samples.iter().map(|sample| sample.packet.car_info.position.world))
+    }
+
+    /// 加入一圈的位置序列
+    pub fn add_positions(&mut self, positions: impl IntoIterator<Item = Vector3>) -> &mut Self {
+        let positions: Vec<Vector3> = positions.into_iter().collect();
+        if positions.len() >= 2 {
+            self.laps.push(positions);
+        }
+        self
+    }
+
+    /// 重建赛道地图
+    ///
+    /// 以第一圈为参考线，把所有圈的位置按沿参考线的距离分组取平均得到中心线，
+    /// 再把所有位置投影到新的中心线上得到宽度包络
+    pub fn build(&self) -> Result<TrackMap> {
+        let Some(reference) = self.laps.first() else {
+            return Err(GT7Error::config_error("laps", "0", "至少需要一圈位置数据"));
+        };
+        let closed = horizontal_distance(reference[0], reference[reference.len() - 1]) < CLOSED_LOOP_GAP;
+        let reference = resample(reference, self.spacing, closed);
+        if reference.len() < 2 {
+            return Err(GT7Error::config_error("laps", "1", "参考圈太短"));
+        }
+        let reference_map = polyline_map(&self.track_id, &reference, closed, self.laps.len() as u32);
+
+        // 每个参考点的横向偏移和海拔: 先按圈平均，再在圈之间平均，避免慢圈采样多而占比大。
+        // 只沿法线方向移动参考点，不改变沿赛道方向的位置
+        let mut totals = vec![(0.0f32, 0.0f32, 0u32); reference.len()];
+        for lap in &self.laps {
+            let mut sums = vec![(0.0f32, 0.0f32, 0u32); reference.len()];
+            let mut near = 0.0;
+            for &position in lap {
+                let Some(located) = reference_map.locate_near(position, near) else {
+                    continue;
+                };
+                near = located.projection.distance;
+                let (lateral, elevation, count) = &mut sums[reference_map.nearest_point(&located)];
+                *lateral += located.projection.lateral_offset;
+                *elevation += position.y;
+                *count += 1;
+            }
+            for (total, (lateral, elevation, count)) in totals.iter_mut().zip(sums) {
+                if count > 0 {
+                    total.0 += lateral / count as f32;
+                    total.1 += elevation / count as f32;
+                    total.2 += 1;
+                }
+            }
+        }
+        let centreline: Vec<Vector3> = (0..reference.len())
+            .map(|index| {
+                let (lateral, elevation, laps) = totals[index];
+                if laps == 0 {
+                    return reference[index];
+                }
+                let (nx, nz) = reference_map.normal(index);
+                let lateral = lateral / laps as f32;
+                Vector3::new(
+                    reference[index].x + nx * lateral,
+                    elevation / laps as f32,
+                    reference[index].z + nz * lateral,
+                )
+            })
+            .collect();
+        let mut map = polyline_map(&self.track_id, &centreline, closed, self.laps.len() as u32);
+
+        // 宽度包络
+        let mut extents = vec![(0.0f32, 0.0f32); map.points.len()];
+        for lap in &self.laps {
+            let mut near = 0.0;
+            for &position in lap {
+                let Some(located) = map.locate_near(position, near) else {
+                    continue;
+                };
+                let projection = located.projection;
+                near = projection.distance;
+                let (left, right) = &mut extents[map.nearest_point(&located)];
+                *left = left.max(projection.lateral_offset);
+                *right = right.max(-projection.lateral_offset);
+            }
+        }
+        for (point, (left, right)) in map.points.iter_mut().zip(extents) {
+            point.left_width = left;
+            point.right_width = right;
+        }
+
+        log::info!(
+            "重建赛道地图 {}: 长度 {:.0}m，{} 个点，{} 圈",
+            map.track_id,
+            map.length,
+            map.points.len(),
+            map.laps
+        );
+        Ok(map)
+    }
+}
+
+/// 按赛道标识保存和加载地图 (每条赛道一个JSON文件)
+///
+/// 文件名是百分号编码的赛道标识 (ASCII字母、数字、`-`、`_` 以外的字节写成 `%XX`)，不同标识不会共用文件。
+#[derive(Debug, Clone)]
+pub struct TrackMapStore {
+    dir: PathBuf,
+}
+
+impl TrackMapStore {
+    /// 使用指定目录
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 保存地图，返回文件路径
+    pub fn save(&self, map: &TrackMap) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| GT7Error::file_error(format!("创建赛道地图目录 {}: {}", self.dir.display(), e)))?;
+        let path = self.path(&map.track_id);
+        let json = serde_json::to_vec(map)?;
+        std::fs::write(&path, json)
+            .map_err(|e| GT7Error::file_error(format!("写入赛道地图 {}: {}", path.display(), e)))?;
+        Ok(path)
+    }
+
+    /// 加载地图，不存在时返回 `None`
+    ///
+    /// 文件中的赛道标识与 `track_id` 不一致时 (例如大小写不敏感的文件系统) 返回错误
+    pub fn load(&self, track_id: &str) -> Result<Option<TrackMap>> {
+        let path = self.path(track_id);
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(GT7Error::file_error(format!("读取赛道地图 {}: {}", path.display(), e))),
+        };
+        let map: TrackMap = serde_json::from_slice(&data)?;
+        if map.track_id != track_id {
+            return Err(GT7Error::file_error(format!(
+                "赛道地图 {} 属于 {}，不是 {}",
+                path.display(),
+                map.track_id,
+                track_id
+            )));
+        }
+        Ok(Some(map))
+    }
+
+    /// 已保存的赛道标识
+    pub fn track_ids(&self) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(GT7Error::file_error(format!("读取赛道地图目录 {}: {}", self.dir.display(), e))),
+        };
+        let mut ids = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                match self.load_path(&path) {
+                    Ok(map) => ids.push(map.track_id),
+                    Err(e) => log::warn!("跳过无法读取的赛道地图 {}: {}", path.display(), e),
+                }
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn load_path(&self, path: &Path) -> Result<TrackMap> {
+        let data = std::fs::read(path).map_err(|e| GT7Error::file_error(format!("读取赛道地图 {}: {}", path.display(), e)))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    fn path(&self, track_id: &str) -> PathBuf {
+        let mut name = String::with_capacity(track_id.len());
+        for byte in track_id.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                name.push(byte as char);
+            } else {
+                name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+/// 由折线创建地图 (宽度为0)
+fn polyline_map(track_id: &str, line: &[Vector3], closed: bool, laps: u32) -> TrackMap {
+    let mut distance = 0.0;
+    let mut points = Vec::with_capacity(line.len());
+    for (index, &position) in line.iter().enumerate() {
+        if index > 0 {
+            distance += horizontal_distance(line[index - 1], position);
+        }
+        points.push(TrackPoint { distance, position, left_width: 0.0, right_width: 0.0 });
+    }
+    if closed {
+        distance += horizontal_distance(line[line.len() - 1], line[0]);
+    }
+    TrackMap {
+        track_id: track_id.to_string(),
+        length: distance,
+        closed,
+        points,
+        laps,
+    }
+}
+
+/// 按固定间距重新采样折线 (闭合赛道不重复起点)
+fn resample(line: &[Vector3], spacing: f32, closed: bool) -> Vec<Vector3> {
+    let mut resampled = vec![line[0]];
+    let mut carried = 0.0;
+    let closing = closed.then(|| line[0]);
+    let segments = line.windows(2).map(|pair| (pair[0], pair[1])).chain(closing.map(|first| (line[line.len() - 1], first)));
+    for (a, b) in segments {
+        let length = horizontal_distance(a, b);
+        let mut offset = spacing - carried;
+        while offset < length {
+            resampled.push(lerp(a, b, offset / length));
+            offset += spacing;
+        }
+        carried = length - (offset - spacing);
+    }
+    if closed {
+        if resampled.len() > 1 && horizontal_distance(resampled[resampled.len() - 1], line[0]) < spacing / 2.0 {
+            resampled.pop();
+        }
+    } else if carried > 0.0 {
+        // 开放赛道保留终点
+        resampled.push(line[line.len() - 1]);
+    }
+    resampled
+}
+
+/// 点在线段ab上的投影 (x-z平面): (参数t, 横向偏移)
+fn project_onto(a: Vector3, b: Vector3, p: Vector3) -> (f32, f32) {
+    let (dx, dz) = (b.x - a.x, b.z - a.z);
+    let length_sq = dx * dx + dz * dz;
+    if length_sq <= f32::EPSILON {
+        return (0.0, 0.0);
+    }
+    let (px, pz) = (p.x - a.x, p.z - a.z);
+    let t = ((px * dx + pz * dz) / length_sq).clamp(0.0, 1.0);
+    // 行驶方向从x轴转向z轴90°为正
+    let lateral = (dx * pz - dz * px) / length_sq.sqrt();
+    (t, lateral)
+}
+
+fn lerp(a: Vector3, b: Vector3, t: f32) -> Vector3 {
+    Vector3::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t, a.z + (b.z - a.z) * t)
+}
+
+fn horizontal_distance(a: Vector3, b: Vector3) -> f32 {
+    ((b.x - a.x).powi(2) + (b.z - a.z).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    /// 半径 `radius` 的圆形赛道，海拔随角度起伏
+    fn circle(radius: f32, points: usize) -> Vec<Vector3> {
+        (0..points)
+            .map(|i| {
+                let angle = TAU * i as f32 / points as f32;
+                Vector3::new(radius * angle.cos(), 5.0 * angle.sin(), radius * angle.sin())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_circle() {
+        let mut builder = TrackMapBuilder::new("circle", DEFAULT_SPACING);
+        builder.add_positions(circle(100.0, 600)).add_positions(circle(104.0, 600));
+        let map = builder.build().unwrap();
+        assert!(map.closed);
+        assert_eq!(map.laps, 2);
+        // 平均行驶线半径102m
+        let expected = TAU * 102.0;
+        assert!((map.length - expected).abs() < 5.0, "{} vs {}", map.length, expected);
+        for point in &map.points {
+            let radius = point.position.x.hypot(point.position.z);
+            assert!((radius - 102.0).abs() < 0.5, "{}", radius);
+            assert!((point.left_width + point.right_width - 4.0).abs() < 0.5);
+        }
+
+        // 从+x向+z绕行，圆心一侧为正
+        let projection = map.project(Vector3::new(0.0, 0.0, 101.0)).unwrap();
+        assert!((projection.distance - map.length / 4.0).abs() < 5.0, "{:?}", projection);
+        assert!((projection.lateral_offset - 1.0).abs() < 0.1, "{:?}", projection);
+        assert!(projection.within_envelope);
+        assert!((projection.elevation - 5.0).abs() < 0.2);
+        assert!(!map.project(Vector3::new(0.0, 0.0, 90.0)).unwrap().within_envelope);
+
+        let near = map.project_near(Vector3::new(0.0, 0.0, 101.0), map.length / 4.0 + 10.0).unwrap();
+        assert!((near.distance - projection.distance).abs() < 0.01);
+        // 跨过起点
+        let wrapped = map.project_near(Vector3::new(102.0, 0.0, -1.0), 3.0).unwrap();
+        assert!(wrapped.distance > map.length - 5.0, "{:?}", wrapped);
+
+        let profile = map.elevation_profile();
+        let highest = profile.iter().cloned().fold(f32::MIN, |max, (_, y)| max.max(y));
+        assert!((highest - 5.0).abs() < 0.1);
+        assert!((map.elevation_at(map.length * 0.75).unwrap() + 5.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_open_course() {
+        let line: Vec<_> = (0..=500).map(|i| Vector3::new(i as f32, i as f32 * 0.1, 0.0)).collect();
+        let map = TrackMapBuilder::new("hillclimb", 5.0).add_positions(line).build().unwrap();
+        assert!(!map.closed);
+        assert!((map.length - 500.0).abs() < 5.0);
+        let projection = map.project(Vector3::new(250.0, 0.0, 3.0)).unwrap();
+        assert!((projection.distance - 250.0).abs() < 0.01);
+        // 沿+x行驶时，+z一侧为正
+        assert!((projection.lateral_offset - 3.0).abs() < 0.01);
+        assert!((map.elevation_at(250.0).unwrap() - 25.0).abs() < 0.1);
+        assert!(TrackMapBuilder::new("empty", 2.0).build().is_err());
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("gt7-track-maps-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = TrackMapStore::new(&dir);
+        assert!(store.load("Suzuka Circuit / East").unwrap().is_none());
+
+        let map = TrackMapBuilder::new("Suzuka Circuit / East", 4.0).add_positions(circle(50.0, 200)).build().unwrap();
+        store.save(&map).unwrap();
+        assert_eq!(store.load("Suzuka Circuit / East").unwrap().as_ref(), Some(&map));
+        assert_eq!(store.track_ids().unwrap(), ["Suzuka Circuit / East"]);
+
+        // 清理字符后相同的标识各自使用独立的文件
+        for id in ["A/B", "A B", "A_B"] {
+            let mut other = map.clone();
+            other.track_id = id.to_string();
+            store.save(&other).unwrap();
+        }
+        for id in ["A/B", "A B", "A_B"] {
+            assert_eq!(store.load(id).unwrap().unwrap().track_id, id);
+        }
+
+        // 文件中的标识不符时报错
+        std::fs::copy(store.path("A/B"), store.path("C")).unwrap();
+        assert!(store.load("C").is_err());
+        std::fs::remove_file(store.path("C")).unwrap();
+
+        // 损坏的文件不出现在列表中
+        std::fs::write(dir.join("broken.json"), b"{").unwrap();
+        assert_eq!(store.track_ids().unwrap(), ["A B", "A/B", "A_B", "Suzuka Circuit / East"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -2,6 +2,7 @@
 //! 
 //! 参考gt7telemetry Python库的数据结构
 
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 
 /// 3D向量
@@ -31,8 +32,10 @@ pub struct Position {
     pub velocity: Vector3,
     /// 角速度 (rad/s)
     pub angular_velocity: Vector3,
-    /// 朝向角度 (弧度)
+    /// 车身旋转 (pitch/yaw/roll，范围 -1.0~1.0)
     pub rotation: Vector3,
+    /// 相对正北的朝向 (1.0为正北，0.0为正南)
+    pub orientation: f32,
 }
 
 /// 车辆轮胎信息
@@ -51,11 +54,11 @@ pub struct TireInfo {
 /// 单个轮胎数据
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct TireData {
-    /// 轮胎温度 (摄氏度)
+    /// 胎面温度 (摄氏度)
     pub temperature: f32,
-    /// 轮胎磨损 (0.0-1.0)
+    /// 轮胎磨损 (0.0-1.0，GT7数据包未提供，默认为0)
     pub wear: f32,
-    /// 悬挂行程 (米)
+    /// 悬挂高度 (米)
     pub suspension_travel: f32,
     /// 轮速 (rad/s)
     pub wheel_speed: f32,
@@ -68,21 +71,35 @@ pub struct TireData {
 pub struct EngineInfo {
     /// 发动机转速 (RPM)
     pub rpm: f32,
-    /// 最大转速 (RPM)
+    /// 换挡提示最高转速 (RPM，即红线)
     pub max_rpm: f32,
+    /// 换挡提示最低转速 (RPM)
+    pub min_alert_rpm: f32,
     /// 油门开度 (0.0-1.0)
     pub throttle: f32,
     /// 刹车力度 (0.0-1.0)
     pub brake: f32,
-    /// 离合器状态 (0.0-1.0)
+    /// 离合器踏板 (0.0-1.0)
     pub clutch: f32,
+    /// 离合器接合度 (0.0-1.0)
+    pub clutch_engagement: f32,
+    /// 离合器后转速 (RPM)
+    pub rpm_after_clutch: f32,
     /// 当前档位 (0=倒档, 1-8=前进档)
     pub gear: i8,
-    /// 建议档位
+    /// 建议档位 (15表示无建议)
     pub suggested_gear: i8,
-    /// 燃油剩余 (升)
+    /// 涡轮增压 (bar，无增压时为负值)
+    pub boost: f32,
+    /// 机油压力 (bar)
+    pub oil_pressure: f32,
+    /// 机油温度 (摄氏度)
+    pub oil_temperature: f32,
+    /// 水温 (摄氏度)
+    pub water_temperature: f32,
+    /// 燃油剩余 (升，电动车为百分比)
     pub fuel_remaining: f32,
-    /// 燃油消耗率 (升/圈)
+    /// 燃油消耗率 (升/圈，GT7数据包未提供，默认为0)
     pub fuel_consumption: f32,
     /// 燃油箱容量 (升)
     pub fuel_capacity: f32,
@@ -97,20 +114,102 @@ pub struct RaceInfo {
     pub current_lap: u16,
     /// 总圈数
     pub total_laps: u16,
-    /// 当前位置
+    /// 起跑位置 (比赛开始后为0)
     pub position: u8,
-    /// 总参赛者数
+    /// 总参赛者数 (比赛开始后为0)
     pub total_participants: u8,
     /// 最快圈速 (毫秒)
     pub best_lap_time: Option<u32>,
     /// 上一圈时间 (毫秒)
     pub last_lap_time: Option<u32>,
-    /// 当前圈时间 (毫秒)
+    /// 当前圈时间 (毫秒，GT7数据包未提供，默认为0)
     pub current_lap_time: u32,
-    /// 赛道完成进度 (0.0-1.0)
+    /// 赛道完成进度 (0.0-1.0，GT7数据包未提供，默认为0)
     pub track_progress: f32,
 }
 
+/// 变速箱信息
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Transmission {
+    /// 理论极速对应的传动比
+    pub top_speed_ratio: f32,
+    /// 1-8档传动比 (未使用的档位为0)
+    pub gear_ratios: [f32; 8],
+}
+
+/// 附加运动数据 (心跳"B"及以上的数据包提供)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MotionData {
+    /// 方向盘转角 (弧度)
+    pub wheel_rotation: f32,
+    /// 横向加速度 (sway)
+    pub sway: f32,
+    /// 垂直加速度 (heave)
+    pub heave: f32,
+    /// 纵向加速度 (surge)
+    pub surge: f32,
+}
+
+/// 附加输入数据 (心跳"~"的数据包提供)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InputData {
+    /// 经过辅助系统过滤后的油门 (0.0-1.0)
+    pub throttle_filtered: f32,
+    /// 经过辅助系统过滤后的刹车 (0.0-1.0)
+    pub brake_filtered: f32,
+    /// 能量回收
+    pub energy_recovery: f32,
+}
+
+/// 数据包格式 (由发送的心跳内容决定)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum PacketVariant {
+    /// 心跳"A": 296字节基础数据包
+    #[default]
+    A,
+    /// 心跳"B": 316字节，附加方向盘转角和sway/heave/surge
+    B,
+    /// 心跳"~": 344字节，在"B"基础上附加过滤后输入和能量回收
+    Tilde,
+}
+
+impl PacketVariant {
+    /// 所有数据包格式
+    pub const ALL: [PacketVariant; 3] = [Self::A, Self::B, Self::Tilde];
+
+    /// 请求该格式时发送的心跳内容
+    pub fn heartbeat(&self) -> &'static [u8] {
+        match self {
+            Self::A => b"A",
+            Self::B => b"B",
+            Self::Tilde => b"~",
+        }
+    }
+
+    /// 该格式的数据包大小 (字节)
+    pub fn packet_size(&self) -> usize {
+        match self {
+            Self::A => 296,
+            Self::B => 316,
+            Self::Tilde => 344,
+        }
+    }
+
+    /// 派生Salsa20 IV时使用的异或掩码
+    pub fn iv_xor(&self) -> u32 {
+        match self {
+            Self::A => 0xDEADBEAF,
+            Self::B => 0xDEADBEEF,
+            Self::Tilde => 0x55FABB4F,
+        }
+    }
+
+    /// 根据数据包大小识别格式
+    pub fn from_packet_size(size: usize) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.packet_size() == size)
+    }
+}
+
 /// 游戏状态枚举 (参考gt7telemetry)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameStateType {
@@ -144,6 +243,117 @@ impl From<u8> for GameStateType {
     }
 }
 
+impl GameStateType {
+    /// 根据GT7状态标志位推断游戏状态
+    pub fn from_flags(flags: &CarFlags) -> Self {
+        if flags.loading_or_processing {
+            Self::Loading
+        } else if flags.paused {
+            Self::Paused
+        } else if flags.car_on_track {
+            Self::InRace
+        } else {
+            Self::InMenu
+        }
+    }
+}
+
+/// GT7状态标志位 (数据包0x8E处的u16)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarFlags {
+    /// 车辆在赛道上
+    pub car_on_track: bool,
+    /// 游戏暂停
+    pub paused: bool,
+    /// 加载或处理中
+    pub loading_or_processing: bool,
+    /// 已挂档
+    pub in_gear: bool,
+    /// 车辆装有涡轮
+    pub has_turbo: bool,
+    /// 转速限制器闪烁提示
+    pub rev_limiter_alert: bool,
+    /// 手刹拉起
+    pub handbrake: bool,
+    /// 车灯开启
+    pub lights: bool,
+    /// 远光灯
+    pub high_beam: bool,
+    /// 近光灯
+    pub low_beam: bool,
+    /// ASM (车身稳定) 介入
+    pub asm_active: bool,
+    /// TCS (牵引力控制) 介入
+    pub tcs_active: bool,
+}
+
+impl CarFlags {
+    /// 标志位: 车辆在赛道上
+    pub const CAR_ON_TRACK: u16 = 1 << 0;
+    /// 标志位: 游戏暂停
+    pub const PAUSED: u16 = 1 << 1;
+    /// 标志位: 加载或处理中
+    pub const LOADING_OR_PROCESSING: u16 = 1 << 2;
+    /// 标志位: 已挂档
+    pub const IN_GEAR: u16 = 1 << 3;
+    /// 标志位: 车辆装有涡轮
+    pub const HAS_TURBO: u16 = 1 << 4;
+    /// 标志位: 转速限制器闪烁提示
+    pub const REV_LIMITER_ALERT: u16 = 1 << 5;
+    /// 标志位: 手刹拉起
+    pub const HANDBRAKE: u16 = 1 << 6;
+    /// 标志位: 车灯开启
+    pub const LIGHTS: u16 = 1 << 7;
+    /// 标志位: 远光灯
+    pub const HIGH_BEAM: u16 = 1 << 8;
+    /// 标志位: 近光灯
+    pub const LOW_BEAM: u16 = 1 << 9;
+    /// 标志位: ASM介入
+    pub const ASM_ACTIVE: u16 = 1 << 10;
+    /// 标志位: TCS介入
+    pub const TCS_ACTIVE: u16 = 1 << 11;
+
+    /// 转换回原始标志位
+    pub fn bits(&self) -> u16 {
+        [
+            (self.car_on_track, Self::CAR_ON_TRACK),
+            (self.paused, Self::PAUSED),
+            (self.loading_or_processing, Self::LOADING_OR_PROCESSING),
+            (self.in_gear, Self::IN_GEAR),
+            (self.has_turbo, Self::HAS_TURBO),
+            (self.rev_limiter_alert, Self::REV_LIMITER_ALERT),
+            (self.handbrake, Self::HANDBRAKE),
+            (self.lights, Self::LIGHTS),
+            (self.high_beam, Self::HIGH_BEAM),
+            (self.low_beam, Self::LOW_BEAM),
+            (self.asm_active, Self::ASM_ACTIVE),
+            (self.tcs_active, Self::TCS_ACTIVE),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |bits, (_, bit)| bits | bit)
+    }
+}
+
+impl From<u16> for CarFlags {
+    fn from(value: u16) -> Self {
+        Self {
+            car_on_track: value & Self::CAR_ON_TRACK != 0,
+            paused: value & Self::PAUSED != 0,
+            loading_or_processing: value & Self::LOADING_OR_PROCESSING != 0,
+            in_gear: value & Self::IN_GEAR != 0,
+            has_turbo: value & Self::HAS_TURBO != 0,
+            rev_limiter_alert: value & Self::REV_LIMITER_ALERT != 0,
+            handbrake: value & Self::HANDBRAKE != 0,
+            lights: value & Self::LIGHTS != 0,
+            high_beam: value & Self::HIGH_BEAM != 0,
+            low_beam: value & Self::LOW_BEAM != 0,
+            asm_active: value & Self::ASM_ACTIVE != 0,
+            tcs_active: value & Self::TCS_ACTIVE != 0,
+        }
+    }
+}
+
 /// 赛道信息
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TrackData {
@@ -222,25 +432,66 @@ pub struct CarConfiguration {
 pub struct TelemetryConfig {
     /// PS4/PS5 IP地址
     pub console_ip: String,
-    /// 遥测端口
+    /// 主机心跳端口
     pub port: u16,
     /// 连接超时时间 (秒)
     pub timeout: u64,
     /// 心跳间隔 (毫秒)
     pub heartbeat_interval: u64,
-    /// 是否启用数据记录
+    /// 请求的数据包格式
+    #[serde(default)]
+    pub packet_variant: PacketVariant,
+    /// 抖动缓冲 (None表示按到达顺序直接转发)
+    #[serde(default)]
+    pub jitter_buffer: Option<JitterBufferConfig>,
+    /// 局域网地址之外允许连接的网段 (如VPN或跨VLAN的 "10.8.0.0/24")
+    #[serde(default)]
+    pub allowed_networks: Vec<IpNet>,
+    /// 是否允许IPv6地址
+    #[serde(default)]
+    pub allow_ipv6: bool,
+    /// 所有连接共用一个绑定在该端口的套接字 (通常为33740，主机把数据包发送到心跳来源IP的该端口)。
+    /// None表示每个连接使用独立的随机端口套接字
+    #[serde(default)]
+    pub shared_socket_port: Option<u16>,
+    /// 是否录制收到的原始数据报
     pub enable_logging: bool,
-    /// 日志文件路径
+    /// 录制文件目录 (默认 "gt7-recordings")，每次启动客户端新建一个会话文件
     pub log_file_path: Option<String>,
 }
 
+/// 抖动缓冲配置
+/// 
+/// 按数据包序号去重、丢弃过期数据包，并最多暂存一定数量的数据包等待乱序到达的数据包
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JitterBufferConfig {
+    /// 最多暂存的数据包数量 (0表示不等待，只去重和丢弃过期数据包)
+    pub reorder_window: usize,
+    /// 数据包最长暂存时间 (毫秒)，超时后跳过缺失的数据包
+    pub max_delay_ms: u64,
+}
+
+impl Default for JitterBufferConfig {
+    fn default() -> Self {
+        Self {
+            reorder_window: 3,
+            max_delay_ms: 50,
+        }
+    }
+}
+
 impl Default for TelemetryConfig {
     fn default() -> Self {
         Self {
             console_ip: "192.168.1.30".to_string(),
-            port: crate::GT7_TELEMETRY_PORT,
+            port: crate::GT7_HEARTBEAT_PORT,
             timeout: 5,
             heartbeat_interval: 100,
+            packet_variant: PacketVariant::default(),
+            jitter_buffer: None,
+            allowed_networks: Vec::new(),
+            allow_ipv6: false,
+            shared_socket_port: None,
             enable_logging: false,
             log_file_path: None,
         }
@@ -0,0 +1,594 @@
+//! 轮胎温度与磨损模型
+//!
+//! [`TyreModel`] 按轮胎配方的工作温度区间把每个轮胎分为过冷、正常、过热，
+//! 按圈统计温度分布，用每圈结束时的磨损拟合磨损速率并预测抓地力低于阈值的圈数，
+//! 同时比较前后、左右和单个轮胎的温度，给出不平衡提示 (例如前轮过热通常意味着推头)。
+//!
+//! GT7数据包不包含磨损 (`TireData::wear` 始终为0)，磨损可以通过 [`TyreModel::observe_wear`]
+//! 手动提供 (例如进站时读到的数值)，没有磨损数据时只做温度分析。
+//! 温度区间是按配方整理的近似值，可以通过 [`TyreConfig::window`] 覆盖
+
+use crate::laps::{Lap, LapTracker};
+use crate::packet::GT7TelemetryPacket;
+use crate::types::{TireData, TireInfo};
+
+/// 磨损下降超过该值视为换胎
+const TYRE_CHANGE_THRESHOLD: f32 = 0.05;
+
+/// 轮胎位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wheel {
+    /// 前左
+    FrontLeft,
+    /// 前右
+    FrontRight,
+    /// 后左
+    RearLeft,
+    /// 后右
+    RearRight,
+}
+
+impl Wheel {
+    /// 所有轮胎，顺序与数组下标一致
+    pub const ALL: [Wheel; 4] = [Wheel::FrontLeft, Wheel::FrontRight, Wheel::RearLeft, Wheel::RearRight];
+
+    /// 是否是前轮
+    pub fn is_front(self) -> bool {
+        matches!(self, Wheel::FrontLeft | Wheel::FrontRight)
+    }
+
+    /// 是否是左侧
+    pub fn is_left(self) -> bool {
+        matches!(self, Wheel::FrontLeft | Wheel::RearLeft)
+    }
+
+    /// 取出该位置的轮胎数据
+    pub fn data(self, tires: &TireInfo) -> &TireData {
+        match self {
+            Wheel::FrontLeft => &tires.front_left,
+            Wheel::FrontRight => &tires.front_right,
+            Wheel::RearLeft => &tires.rear_left,
+            Wheel::RearRight => &tires.rear_right,
+        }
+    }
+}
+
+/// 轮胎配方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TyreCompound {
+    /// 舒适胎 (CH/CM/CS)
+    Comfort,
+    /// 运动胎 (SH/SM/SS)
+    Sports,
+    /// 赛车硬胎
+    RacingHard,
+    /// 赛车中性胎
+    #[default]
+    RacingMedium,
+    /// 赛车软胎
+    RacingSoft,
+    /// 半雨胎
+    Intermediate,
+    /// 全雨胎
+    Wet,
+}
+
+impl TyreCompound {
+    /// 配方的工作温度区间 (近似值)
+    pub fn window(self) -> TemperatureWindow {
+        let (cold_below, overheating_above) = match self {
+            TyreCompound::Comfort => (40.0, 75.0),
+            TyreCompound::Sports => (50.0, 85.0),
+            TyreCompound::RacingHard => (70.0, 100.0),
+            TyreCompound::RacingMedium => (65.0, 95.0),
+            TyreCompound::RacingSoft => (60.0, 90.0),
+            TyreCompound::Intermediate => (40.0, 75.0),
+            TyreCompound::Wet => (30.0, 65.0),
+        };
+        TemperatureWindow { cold_below, overheating_above }
+    }
+}
+
+/// 轮胎工作温度区间 (摄氏度)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureWindow {
+    /// 低于该温度为过冷
+    pub cold_below: f32,
+    /// 高于该温度为过热
+    pub overheating_above: f32,
+}
+
+impl TemperatureWindow {
+    /// 温度所处的状态
+    pub fn classify(&self, temperature: f32) -> TemperatureState {
+        if temperature < self.cold_below {
+            TemperatureState::Cold
+        } else if temperature > self.overheating_above {
+            TemperatureState::Overheating
+        } else {
+            TemperatureState::Optimal
+        }
+    }
+}
+
+/// 轮胎温度状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TemperatureState {
+    /// 过冷
+    Cold,
+    /// 工作温度
+    Optimal,
+    /// 过热
+    Overheating,
+}
+
+/// 轮胎模型配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct TyreConfig {
+    /// 轮胎配方
+    pub compound: TyreCompound,
+    /// 自定义工作温度区间，为 `None` 时使用配方的默认区间
+    pub window: Option<TemperatureWindow>,
+    /// 抓地力阈值 (剩余胎面比例 `1 - wear`)，低于该值时认为需要换胎
+    pub min_grip: f32,
+    /// 温度差超过该值时提示不平衡 (摄氏度)
+    pub imbalance_threshold: f32,
+    /// 实时温度的平滑系数 (0-1，越小越平滑)
+    pub smoothing: f32,
+}
+
+impl Default for TyreConfig {
+    fn default() -> Self {
+        Self {
+            compound: TyreCompound::default(),
+            window: None,
+            min_grip: 0.6,
+            imbalance_threshold: 8.0,
+            smoothing: 0.1,
+        }
+    }
+}
+
+impl TyreConfig {
+    /// 生效的工作温度区间
+    pub fn window(&self) -> TemperatureWindow {
+        self.window.unwrap_or_else(|| self.compound.window())
+    }
+}
+
+/// 操控倾向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handling {
+    /// 推头
+    Understeer,
+    /// 甩尾
+    Oversteer,
+}
+
+/// 温度不平衡的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImbalanceKind {
+    /// 前轮比后轮热
+    FrontHot,
+    /// 后轮比前轮热
+    RearHot,
+    /// 左侧比右侧热 (通常来自赛道弯道方向)
+    LeftHot,
+    /// 右侧比左侧热 (通常来自赛道弯道方向)
+    RightHot,
+    /// 单个轮胎比其余轮胎热
+    WheelHot(Wheel),
+}
+
+/// 温度不平衡
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TyreImbalance {
+    /// 类型
+    pub kind: ImbalanceKind,
+    /// 温度差 (摄氏度)
+    pub difference: f32,
+}
+
+impl TyreImbalance {
+    /// 不平衡通常对应的操控倾向
+    pub fn handling(&self) -> Option<Handling> {
+        match self.kind {
+            ImbalanceKind::FrontHot => Some(Handling::Understeer),
+            ImbalanceKind::RearHot => Some(Handling::Oversteer),
+            ImbalanceKind::WheelHot(wheel) if wheel.is_front() => Some(Handling::Understeer),
+            ImbalanceKind::WheelHot(_) => Some(Handling::Oversteer),
+            ImbalanceKind::LeftHot | ImbalanceKind::RightHot => None,
+        }
+    }
+}
+
+/// 单个轮胎在一圈内的统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelLapSummary {
+    /// 平均温度
+    pub average_temperature: f32,
+    /// 最低温度
+    pub min_temperature: f32,
+    /// 最高温度
+    pub max_temperature: f32,
+    /// 过冷的采样比例
+    pub cold: f32,
+    /// 工作温度的采样比例
+    pub optimal: f32,
+    /// 过热的采样比例
+    pub overheating: f32,
+    /// 圈结束时的磨损 (没有磨损数据时为 `None`)
+    pub wear: Option<f32>,
+}
+
+/// 一圈的轮胎统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct TyreLapSummary {
+    /// 圈数
+    pub lap: u16,
+    /// 各轮胎统计，顺序同 [`Wheel::ALL`]
+    pub wheels: [WheelLapSummary; 4],
+    /// 按圈平均温度判断的不平衡
+    pub imbalances: Vec<TyreImbalance>,
+}
+
+/// 单个轮胎的实时状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelStatus {
+    /// 平滑后的温度
+    pub temperature: f32,
+    /// 温度状态
+    pub state: TemperatureState,
+    /// 最近的磨损 (没有磨损数据时为 `None`)
+    pub wear: Option<f32>,
+    /// 拟合的磨损速率 (每圈)
+    pub wear_per_lap: Option<f32>,
+    /// 预计抓地力低于阈值的圈数
+    pub grip_limit_lap: Option<u16>,
+}
+
+/// 轮胎实时状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct TyreStatus {
+    /// 当前圈数
+    pub lap: u16,
+    /// 各轮胎状态，顺序同 [`Wheel::ALL`]
+    pub wheels: [WheelStatus; 4],
+    /// 按平滑温度判断的不平衡
+    pub imbalances: Vec<TyreImbalance>,
+}
+
+/// 单个轮胎的会话统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelSummary {
+    /// 各圈平均温度的平均值
+    pub average_temperature: f32,
+    /// 会话最高温度
+    pub max_temperature: f32,
+    /// 工作温度的采样比例 (各圈平均)
+    pub optimal: f32,
+    /// 过热的采样比例 (各圈平均)
+    pub overheating: f32,
+    /// 当前这套轮胎的磨损速率 (每圈)
+    pub wear_per_lap: Option<f32>,
+    /// 预计抓地力低于阈值的圈数
+    pub grip_limit_lap: Option<u16>,
+}
+
+/// 会话轮胎统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct TyreSummary {
+    /// 统计的圈数
+    pub laps: usize,
+    /// 各轮胎统计，顺序同 [`Wheel::ALL`]
+    pub wheels: [WheelSummary; 4],
+    /// 按会话平均温度判断的不平衡
+    pub imbalances: Vec<TyreImbalance>,
+}
+
+/// 轮胎模型
+#[derive(Debug)]
+pub struct TyreModel {
+    config: TyreConfig,
+    tracker: LapTracker,
+    laps: Vec<TyreLapSummary>,
+    /// 当前这套轮胎每个轮胎的 (圈数, 圈结束时磨损)
+    wear_points: [Vec<(u16, f32)>; 4],
+    /// 手动提供的当前圈磨损
+    observed_wear: Option<[f32; 4]>,
+    live: Option<TyreStatus>,
+}
+
+impl TyreModel {
+    /// 创建轮胎模型
+    pub fn new(config: TyreConfig) -> Self {
+        Self {
+            config,
+            tracker: LapTracker::new(),
+            laps: Vec::new(),
+            wear_points: Default::default(),
+            observed_wear: None,
+            live: None,
+        }
+    }
+
+    /// 实时状态
+    pub fn status(&self) -> Option<&TyreStatus> {
+        self.live.as_ref()
+    }
+
+    /// 各圈统计
+    pub fn laps(&self) -> &[TyreLapSummary] {
+        &self.laps
+    }
+
+    /// 手动提供当前磨损 (0-1，顺序同 [`Wheel::ALL`])，在当前圈结束时计入拟合
+    pub fn observe_wear(&mut self, wear: [f32; 4]) {
+        self.observed_wear = Some(wear);
+        if let Some(live) = self.live.as_mut() {
+            for (status, wear) in live.wheels.iter_mut().zip(wear) {
+                status.wear = Some(wear);
+            }
+        }
+    }
+
+    /// 处理一个数据包，返回刚结束的一圈的统计
+    pub fn push(&mut self, packet: &GT7TelemetryPacket) -> Option<TyreLapSummary> {
+        let completed = self.tracker.push(packet).map(|lap| self.complete_lap(&lap));
+        if let Some(lap) = self.tracker.current_lap() {
+            self.update_live(lap, packet);
+        }
+        completed
+    }
+
+    /// 数据流结束，统计进行中的圈
+    pub fn finish(&mut self) -> Option<TyreLapSummary> {
+        self.tracker.finish().map(|lap| self.complete_lap(&lap))
+    }
+
+    /// 会话统计
+    pub fn summary(&self) -> Option<TyreSummary> {
+        if self.laps.is_empty() {
+            return None;
+        }
+        let count = self.laps.len() as f32;
+        let wheels = std::array::from_fn(|index| {
+            let laps = self.laps.iter().map(|lap| &lap.wheels[index]);
+            let (wear_per_lap, grip_limit_lap) = self.wear_trend(index);
+            WheelSummary {
+                average_temperature: laps.clone().map(|wheel| wheel.average_temperature).sum::<f32>() / count,
+                max_temperature: laps.clone().map(|wheel| wheel.max_temperature).fold(f32::MIN, f32::max),
+                optimal: laps.clone().map(|wheel| wheel.optimal).sum::<f32>() / count,
+                overheating: laps.map(|wheel| wheel.overheating).sum::<f32>() / count,
+                wear_per_lap,
+                grip_limit_lap,
+            }
+        });
+        let temperatures = wheels.map(|wheel: WheelSummary| wheel.average_temperature);
+        Some(TyreSummary {
+            laps: self.laps.len(),
+            wheels,
+            imbalances: imbalances(temperatures, self.config.imbalance_threshold),
+        })
+    }
+
+    fn complete_lap(&mut self, lap: &Lap) -> TyreLapSummary {
+        let window = self.config.window();
+        let packet_wear = lap.samples.last().map(|sample| Wheel::ALL.map(|wheel| wheel.data(&sample.packet.car_info.tires).wear));
+        let wear = self
+            .observed_wear
+            .take()
+            .or(packet_wear.filter(|wear| wear.iter().any(|&wear| wear > 0.0)));
+
+        let wheels: [WheelLapSummary; 4] = std::array::from_fn(|index| {
+            let temperatures = || lap.samples.iter().map(|sample| Wheel::ALL[index].data(&sample.packet.car_info.tires).temperature);
+            let count = lap.samples.len().max(1) as f32;
+            let share = |state| temperatures().filter(|&t| window.classify(t) == state).count() as f32 / count;
+            WheelLapSummary {
+                average_temperature: temperatures().sum::<f32>() / count,
+                min_temperature: temperatures().fold(f32::MAX, f32::min),
+                max_temperature: temperatures().fold(f32::MIN, f32::max),
+                cold: share(TemperatureState::Cold),
+                optimal: share(TemperatureState::Optimal),
+                overheating: share(TemperatureState::Overheating),
+                wear: wear.map(|wear| wear[index]),
+            }
+        });
+
+        if let Some(wear) = wear {
+            for (points, wear) in self.wear_points.iter_mut().zip(wear) {
+                if points.last().is_some_and(|&(_, last)| wear < last - TYRE_CHANGE_THRESHOLD) {
+                    log::info!("第 {} 圈磨损下降，视为换胎", lap.number);
+                    points.clear();
+                }
+                points.push((lap.number, wear));
+            }
+        }
+
+        let summary = TyreLapSummary {
+            lap: lap.number,
+            imbalances: imbalances(wheels.map(|wheel| wheel.average_temperature), self.config.imbalance_threshold),
+            wheels,
+        };
+        for imbalance in &summary.imbalances {
+            log::debug!("第 {} 圈轮胎温度不平衡: {:?}", lap.number, imbalance);
+        }
+        self.laps.push(summary.clone());
+        summary
+    }
+
+    fn update_live(&mut self, lap: u16, packet: &GT7TelemetryPacket) {
+        let window = self.config.window();
+        let alpha = self.config.smoothing.clamp(0.01, 1.0);
+        let tires = &packet.car_info.tires;
+        let previous = self.live.as_ref().map(|live| live.wheels);
+        let observed = self.observed_wear;
+        let trends: [(Option<f32>, Option<u16>); 4] = std::array::from_fn(|index| self.wear_trend(index));
+
+        let wheels = std::array::from_fn(|index| {
+            let data = Wheel::ALL[index].data(tires);
+            let temperature = match previous {
+                Some(previous) => previous[index].temperature + (data.temperature - previous[index].temperature) * alpha,
+                None => data.temperature,
+            };
+            let wear = observed
+                .map(|wear| wear[index])
+                .or((data.wear > 0.0).then_some(data.wear))
+                .or(previous.and_then(|previous| previous[index].wear));
+            WheelStatus {
+                temperature,
+                state: window.classify(temperature),
+                wear,
+                wear_per_lap: trends[index].0,
+                grip_limit_lap: trends[index].1,
+            }
+        });
+        let temperatures = wheels.map(|wheel: WheelStatus| wheel.temperature);
+        self.live = Some(TyreStatus {
+            lap,
+            wheels,
+            imbalances: imbalances(temperatures, self.config.imbalance_threshold),
+        });
+    }
+
+    /// 当前这套轮胎的磨损速率和抓地力低于阈值的预计圈数 (最小二乘拟合)
+    fn wear_trend(&self, index: usize) -> (Option<f32>, Option<u16>) {
+        let points = &self.wear_points[index];
+        if points.len() < 2 {
+            return (None, None);
+        }
+        let n = points.len() as f32;
+        let mean_x = points.iter().map(|&(lap, _)| f32::from(lap)).sum::<f32>() / n;
+        let mean_y = points.iter().map(|&(_, wear)| wear).sum::<f32>() / n;
+        let covariance: f32 = points.iter().map(|&(lap, wear)| (f32::from(lap) - mean_x) * (wear - mean_y)).sum();
+        let variance: f32 = points.iter().map(|&(lap, _)| (f32::from(lap) - mean_x).powi(2)).sum();
+        if variance <= 0.0 {
+            return (None, None);
+        }
+        let slope = covariance / variance;
+        if slope <= 0.0 {
+            return (Some(slope), None);
+        }
+        let intercept = mean_y - slope * mean_x;
+        let limit = (1.0 - self.config.min_grip - intercept) / slope;
+        // 容忍浮点误差，避免正好落在整圈时多算一圈
+        let lap = (limit - 1e-3).ceil().clamp(0.0, f32::from(u16::MAX)) as u16;
+        (Some(slope), Some(lap))
+    }
+}
+
+/// 按温度 (顺序同 [`Wheel::ALL`]) 判断不平衡
+fn imbalances(temperatures: [f32; 4], threshold: f32) -> Vec<TyreImbalance> {
+    let mut found = Vec::new();
+    let average = |filter: fn(Wheel) -> bool| {
+        let selected: Vec<f32> = Wheel::ALL.iter().zip(temperatures).filter(|(wheel, _)| filter(**wheel)).map(|(_, t)| t).collect();
+        selected.iter().sum::<f32>() / selected.len() as f32
+    };
+    let mut compare = |difference: f32, positive: ImbalanceKind, negative: ImbalanceKind| {
+        if difference > threshold {
+            found.push(TyreImbalance { kind: positive, difference });
+        } else if -difference > threshold {
+            found.push(TyreImbalance { kind: negative, difference: -difference });
+        }
+    };
+    compare(
+        average(Wheel::is_front) - average(|wheel| !wheel.is_front()),
+        ImbalanceKind::FrontHot,
+        ImbalanceKind::RearHot,
+    );
+    compare(
+        average(Wheel::is_left) - average(|wheel| !wheel.is_left()),
+        ImbalanceKind::LeftHot,
+        ImbalanceKind::RightHot,
+    );
+    for (index, wheel) in Wheel::ALL.into_iter().enumerate() {
+        let others = (temperatures.iter().sum::<f32>() - temperatures[index]) / 3.0;
+        let difference = temperatures[index] - others;
+        if difference > threshold {
+            found.push(TyreImbalance { kind: ImbalanceKind::WheelHot(wheel), difference });
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::LapDriver;
+
+    /// 以固定温度和磨损跑一圈 (60帧)，返回期间完成的一圈
+    fn drive(driver: &mut LapDriver, model: &mut TyreModel, lap: u16, temperatures: [f32; 4], wear: f32) -> Option<TyreLapSummary> {
+        let mut completed = None;
+        for mut packet in driver.lap(lap, 0, 60, |builder, _| builder.tyre_temperatures(temperatures)) {
+            let tires = &mut packet.car_info.tires;
+            for tire in [&mut tires.front_left, &mut tires.front_right, &mut tires.rear_left, &mut tires.rear_right] {
+                tire.wear = wear;
+            }
+            completed = completed.or(model.push(&packet));
+        }
+        completed
+    }
+
+    #[test]
+    fn test_temperature_windows_and_imbalance() {
+        let mut model = TyreModel::new(TyreConfig::default());
+        let mut driver = LapDriver::default();
+        drive(&mut driver, &mut model, 1, [50.0, 50.0, 45.0, 45.0], 0.0);
+        // 前左过热
+        let out_lap = drive(&mut driver, &mut model, 2, [110.0, 92.0, 85.0, 92.0], 0.0).unwrap();
+        assert_eq!(out_lap.wheels[0].cold, 1.0);
+        assert!(out_lap.imbalances.is_empty());
+        let lap = model.finish().unwrap();
+        assert_eq!(lap.lap, 2);
+        assert_eq!(lap.wheels[0].overheating, 1.0);
+        assert_eq!(lap.wheels[1].optimal, 1.0);
+        assert_eq!(lap.wheels[0].wear, None);
+
+        let kinds: Vec<_> = lap.imbalances.iter().map(|imbalance| imbalance.kind).collect();
+        assert_eq!(kinds, [ImbalanceKind::FrontHot, ImbalanceKind::WheelHot(Wheel::FrontLeft)]);
+        assert!(lap.imbalances.iter().all(|imbalance| imbalance.handling() == Some(Handling::Understeer)));
+
+        let live = model.status().unwrap();
+        assert_eq!(live.wheels[0].state, TemperatureState::Overheating);
+        assert!(live.wheels[0].temperature > 100.0);
+
+        let summary = model.summary().unwrap();
+        assert_eq!(summary.laps, 2);
+        assert_eq!(summary.wheels[0].max_temperature, 110.0);
+        assert!((summary.wheels[1].optimal - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_wear_trend_and_tyre_change() {
+        let mut model = TyreModel::new(TyreConfig::default());
+        let mut driver = LapDriver::default();
+        for lap in 1..=4u16 {
+            drive(&mut driver, &mut model, lap, [80.0; 4], 0.05 * f32::from(lap));
+        }
+        // 第1-3圈结束时磨损0.05/0.10/0.15，剩余胎面低于0.6 (磨损0.4) 在第8圈
+        let status = model.status().unwrap().wheels[0];
+        assert!((status.wear_per_lap.unwrap() - 0.05).abs() < 1e-4, "{:?}", status);
+        assert_eq!(status.grip_limit_lap, Some(8));
+
+        // 换胎后重新拟合
+        drive(&mut driver, &mut model, 5, [80.0; 4], 0.02);
+        drive(&mut driver, &mut model, 6, [80.0; 4], 0.04);
+        let summary = model.summary().unwrap();
+        assert_eq!(summary.wheels[3].wear_per_lap, None);
+        drive(&mut driver, &mut model, 7, [80.0; 4], 0.06);
+        let summary = model.summary().unwrap();
+        assert!((summary.wheels[3].wear_per_lap.unwrap() - 0.02).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_observed_wear() {
+        let mut model = TyreModel::new(TyreConfig::default());
+        let mut driver = LapDriver::default();
+        drive(&mut driver, &mut model, 1, [80.0; 4], 0.0);
+        model.observe_wear([0.1, 0.1, 0.05, 0.05]);
+        assert_eq!(model.status().unwrap().wheels[0].wear, Some(0.1));
+        let lap = drive(&mut driver, &mut model, 2, [80.0; 4], 0.0).unwrap();
+        assert_eq!(lap.wheels[2].wear, Some(0.05));
+        assert_eq!(model.status().unwrap().wheels[2].wear, Some(0.05));
+    }
+}